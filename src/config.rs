@@ -0,0 +1,509 @@
+use crate::domain::money::{Currency, Money, Rounding, MINOR_PER_MAJOR};
+use crate::domain::pricing::{Holiday, PriceChange, PriceRule};
+use crate::domain::reservation::minutes_of_day;
+use crate::domain::ticket::{TicketCategory, TicketDiscount};
+use crate::repo::repo_file::FlushPolicy;
+use crate::validators::context::ValidationLimits;
+use crate::validators::reservation_validator::time_validator;
+use serde::Deserialize;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+pub const DEFAULT_CONFIG_FILE: &str = "cinema.toml";
+
+// Every key can also be set through an environment variable named
+// CINEMA_<SECTION>_<KEY>, e.g. CINEMA_DATA_DIR or CINEMA_LOYALTY_ACCRUAL_PERCENT,
+// which takes precedence over the file. CINEMA_CONFIG picks another file, and
+// other CINEMA_ variables are ignored.
+const ENV_PREFIX: &str = "CINEMA_";
+const ENV_CONFIG_FILE: &str = "CINEMA_CONFIG";
+
+/// Everything read from `cinema.toml`. Missing keys keep their defaults.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub data: DataConfig,
+    pub validation: ValidationLimits,
+    pub loyalty: LoyaltyConfig,
+    pub holds: HoldConfig,
+    pub schedule: ScheduleConfig,
+    pub movies: MovieConfig,
+    pub pricing: PricingConfig,
+    pub tickets: TicketConfig,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageFormat {
+    Csv,
+    Sqlite,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FlushMode {
+    WriteThrough,
+    Batched,
+    Manual,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DataConfig {
+    pub dir: String,
+    pub format: StorageFormat,
+    pub movies_file: String,
+    pub cards_file: String,
+    pub reservations_file: String,
+    pub screenings_file: String,
+    pub halls_file: String,
+    pub holds_file: String,
+    pub promotions_file: String,
+    pub gift_cards_file: String,
+    /// every payment and refund made with a gift card
+    pub gift_card_ledger_file: String,
+    /// used instead of the CSV files when the format is sqlite
+    pub database_file: String,
+    /// refuse to start on a corrupt CSV file instead of skipping the bad lines
+    pub strict: bool,
+    pub flush: FlushMode,
+    /// only used by the batched flush mode
+    pub flush_ops: usize,
+    pub flush_interval_secs: u64,
+}
+
+impl Default for DataConfig {
+    fn default() -> Self {
+        DataConfig {
+            dir: ".".to_string(),
+            format: StorageFormat::Csv,
+            movies_file: "movies.csv".to_string(),
+            cards_file: "cards.csv".to_string(),
+            reservations_file: "reservations.csv".to_string(),
+            screenings_file: "screenings.csv".to_string(),
+            halls_file: "halls.csv".to_string(),
+            holds_file: "holds.csv".to_string(),
+            promotions_file: "promotions.csv".to_string(),
+            gift_cards_file: "gift_cards.csv".to_string(),
+            gift_card_ledger_file: "gift_card_ledger.csv".to_string(),
+            database_file: "cinema.db".to_string(),
+            strict: true,
+            flush: FlushMode::WriteThrough,
+            flush_ops: 10,
+            flush_interval_secs: 30,
+        }
+    }
+}
+
+impl DataConfig {
+    /// A data file's name, relative to the data directory.
+    pub fn path(&self, filename: &str) -> String {
+        Path::new(&self.dir).join(filename).display().to_string()
+    }
+
+    pub fn flush_policy(&self) -> FlushPolicy {
+        match self.flush {
+            FlushMode::WriteThrough => FlushPolicy::WriteThrough,
+            FlushMode::Batched => FlushPolicy::Batched {
+                ops: self.flush_ops,
+                interval: Duration::from_secs(self.flush_interval_secs),
+            },
+            FlushMode::Manual => FlushPolicy::Manual,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoyaltyConfig {
+    /// share of what a reservation is paid in money credited to the card as
+    /// points, rounded down to whole lei
+    pub accrual_percent: u32,
+    /// what a point pays for when a reservation is paid with points
+    pub point_value: Money,
+}
+
+impl Default for LoyaltyConfig {
+    fn default() -> Self {
+        LoyaltyConfig {
+            accrual_percent: 10,
+            point_value: Money::new(MINOR_PER_MAJOR, Currency::Ron),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HoldConfig {
+    /// how long seats stay held before they are released on their own
+    pub timeout_secs: u64,
+}
+
+impl Default for HoldConfig {
+    fn default() -> Self {
+        HoldConfig { timeout_secs: 600 }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScheduleConfig {
+    /// minutes a hall stays empty after a screening ends, for cleaning
+    pub cleaning_minutes: u32,
+    /// "hh:mm"; the planner gives these hours to the most reserved movies
+    pub prime_time_start: String,
+    pub prime_time_end: String,
+    /// planned screenings start on a multiple of this many minutes
+    pub start_step_minutes: u32,
+}
+
+impl Default for ScheduleConfig {
+    fn default() -> Self {
+        ScheduleConfig {
+            cleaning_minutes: 15,
+            prime_time_start: "18:00".to_string(),
+            prime_time_end: "22:00".to_string(),
+            start_step_minutes: 15,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PricingConfig {
+    /// share of the ticket price given back when a reservation is cancelled,
+    /// rounded down to the ban
+    pub refund_percent: u32,
+    /// days the rules count as public holidays
+    pub holidays: Vec<Holiday>,
+    /// how the percentages of the rules are rounded to the ban
+    pub rounding: Rounding,
+    /// applied in order to the movie's price, each to the result of the ones
+    /// before it
+    pub rules: Vec<PriceRule>,
+}
+
+impl PricingConfig {
+    fn check(&self) -> Result<(), String> {
+        if self.refund_percent > 100 {
+            return Err("pricing.refund_percent can be at most 100".into());
+        }
+
+        for rule in &self.rules {
+            if rule.name.trim().is_empty() {
+                return Err("pricing.rules: every rule needs a name".into());
+            }
+
+            for (key, time) in [("from", &rule.from), ("until", &rule.until)] {
+                if let Some(Err(err)) = time.as_deref().map(time_validator) {
+                    return Err(format!("pricing.rules.{}.{}: {}", rule.name, key, err));
+                }
+            }
+
+            if matches!(rule.change, PriceChange::Percent(percent) if percent < -100) {
+                return Err(format!(
+                    "pricing.rules.{}: can't take off more than 100 percent",
+                    rule.name
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for PricingConfig {
+    fn default() -> Self {
+        PricingConfig {
+            refund_percent: 100,
+            holidays: vec![],
+            rounding: Rounding::HalfUp,
+            rules: vec![],
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TicketConfig {
+    /// oldest age, on the day of the screening, for a child ticket; only the
+    /// card holder's age is checked, one of a reservation's tickets must be
+    /// for it, and the attendant checks the others at the door
+    pub child_max_age: u32,
+    /// youngest age, on the day of the screening, for a senior ticket,
+    /// checked like a child ticket's
+    pub senior_min_age: u32,
+    pub adult: TicketDiscount,
+    pub child: TicketDiscount,
+    /// student tickets aren't checked, the attendant asks for the student ID
+    pub student: TicketDiscount,
+    pub senior: TicketDiscount,
+}
+
+impl TicketConfig {
+    pub fn discount(&self, category: TicketCategory) -> TicketDiscount {
+        match category {
+            TicketCategory::Adult => self.adult,
+            TicketCategory::Child => self.child,
+            TicketCategory::Student => self.student,
+            TicketCategory::Senior => self.senior,
+        }
+    }
+
+    fn check(&self) -> Result<(), String> {
+        for category in [
+            TicketCategory::Adult,
+            TicketCategory::Child,
+            TicketCategory::Student,
+            TicketCategory::Senior,
+        ] {
+            match self.discount(category) {
+                TicketDiscount::Percent(percent) if percent > 100 => {
+                    return Err(format!("tickets.{}: at most 100 percent", category))
+                }
+                TicketDiscount::Amount(amount) if amount.is_negative() => {
+                    return Err(format!(
+                        "tickets.{}: the amount can't be negative",
+                        category
+                    ))
+                }
+                _ => {}
+            }
+        }
+
+        if self.child_max_age >= self.senior_min_age {
+            Err("tickets.child_max_age must be below tickets.senior_min_age".into())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Default for TicketConfig {
+    fn default() -> Self {
+        TicketConfig {
+            child_max_age: 15,
+            senior_min_age: 65,
+            adult: TicketDiscount::Percent(0),
+            child: TicketDiscount::Percent(50),
+            student: TicketDiscount::Percent(25),
+            senior: TicketDiscount::Percent(30),
+        }
+    }
+}
+
+/// A movie field that can be part of the key that tells movies apart.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MovieKeyField {
+    Title,
+    ReleaseYear,
+    Language,
+    Subtitles,
+    Format,
+}
+
+impl MovieKeyField {
+    pub fn name(&self) -> &'static str {
+        match self {
+            MovieKeyField::Title => "title",
+            MovieKeyField::ReleaseYear => "release year",
+            MovieKeyField::Language => "language",
+            MovieKeyField::Subtitles => "subtitles",
+            MovieKeyField::Format => "format",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MovieConfig {
+    /// two movies can't agree on all of these; text is compared ignoring
+    /// case and extra whitespace
+    pub unique_key: Vec<MovieKeyField>,
+}
+
+impl Default for MovieConfig {
+    fn default() -> Self {
+        MovieConfig {
+            unique_key: vec![MovieKeyField::Title, MovieKeyField::ReleaseYear],
+        }
+    }
+}
+
+impl Config {
+    /// Reads CINEMA_CONFIG, or cinema.toml if that is not set, and applies the
+    /// environment overrides. A missing default file just means the defaults.
+    pub fn load() -> Result<Config, String> {
+        let (filename, required) = match env::var(ENV_CONFIG_FILE) {
+            Ok(filename) => (filename, true),
+            Err(_) => (DEFAULT_CONFIG_FILE.to_string(), false),
+        };
+
+        let content = match fs::read_to_string(&filename) {
+            Ok(content) => content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound && !required => String::new(),
+            Err(err) => return Err(format!("Unable to read {}: {}", filename, err)),
+        };
+
+        let overrides: Vec<(String, String)> = env::vars()
+            .filter(|(key, _)| key.starts_with(ENV_PREFIX) && key != ENV_CONFIG_FILE)
+            .collect();
+
+        Config::parse(&content, &overrides).map_err(|err| {
+            if overrides.is_empty() {
+                format!("Invalid {}: {}", filename, err)
+            } else {
+                format!(
+                    "Invalid {} or {}* environment variables: {}",
+                    filename, ENV_PREFIX, err
+                )
+            }
+        })
+    }
+
+    pub fn parse(content: &str, overrides: &[(String, String)]) -> Result<Config, String> {
+        let mut table: toml::Table = toml::from_str(content).map_err(|err| err.to_string())?;
+
+        for (key, value) in overrides {
+            if is_config_key(key) {
+                apply_override(&mut table, key, value)?;
+            }
+        }
+
+        let config: Config = toml::Value::Table(table)
+            .try_into()
+            .map_err(|err: toml::de::Error| err.to_string())?;
+
+        config.check()?;
+        Ok(config)
+    }
+
+    // Values that have the right type but make no sense together.
+    fn check(&self) -> Result<(), String> {
+        let limits = &self.validation;
+
+        if limits.min_name_length > limits.max_name_length {
+            Err("validation.min_name_length is greater than validation.max_name_length".into())
+        } else if limits.min_title_length > limits.max_title_length {
+            Err("validation.min_title_length is greater than validation.max_title_length".into())
+        } else if self.loyalty.accrual_percent > 100 {
+            Err("loyalty.accrual_percent can be at most 100".into())
+        } else if self.loyalty.point_value.is_negative() || self.loyalty.point_value.is_zero() {
+            Err("loyalty.point_value must be more than nothing".into())
+        } else if self.holds.timeout_secs == 0 {
+            Err("holds.timeout_secs must be at least 1".into())
+        } else if let Err(err) = time_validator(&self.schedule.prime_time_start) {
+            Err(format!("schedule.prime_time_start: {}", err))
+        } else if let Err(err) = time_validator(&self.schedule.prime_time_end) {
+            Err(format!("schedule.prime_time_end: {}", err))
+        } else if minutes_of_day(&self.schedule.prime_time_start)
+            >= minutes_of_day(&self.schedule.prime_time_end)
+        {
+            Err("schedule.prime_time_start must be before schedule.prime_time_end".into())
+        } else if self.schedule.start_step_minutes == 0 {
+            Err("schedule.start_step_minutes must be at least 1".into())
+        } else if let Err(err) = self.pricing.check() {
+            Err(err)
+        } else if self.movies.unique_key.is_empty() {
+            Err("movies.unique_key needs at least one field".into())
+        } else if self.data.flush == FlushMode::Batched && self.data.flush_ops == 0 {
+            Err("data.flush_ops must be at least 1".into())
+        } else {
+            self.tickets.check()
+        }
+    }
+}
+
+// Whether the variable names a key of the file. Other CINEMA_ variables are
+// someone else's, and are left alone.
+fn is_config_key(key: &str) -> bool {
+    let mut table = toml::Table::new();
+    if apply_override(&mut table, key, "0").is_err() {
+        return false;
+    }
+
+    match toml::Value::Table(table).try_into::<Config>() {
+        Ok(_) => true,
+        // the value may not suit the key, which doesn't matter here
+        Err(err) => !err.to_string().contains("unknown field"),
+    }
+}
+
+// CINEMA_DATA_FLUSH_OPS=20 becomes `flush_ops = 20` in the [data] table. The
+// value is read as TOML when it can be, so numbers and booleans keep their
+// type, and as a plain string otherwise.
+fn apply_override(table: &mut toml::Table, key: &str, value: &str) -> Result<(), String> {
+    let name = key[ENV_PREFIX.len()..].to_lowercase();
+    let (section, field) = match name.split_once('_') {
+        Some(x) => x,
+        None => return Err(format!("{} does not name a configuration key.", key)),
+    };
+
+    let value = match format!("value = {}", value).parse::<toml::Table>() {
+        Ok(mut parsed) => parsed.remove("value").unwrap(),
+        Err(_) => toml::Value::String(value.to_string()),
+    };
+
+    match table
+        .entry(section)
+        .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+    {
+        toml::Value::Table(section) => {
+            section.insert(field.to_string(), value);
+            Ok(())
+        }
+        _ => Err(format!("{} does not name a configuration key.", key)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule(start: &str, end: &str) -> Result<Config, String> {
+        Config::parse(
+            &format!(
+                "[schedule]\nprime_time_start = \"{}\"\nprime_time_end = \"{}\"\n",
+                start, end
+            ),
+            &[],
+        )
+    }
+
+    #[test]
+    fn prime_time_must_start_before_it_ends() {
+        assert!(schedule("18:00", "22:00").is_ok());
+        assert!(schedule("22:00", "18:00").is_err());
+        assert!(schedule("20:00", "20:00").is_err());
+    }
+
+    #[test]
+    fn only_variables_naming_a_key_override_it() {
+        let overrides = |pairs: &[(&str, &str)]| -> Vec<(String, String)> {
+            pairs
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect()
+        };
+
+        let config = Config::parse(
+            "",
+            &overrides(&[
+                ("CINEMA_HOME", "/opt/cinema"),
+                ("CINEMA_DATA_COLOUR", "red"),
+                ("CINEMA_PROJECTOR_LAMP_HOURS", "2000"),
+                ("CINEMA_DATA_DIR", "/var/cinema"),
+                ("CINEMA_LOYALTY_ACCRUAL_PERCENT", "20"),
+            ]),
+        )
+        .unwrap();
+        assert_eq!(config.data.dir, "/var/cinema");
+        assert_eq!(config.loyalty.accrual_percent, 20);
+
+        // a key's value must still suit it
+        assert!(Config::parse("", &overrides(&[("CINEMA_DATA_FLUSH_OPS", "many")])).is_err());
+    }
+}
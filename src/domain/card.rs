@@ -1,10 +1,13 @@
 use crate::domain::date::Date;
-use crate::repo::traits::Serializable;
+use crate::repo::traits::{csv_fields, parse_field, Identifiable, Serializable};
 use crate::validators::card_validator::{
-    card_validator, cnp_validator, first_name_validator, last_name_validator,
+    birthday_validator, card_fields_validator, card_rules_validator, cnp_validator,
+    first_name_validator, last_name_validator,
 };
+use crate::validators::context;
 use crate::validators::{common_validators::id_validator, date_validators::date_validator};
 
+/// A client's loyalty card, which collects points from reservations.
 #[derive(Clone)]
 pub struct Card {
     id: u32,
@@ -24,32 +27,32 @@ impl Serializable for Card {
             self.first_name,
             self.last_name,
             self.cnp,
-            self.birthday.to_string(),
-            self.registration_date.to_string(),
+            self.birthday,
+            self.registration_date,
             self.points
         )
     }
 
-    fn from_csv_to_obj(s: &str) -> Card {
-        let mut parts = s.trim().trim_matches('"').split("\",\"");
+    fn from_csv_to_obj(s: &str) -> Result<Card, String> {
+        let mut parts = csv_fields(s);
 
-        let id: u32 = parts.next().unwrap_or("0").parse().unwrap_or(0);
+        let id: u32 = parse_field(&mut parts, "id")?;
 
-        let first_name: String = parts.next().unwrap_or("firstname").to_string();
+        let first_name: String = parse_field(&mut parts, "first name")?;
 
-        let last_name: String = parts.next().unwrap_or("lastname").to_string();
+        let last_name: String = parse_field(&mut parts, "last name")?;
 
-        let cnp: String = parts.next().unwrap_or("1234567890123").to_string();
+        let cnp: String = parse_field(&mut parts, "CNP")?;
 
-        let birthday: String = parts.next().unwrap_or("01.01.2000").to_string();
-        let birthday = Date::from_string(&birthday).unwrap_or_default();
+        let birthday: String = parse_field(&mut parts, "birthday")?;
+        let birthday = Date::from_string(&birthday)?;
 
-        let registration_date: String = parts.next().unwrap_or("01.01.2000").to_string();
-        let registration_date = Date::from_string(&registration_date).unwrap_or_default();
+        let registration_date: String = parse_field(&mut parts, "registration date")?;
+        let registration_date = Date::from_string(&registration_date)?;
 
-        let points: u32 = parts.next().unwrap_or("0").parse().unwrap_or(0);
+        let points: u32 = parse_field(&mut parts, "points")?;
 
-        Card::new(
+        Card::from_stored(
             id,
             &first_name,
             &last_name,
@@ -58,7 +61,12 @@ impl Serializable for Card {
             registration_date,
             points,
         )
-        .unwrap()
+    }
+}
+
+impl Identifiable for Card {
+    fn id(&self) -> u32 {
+        self.id
     }
 }
 
@@ -71,18 +79,47 @@ impl Card {
         birthday: Date,
         registration_date: Date,
         points: u32,
+    ) -> Result<Card, String> {
+        let card = Card::from_stored(
+            id,
+            first_name,
+            last_name,
+            cnp,
+            birthday,
+            registration_date,
+            points,
+        )?;
+
+        match card_rules_validator(&card, &*context::clock()) {
+            Result::Ok(_) => Result::Ok(card),
+            Result::Err(err) => Result::Err(err),
+        }
+    }
+
+    /// Builds a card read back from storage. Only its fields are checked:
+    /// cards saved before the rules spanning several fields existed may break
+    /// them, so they are loaded and listed by `CardService::rule_violations`
+    /// until they are fixed.
+    pub fn from_stored(
+        id: u32,
+        first_name: &str,
+        last_name: &str,
+        cnp: &str,
+        birthday: Date,
+        registration_date: Date,
+        points: u32,
     ) -> Result<Card, String> {
         let card = Card {
             id,
             first_name: first_name.to_string(),
             last_name: last_name.to_string(),
             cnp: cnp.to_string(),
-            birthday: birthday,
-            registration_date: registration_date,
+            birthday,
+            registration_date,
             points,
         };
 
-        match card_validator(&card) {
+        match card_fields_validator(&card, &*context::clock()) {
             Result::Ok(_) => Result::Ok(card),
             Result::Err(err) => Result::Err(err),
         }
@@ -148,37 +185,80 @@ impl Card {
 
     pub fn set_cnp(&mut self, cnp: &str) -> Result<(), String> {
         match cnp_validator(cnp) {
-            Result::Ok(_) => {
-                self.cnp = cnp.to_string();
-                Result::Ok(())
-            }
+            Result::Ok(_) => self.change(|card| card.cnp = cnp.to_string()),
             Result::Err(err) => Result::Err(err),
         }
     }
 
     pub fn set_birthday(&mut self, birthday: Date) -> Result<(), String> {
-        match date_validator(&birthday) {
-            Result::Ok(_) => {
-                self.birthday = birthday;
-                Result::Ok(())
-            }
+        match birthday_validator(&birthday, &*context::clock()) {
+            Result::Ok(_) => self.change(|card| card.birthday = birthday),
             Result::Err(err) => Result::Err(err),
         }
     }
 
     pub fn set_registration_date(&mut self, registration_date: Date) -> Result<(), String> {
-        match date_validator(&registration_date) {
+        match date_validator(&registration_date, &*context::clock()) {
+            Result::Ok(_) => self.change(|card| card.registration_date = registration_date),
+            Result::Err(err) => Result::Err(err),
+        }
+    }
+
+    pub fn set_points(&mut self, points: u32) -> Result<(), String> {
+        self.points = points;
+        Result::Ok(())
+    }
+
+    /// The CNP encodes the birthday, so fixing one usually means fixing both.
+    pub fn set_cnp_and_birthday(&mut self, cnp: &str, birthday: Date) -> Result<(), String> {
+        cnp_validator(cnp)?;
+        birthday_validator(&birthday, &*context::clock())?;
+
+        self.change(|card| {
+            card.cnp = cnp.to_string();
+            card.birthday = birthday;
+        })
+    }
+
+    // Applies a change to a copy first, so the card is only modified if the
+    // rules spanning several fields still hold afterwards. Only the fields
+    // those rules look at go through here, so a stored card that breaks them
+    // can still have its points and names changed.
+    fn change<F>(&mut self, change: F) -> Result<(), String>
+    where
+        F: FnOnce(&mut Card),
+    {
+        let mut card = self.clone();
+        change(&mut card);
+
+        match card_rules_validator(&card, &*context::clock()) {
             Result::Ok(_) => {
-                self.registration_date = registration_date;
+                *self = card;
                 Result::Ok(())
             }
             Result::Err(err) => Result::Err(err),
         }
     }
+}
 
-    pub fn set_points(&mut self, points: u32) -> Result<(), String> {
-        self.points = points;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::clock::FixedClock;
+    use std::sync::Arc;
 
-        Result::Ok(())
+    #[test]
+    fn a_stored_card_that_breaks_the_rules_still_loads() {
+        context::set_clock(Arc::new(FixedClock::new(Date::new(1, 6, 2026).unwrap())));
+        // the CNP says 1.5.2012, the birthday 2.5.2012
+        let line = "\"1\",\"Ana\",\"Pop\",\"6120501123456\",\"2.5.2012\",\"1.6.2026\",\"10\"";
+
+        let mut card = Card::from_csv_to_obj(line).unwrap();
+        assert!(card_rules_validator(&card, &*context::clock()).is_err());
+
+        card.set_points(15).unwrap();
+        card.set_cnp_and_birthday("6120502123456", card.birthday())
+            .unwrap();
+        assert!(card_rules_validator(&card, &*context::clock()).is_ok());
     }
 }
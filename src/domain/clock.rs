@@ -0,0 +1,78 @@
+use crate::domain::date::Date;
+use chrono::NaiveDateTime;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Where "today" and "now" come from. Everything that depends on the current
+/// time asks a Clock instead of the system, so the time can be pinned.
+pub trait Clock: Send + Sync {
+    fn today(&self) -> Date;
+    /// Seconds since the Unix epoch.
+    fn now(&self) -> i64;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn today(&self) -> Date {
+        Date::from(chrono::Local::today().naive_local())
+    }
+
+    fn now(&self) -> i64 {
+        match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(elapsed) => elapsed.as_secs() as i64,
+            Err(_) => 0,
+        }
+    }
+}
+
+/// A clock that only moves when told to. It starts at midnight (UTC) of the
+/// given day; `advance` moves the time, and the day with it once it passes
+/// midnight.
+pub struct FixedClock {
+    now: AtomicI64,
+}
+
+impl FixedClock {
+    pub fn new(today: Date) -> Self {
+        let midnight = match today.to_naive() {
+            Some(date) => date.and_hms(0, 0, 0).timestamp(),
+            None => 0,
+        };
+
+        FixedClock {
+            now: AtomicI64::new(midnight),
+        }
+    }
+
+    pub fn advance(&self, seconds: i64) {
+        self.now.fetch_add(seconds, Ordering::SeqCst);
+    }
+}
+
+impl Clock for FixedClock {
+    fn today(&self) -> Date {
+        Date::from(NaiveDateTime::from_timestamp(self.now(), 0).date())
+    }
+
+    fn now(&self) -> i64 {
+        self.now.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn advancing_past_midnight_moves_the_day() {
+        let clock = FixedClock::new(Date::new(31, 12, 2026).unwrap());
+        assert!(clock.today() == Date::new(31, 12, 2026).unwrap());
+
+        clock.advance(23 * 3600 + 59 * 60);
+        assert!(clock.today() == Date::new(31, 12, 2026).unwrap());
+
+        clock.advance(60);
+        assert!(clock.today() == Date::new(1, 1, 2027).unwrap());
+    }
+}
@@ -1,10 +1,13 @@
 use chrono::Datelike;
+use std::fmt;
 
+use crate::validators::context;
 use crate::validators::date_validators::{
     date_validator, day_validator, month_validator, year_validator,
 };
 
-#[derive(Clone, Copy)]
+/// A calendar day, written as d.m.yyyy.
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub struct Date {
     day: u8,
     month: u8,
@@ -15,30 +18,58 @@ impl Date {
     pub fn new(day: u8, month: u8, year: u32) -> Result<Date, String> {
         let date = Date { day, month, year };
 
-        match date_validator(&date) {
+        match date_validator(&date, &*context::clock()) {
             Result::Ok(()) => Result::Ok(date),
             Result::Err(err) => Result::Err(err),
         }
     }
 
-    pub fn to_string(&self) -> String {
-        format!("{}.{}.{}", self.day, self.month, self.year)
+    /// dd.mm.yyyy
+    pub fn from_string(s: &str) -> Result<Date, String> {
+        match chrono::NaiveDate::parse_from_str(s, "%d.%m.%Y") {
+            Ok(date) => Ok(Date::from(date)),
+            Err(err) => Err(err.to_string()),
+        }
     }
 
-    // dd.mm.yyyy
-    pub fn from_string(s: &str) -> Result<Date, String> {
-        let naive_date = chrono::NaiveDate::parse_from_str(s, "%d.%m.%Y");
-
-        match naive_date {
-            Ok(date) => Ok(Date {
-                day: date.day() as u8,
-                month: date.month() as u8,
-                year: date.year() as u32,
-            }),
+    /// yyyy-mm-dd, sorts the same way as the dates themselves
+    pub fn to_iso_string(self) -> String {
+        format!("{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+
+    pub fn from_iso_string(s: &str) -> Result<Date, String> {
+        match chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+            Ok(date) => Ok(Date::from(date)),
             Err(err) => Err(err.to_string()),
         }
     }
 
+    /// None if the day does not exist, like 31.02.
+    pub fn to_naive(self) -> Option<chrono::NaiveDate> {
+        chrono::NaiveDate::from_ymd_opt(self.year as i32, self.month as u32, self.day as u32)
+    }
+
+    /// Full years from this date until `later`, e.g. someone's age on that day.
+    pub fn years_until(&self, later: Date) -> u32 {
+        if later < *self {
+            return 0;
+        }
+
+        let years = later.year - self.year;
+        if (later.month, later.day) < (self.month, self.day) {
+            years - 1
+        } else {
+            years
+        }
+    }
+
+    /// The date `days` days later, or earlier if negative. None if this date
+    /// does not exist.
+    pub fn add_days(&self, days: i64) -> Option<Date> {
+        self.to_naive()
+            .map(|date| Date::from(date + chrono::Duration::days(days)))
+    }
+
     pub fn day(&self) -> u8 {
         self.day
     }
@@ -72,7 +103,7 @@ impl Date {
     }
 
     pub fn set_year(&mut self, year: u32) -> Result<(), String> {
-        match year_validator(year) {
+        match year_validator(year, &*context::clock()) {
             Ok(_) => {
                 self.year = year;
                 Ok(())
@@ -82,6 +113,22 @@ impl Date {
     }
 }
 
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.day, self.month, self.year)
+    }
+}
+
+impl From<chrono::NaiveDate> for Date {
+    fn from(date: chrono::NaiveDate) -> Self {
+        Date {
+            day: date.day() as u8,
+            month: date.month() as u8,
+            year: date.year() as u32,
+        }
+    }
+}
+
 impl Default for Date {
     fn default() -> Self {
         Date {
@@ -91,3 +138,15 @@ impl Default for Date {
         }
     }
 }
+
+impl PartialOrd for Date {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Date {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.year, self.month, self.day).cmp(&(other.year, other.month, other.day))
+    }
+}
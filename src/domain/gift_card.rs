@@ -0,0 +1,267 @@
+use crate::domain::date::Date;
+use crate::domain::money::Money;
+use crate::repo::traits::{csv_fields, parse_field, Identifiable, Serializable};
+use crate::validators::gift_card_validator::{gift_card_entry_validator, gift_card_validator};
+use std::fmt;
+use std::str::FromStr;
+
+/// A prepaid gift card. Anyone with its code can pay for reservations with
+/// it until `expires`, that day included, while there is money left on it.
+#[derive(Clone)]
+pub struct GiftCard {
+    id: u32,
+    /// in capitals, e.g. "GIFT-7K2Q-9XW4"
+    code: String,
+    /// what it was bought for
+    value: Money,
+    /// what is left to spend, in the currency of `value`
+    balance: Money,
+    expires: Date,
+}
+
+impl Serializable for GiftCard {
+    fn to_csv(&self) -> String {
+        format!(
+            "\"{}\",\"{}\",\"{}\",\"{}\",\"{}\"",
+            self.id,
+            self.code,
+            self.value.to_plain_string(),
+            self.balance.to_plain_string(),
+            self.expires
+        )
+    }
+
+    fn from_csv_to_obj(s: &str) -> Result<GiftCard, String> {
+        let mut parts = csv_fields(s);
+
+        let id: u32 = parse_field(&mut parts, "id")?;
+
+        let code: String = parse_field(&mut parts, "code")?;
+
+        let value: String = parse_field(&mut parts, "value")?;
+
+        let balance: String = parse_field(&mut parts, "balance")?;
+
+        let expires: String = parse_field(&mut parts, "expiry date")?;
+
+        let mut gift_card = GiftCard::new(id, &code, value.parse()?, Date::from_string(&expires)?)?;
+        gift_card.set_balance(balance.parse()?)?;
+        Ok(gift_card)
+    }
+}
+
+impl Identifiable for GiftCard {
+    fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+impl GiftCard {
+    /// A new gift card, with all of its value left. The code is kept in
+    /// capitals.
+    pub fn new(id: u32, code: &str, value: Money, expires: Date) -> Result<GiftCard, String> {
+        let gift_card = GiftCard {
+            id,
+            code: code.trim().to_uppercase(),
+            value,
+            balance: value,
+            expires,
+        };
+
+        match gift_card_validator(&gift_card) {
+            Result::Ok(_) => Result::Ok(gift_card),
+            Result::Err(err) => Result::Err(err),
+        }
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    pub fn value(&self) -> Money {
+        self.value
+    }
+
+    pub fn balance(&self) -> Money {
+        self.balance
+    }
+
+    pub fn expires(&self) -> Date {
+        self.expires
+    }
+
+    pub fn is_expired_on(&self, date: Date) -> bool {
+        date > self.expires
+    }
+
+    pub fn set_balance(&mut self, balance: Money) -> Result<(), String> {
+        let old_balance = self.balance;
+        self.balance = balance;
+
+        match gift_card_validator(self) {
+            Result::Ok(_) => Result::Ok(()),
+            Result::Err(err) => {
+                self.balance = old_balance;
+                Result::Err(err)
+            }
+        }
+    }
+}
+
+/// Why money went onto or off a gift card.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GiftCardMovement {
+    /// the card was bought, with its value on it
+    Issue,
+    /// part of a reservation was paid with it
+    Payment,
+    /// a cancelled reservation gave back what the card paid for it
+    Refund,
+}
+
+impl fmt::Display for GiftCardMovement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            GiftCardMovement::Issue => "issue",
+            GiftCardMovement::Payment => "payment",
+            GiftCardMovement::Refund => "refund",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for GiftCardMovement {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<GiftCardMovement, String> {
+        match s.trim().to_lowercase().as_str() {
+            "issue" => Ok(GiftCardMovement::Issue),
+            "payment" => Ok(GiftCardMovement::Payment),
+            "refund" => Ok(GiftCardMovement::Refund),
+            _ => Err(format!("Invalid gift card movement: {}", s)),
+        }
+    }
+}
+
+/// A line of the gift card ledger: money that went onto a card or off it.
+/// The amount is never negative; the movement says which way it went.
+#[derive(Clone)]
+pub struct GiftCardEntry {
+    id: u32,
+    gift_card_id: u32,
+    date: Date,
+    movement: GiftCardMovement,
+    amount: Money,
+    /// the reservation paid or refunded; None when the card was issued
+    reservation_id: Option<u32>,
+}
+
+impl Serializable for GiftCardEntry {
+    fn to_csv(&self) -> String {
+        let reservation_id = match self.reservation_id {
+            None => "None".to_string(),
+            Some(x) => x.to_string(),
+        };
+
+        format!(
+            "\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\"",
+            self.id,
+            self.gift_card_id,
+            self.date,
+            self.movement,
+            self.amount.to_plain_string(),
+            reservation_id
+        )
+    }
+
+    fn from_csv_to_obj(s: &str) -> Result<GiftCardEntry, String> {
+        let mut parts = csv_fields(s);
+
+        let id: u32 = parse_field(&mut parts, "id")?;
+
+        let gift_card_id: u32 = parse_field(&mut parts, "gift card id")?;
+
+        let date: String = parse_field(&mut parts, "date")?;
+
+        let movement: String = parse_field(&mut parts, "movement")?;
+
+        let amount: String = parse_field(&mut parts, "amount")?;
+
+        let reservation_id: String = parse_field(&mut parts, "reservation id")?;
+        let reservation_id = match reservation_id.as_str() {
+            "None" => None,
+            x => Some(
+                x.parse()
+                    .map_err(|_| format!("Invalid reservation id: {}", x))?,
+            ),
+        };
+
+        GiftCardEntry::new(
+            id,
+            gift_card_id,
+            Date::from_string(&date)?,
+            movement.parse()?,
+            amount.parse()?,
+            reservation_id,
+        )
+    }
+}
+
+impl Identifiable for GiftCardEntry {
+    fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+impl GiftCardEntry {
+    pub fn new(
+        id: u32,
+        gift_card_id: u32,
+        date: Date,
+        movement: GiftCardMovement,
+        amount: Money,
+        reservation_id: Option<u32>,
+    ) -> Result<GiftCardEntry, String> {
+        let entry = GiftCardEntry {
+            id,
+            gift_card_id,
+            date,
+            movement,
+            amount,
+            reservation_id,
+        };
+
+        match gift_card_entry_validator(&entry) {
+            Result::Ok(_) => Result::Ok(entry),
+            Result::Err(err) => Result::Err(err),
+        }
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn gift_card_id(&self) -> u32 {
+        self.gift_card_id
+    }
+
+    pub fn date(&self) -> Date {
+        self.date
+    }
+
+    pub fn movement(&self) -> GiftCardMovement {
+        self.movement
+    }
+
+    pub fn amount(&self) -> Money {
+        self.amount
+    }
+
+    pub fn reservation_id(&self) -> Option<u32> {
+        self.reservation_id
+    }
+}
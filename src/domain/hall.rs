@@ -0,0 +1,215 @@
+use crate::domain::reservation::minutes_of_day;
+use crate::repo::traits::{csv_fields, parse_field, Identifiable, Serializable};
+use crate::validators::hall_validator::{hall_validator, opening_hours_validator};
+
+/// A place for one person in a hall's layout.
+pub const SEAT: char = 'S';
+/// A place for a wheelchair.
+pub const WHEELCHAIR: char = 'W';
+/// A seat sold as VIP.
+pub const VIP: char = 'V';
+/// An aisle or any other empty spot in a hall's layout.
+pub const GAP: char = '.';
+
+/// Opening hours of halls saved before they were recorded. A closing time
+/// not after the opening time is on the next day, so "00:00" is midnight.
+pub const DEFAULT_OPENS: &str = "10:00";
+pub const DEFAULT_CLOSES: &str = "00:00";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SeatKind {
+    Standard,
+    Wheelchair,
+    Vip,
+}
+
+impl SeatKind {
+    pub fn from_char(c: char) -> Option<SeatKind> {
+        match c {
+            SEAT => Some(SeatKind::Standard),
+            WHEELCHAIR => Some(SeatKind::Wheelchair),
+            VIP => Some(SeatKind::Vip),
+            _ => None,
+        }
+    }
+}
+
+/// One seat of a hall, named after its row letter and its number in the row,
+/// e.g. "C7". Gaps are not numbered, so seats keep their numbers across an
+/// aisle.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Seat {
+    pub label: String,
+    pub kind: SeatKind,
+    pub row: usize,
+    /// position in the row's layout string, gaps included
+    pub column: usize,
+}
+
+/// An auditorium. The layout is a list of rows, front row first (row "A"),
+/// each row a string of seats and gaps such as "SSSS..SSVV". The capacity
+/// is how many of those seats may be sold for one screening.
+#[derive(Clone)]
+pub struct Hall {
+    id: u32,
+    name: String,
+    capacity: u32,
+    layout: Vec<String>,
+    opens: String,
+    closes: String,
+}
+
+impl Serializable for Hall {
+    fn to_csv(&self) -> String {
+        format!(
+            "\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\"",
+            self.id,
+            self.name,
+            self.capacity,
+            self.layout.join("/"),
+            self.opens,
+            self.closes
+        )
+    }
+
+    fn from_csv_to_obj(s: &str) -> Result<Hall, String> {
+        let mut parts = csv_fields(s);
+
+        let id: u32 = parse_field(&mut parts, "id")?;
+
+        let name: String = parse_field(&mut parts, "name")?;
+
+        let capacity: u32 = parse_field(&mut parts, "capacity")?;
+
+        let layout: String = parse_field(&mut parts, "layout")?;
+
+        let mut hall = Hall::new(id, &name, capacity, parse_layout(&layout))?;
+
+        // files written before opening hours have neither column
+        if let (Some(opens), Some(closes)) = (parts.next(), parts.next()) {
+            hall.set_opening_hours(opens, closes)?;
+        }
+
+        Ok(hall)
+    }
+}
+
+impl Identifiable for Hall {
+    fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+impl Hall {
+    /// A hall open from `DEFAULT_OPENS` to `DEFAULT_CLOSES`.
+    pub fn new(id: u32, name: &str, capacity: u32, layout: Vec<String>) -> Result<Hall, String> {
+        let hall = Hall {
+            id,
+            name: name.to_string(),
+            capacity,
+            layout,
+            opens: DEFAULT_OPENS.to_string(),
+            closes: DEFAULT_CLOSES.to_string(),
+        };
+
+        match hall_validator(&hall) {
+            Result::Ok(_) => Result::Ok(hall),
+            Result::Err(err) => Result::Err(err),
+        }
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    pub fn layout(&self) -> &Vec<String> {
+        &self.layout
+    }
+
+    /// "hh:mm"
+    pub fn opens(&self) -> &str {
+        &self.opens
+    }
+
+    /// "hh:mm", on the next day if it is not after the opening time.
+    pub fn closes(&self) -> &str {
+        &self.closes
+    }
+
+    /// The opening hours in minutes from the start of the day the hall opens;
+    /// the closing minute can be past 24:00.
+    pub fn open_minutes(&self) -> (u32, u32) {
+        let opens = minutes_of_day(&self.opens);
+        let closes = minutes_of_day(&self.closes);
+
+        if closes <= opens {
+            (opens, closes + 24 * 60)
+        } else {
+            (opens, closes)
+        }
+    }
+
+    pub fn set_opening_hours(&mut self, opens: &str, closes: &str) -> Result<(), String> {
+        match opening_hours_validator(opens, closes) {
+            Result::Ok(_) => {
+                self.opens = opens.to_string();
+                self.closes = closes.to_string();
+                Result::Ok(())
+            }
+            Result::Err(err) => Result::Err(err),
+        }
+    }
+
+    /// The number of seats in the layout, which is at least the capacity.
+    pub fn seat_count(&self) -> u32 {
+        self.seats().len() as u32
+    }
+
+    /// Every seat, row by row, from left to right.
+    pub fn seats(&self) -> Vec<Seat> {
+        let mut seats = vec![];
+        for (row, line) in self.layout.iter().enumerate() {
+            let mut number = 0;
+            for (column, c) in line.chars().enumerate() {
+                if let Some(kind) = SeatKind::from_char(c) {
+                    number += 1;
+                    seats.push(Seat {
+                        label: seat_label(row, number),
+                        kind,
+                        row,
+                        column,
+                    });
+                }
+            }
+        }
+
+        seats
+    }
+
+    pub fn seat(&self, label: &str) -> Option<Seat> {
+        self.seats().into_iter().find(|seat| seat.label == label)
+    }
+}
+
+/// Rows are lettered from A, seats numbered from 1.
+pub fn seat_label(row: usize, number: usize) -> String {
+    format!("{}{}", row_letter(row), number)
+}
+
+pub fn row_letter(row: usize) -> char {
+    (b'A' + row as u8) as char
+}
+
+/// "SS.SS/SSSSS" -> ["SS.SS", "SSSSS"], the way layouts are written in the
+/// data files.
+pub fn parse_layout(layout: &str) -> Vec<String> {
+    layout.split('/').map(|row| row.to_string()).collect()
+}
@@ -1,4 +1,16 @@
+//! The entities. They check their own fields, and the rules between them,
+//! when they are built or changed.
+
 pub mod card;
+pub mod clock;
 pub mod date;
+pub mod gift_card;
+pub mod hall;
+pub mod money;
 pub mod movie;
+pub mod pricing;
+pub mod promotion;
 pub mod reservation;
+pub mod screening;
+pub mod seat_hold;
+pub mod ticket;
@@ -0,0 +1,346 @@
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+
+/// Minor units in one major unit, e.g. bani in a leu. The same for every
+/// currency the cinema takes.
+pub const MINOR_PER_MAJOR: i64 = 100;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Currency {
+    #[default]
+    Ron,
+    Eur,
+    Usd,
+}
+
+impl Currency {
+    /// ISO 4217 code
+    pub fn code(&self) -> &'static str {
+        match self {
+            Currency::Ron => "RON",
+            Currency::Eur => "EUR",
+            Currency::Usd => "USD",
+        }
+    }
+
+    // what goes after the amount when it is shown to people
+    fn suffix(&self) -> &'static str {
+        match self {
+            Currency::Ron => "lei",
+            other => other.code(),
+        }
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+impl FromStr for Currency {
+    type Err = String;
+
+    /// The code in any case; "lei" is also taken for RON.
+    fn from_str(s: &str) -> Result<Currency, String> {
+        match s.trim().to_uppercase().as_str() {
+            "RON" | "LEI" | "LEU" => Ok(Currency::Ron),
+            "EUR" => Ok(Currency::Eur),
+            "USD" => Ok(Currency::Usd),
+            _ => Err(format!("Unknown currency {}.", s)),
+        }
+    }
+}
+
+/// How a share of an amount that falls between two minor units is settled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Rounding {
+    /// towards zero
+    Down,
+    /// away from zero
+    Up,
+    /// to the nearest, halves away from zero
+    HalfUp,
+    /// to the nearest, halves to the even neighbour
+    HalfEven,
+}
+
+/// An amount of money in minor units, so 25,50 lei is 2550 bani. Amounts in
+/// different currencies can't be added or compared.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Money {
+    minor: i64,
+    currency: Currency,
+}
+
+impl Money {
+    pub fn new(minor: i64, currency: Currency) -> Money {
+        Money { minor, currency }
+    }
+
+    pub fn zero(currency: Currency) -> Money {
+        Money::new(0, currency)
+    }
+
+    /// A whole number of lei, euros, ...
+    pub fn from_major(major: i64, currency: Currency) -> Result<Money, String> {
+        match major.checked_mul(MINOR_PER_MAJOR) {
+            Some(minor) => Ok(Money::new(minor, currency)),
+            None => Err("The amount is too large.".into()),
+        }
+    }
+
+    pub fn minor(&self) -> i64 {
+        self.minor
+    }
+
+    pub fn currency(&self) -> Currency {
+        self.currency
+    }
+
+    /// The whole major units, the minor ones cut off.
+    pub fn major(&self) -> i64 {
+        self.minor / MINOR_PER_MAJOR
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.minor < 0
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.minor == 0
+    }
+
+    fn same_currency(&self, other: &Money) -> Result<(), String> {
+        if self.currency == other.currency {
+            Ok(())
+        } else {
+            Err(format!(
+                "Can't combine amounts in {} and {}.",
+                self.currency, other.currency
+            ))
+        }
+    }
+
+    pub fn checked_add(&self, other: Money) -> Result<Money, String> {
+        self.same_currency(&other)?;
+        match self.minor.checked_add(other.minor) {
+            Some(minor) => Ok(Money::new(minor, self.currency)),
+            None => Err("The amount is too large.".into()),
+        }
+    }
+
+    pub fn checked_sub(&self, other: Money) -> Result<Money, String> {
+        self.same_currency(&other)?;
+        match self.minor.checked_sub(other.minor) {
+            Some(minor) => Ok(Money::new(minor, self.currency)),
+            None => Err("The amount is too large.".into()),
+        }
+    }
+
+    /// The amount `times` times over, e.g. the price of several tickets.
+    pub fn checked_mul(&self, times: i64) -> Result<Money, String> {
+        match self.minor.checked_mul(times) {
+            Some(minor) => Ok(Money::new(minor, self.currency)),
+            None => Err("The amount is too large.".into()),
+        }
+    }
+
+    /// `percent` percent of the amount, rounded to a minor unit as asked.
+    pub fn percent(&self, percent: u32, rounding: Rounding) -> Result<Money, String> {
+        let minor = divide(self.minor as i128 * percent as i128, 100, rounding);
+        match i64::try_from(minor) {
+            Ok(minor) => Ok(Money::new(minor, self.currency)),
+            Err(_) => Err("The amount is too large.".into()),
+        }
+    }
+
+    /// Only amounts in the same currency can be compared.
+    pub fn compare(&self, other: &Money) -> Result<std::cmp::Ordering, String> {
+        self.same_currency(other)?;
+        Ok(self.minor.cmp(&other.minor))
+    }
+
+    /// Adds up the amounts; `currency` is the currency of an empty sum.
+    pub fn sum<I>(amounts: I, currency: Currency) -> Result<Money, String>
+    where
+        I: IntoIterator<Item = Money>,
+    {
+        amounts
+            .into_iter()
+            .try_fold(Money::zero(currency), |total, amount| {
+                total.checked_add(amount)
+            })
+    }
+
+    /// "25.50 RON", the form the repositories store.
+    pub fn to_plain_string(&self) -> String {
+        format!("{} {}", self.number('.'), self.currency.code())
+    }
+
+    fn number(&self, separator: char) -> String {
+        let sign = if self.minor < 0 { "-" } else { "" };
+        let minor = self.minor.unsigned_abs();
+        let per = MINOR_PER_MAJOR as u64;
+        format!("{}{}{}{:02}", sign, minor / per, separator, minor % per)
+    }
+}
+
+// numerator / denominator for a positive denominator
+fn divide(numerator: i128, denominator: i128, rounding: Rounding) -> i128 {
+    let quotient = numerator / denominator;
+    let remainder = numerator % denominator;
+    if remainder == 0 {
+        return quotient;
+    }
+
+    let away = if numerator < 0 {
+        quotient - 1
+    } else {
+        quotient + 1
+    };
+    let twice = remainder.abs() * 2;
+    match rounding {
+        Rounding::Down => quotient,
+        Rounding::Up => away,
+        Rounding::HalfUp if twice >= denominator => away,
+        Rounding::HalfEven if twice > denominator => away,
+        Rounding::HalfEven if twice == denominator && quotient % 2 != 0 => away,
+        Rounding::HalfUp | Rounding::HalfEven => quotient,
+    }
+}
+
+/// 25,50 lei
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.number(','), self.currency.suffix())
+    }
+}
+
+// so the config file can have amounts like "5 lei"
+impl TryFrom<String> for Money {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Money, String> {
+        s.parse()
+    }
+}
+
+impl FromStr for Money {
+    type Err = String;
+
+    /// An amount with at most two decimals after a dot or a comma, then
+    /// optionally the currency: "25", "25.5 RON", "25,50 lei". Without a
+    /// currency it is in lei.
+    fn from_str(s: &str) -> Result<Money, String> {
+        let s = s.trim();
+        let (amount, currency) = match s.split_once(char::is_whitespace) {
+            Some((amount, currency)) => (amount, currency.parse()?),
+            None => (s, Currency::default()),
+        };
+
+        let invalid = || format!("{} is not an amount of money.", s);
+        let (negative, amount) = match amount.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, amount),
+        };
+        let (whole, fraction) = match amount.split_once(['.', ',']) {
+            Some((whole, fraction)) => (whole, fraction),
+            None => (amount, ""),
+        };
+        if whole.is_empty()
+            || fraction.len() > 2
+            || !whole.chars().all(|c| c.is_ascii_digit())
+            || !fraction.chars().all(|c| c.is_ascii_digit())
+        {
+            return Err(invalid());
+        }
+
+        let whole: i64 = whole.parse().map_err(|_| invalid())?;
+        let fraction: i64 = format!("{:0<2}", fraction).parse().map_err(|_| invalid())?;
+        let minor = whole
+            .checked_mul(MINOR_PER_MAJOR)
+            .and_then(|minor| minor.checked_add(fraction))
+            .ok_or_else(invalid)?;
+
+        Ok(Money::new(if negative { -minor } else { minor }, currency))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lei(minor: i64) -> Money {
+        Money::new(minor, Currency::Ron)
+    }
+
+    #[test]
+    fn half_even_rounds_ties_to_the_even_neighbour() {
+        // 1% of 2,50 lei is 2.5 bani, of 3,50 lei 3.5 bani
+        assert_eq!(lei(250).percent(1, Rounding::HalfEven), Ok(lei(2)));
+        assert_eq!(lei(350).percent(1, Rounding::HalfEven), Ok(lei(4)));
+        assert_eq!(lei(260).percent(1, Rounding::HalfEven), Ok(lei(3)));
+        assert_eq!(lei(250).percent(1, Rounding::HalfUp), Ok(lei(3)));
+    }
+
+    #[test]
+    fn negative_amounts_round_away_from_zero_or_towards_it() {
+        assert_eq!(lei(-250).percent(1, Rounding::Down), Ok(lei(-2)));
+        assert_eq!(lei(-250).percent(1, Rounding::Up), Ok(lei(-3)));
+        assert_eq!(lei(-250).percent(1, Rounding::HalfUp), Ok(lei(-3)));
+        assert_eq!(lei(-250).percent(1, Rounding::HalfEven), Ok(lei(-2)));
+        assert_eq!(lei(-350).percent(1, Rounding::HalfEven), Ok(lei(-4)));
+    }
+
+    #[test]
+    fn parses_amounts_written_by_people_and_by_the_repositories() {
+        assert_eq!("25,5".parse(), Ok(lei(2550)));
+        assert_eq!("25".parse(), Ok(lei(2500)));
+        assert_eq!("-0.5".parse(), Ok(lei(-50)));
+        assert_eq!("25.50 RON".parse(), Ok(lei(2550)));
+        assert_eq!("25,50 lei".parse(), Ok(lei(2550)));
+        assert_eq!(
+            "3.05 EUR".parse::<Money>(),
+            Ok(Money::new(305, Currency::Eur))
+        );
+    }
+
+    #[test]
+    fn refuses_what_is_not_an_amount() {
+        for s in ["", "abc", "1.234", ".5", "-", "2 5", "25 GBP", "1e3"] {
+            assert!(s.parse::<Money>().is_err(), "{:?} was accepted", s);
+        }
+    }
+
+    #[test]
+    fn round_trips_through_the_plain_string() {
+        for minor in [0, 5, -5, 2550, -2550, i64::MAX] {
+            let money = lei(minor);
+            assert_eq!(money.to_plain_string().parse(), Ok(money));
+        }
+        assert_eq!(lei(-5).to_string(), "-0,05 lei");
+    }
+
+    #[test]
+    fn overflows_are_errors() {
+        assert!("92233720368547758.08".parse::<Money>().is_err());
+        assert!(Money::from_major(i64::MAX, Currency::Ron).is_err());
+        assert!(lei(i64::MAX).checked_add(lei(1)).is_err());
+        assert!(lei(i64::MIN).checked_sub(lei(1)).is_err());
+        assert!(lei(i64::MAX).checked_mul(2).is_err());
+        assert!(lei(i64::MAX).percent(200, Rounding::Down).is_err());
+        assert!(Money::sum([lei(i64::MAX), lei(1)], Currency::Ron).is_err());
+    }
+
+    #[test]
+    fn amounts_in_different_currencies_dont_mix() {
+        let euro = Money::new(100, Currency::Eur);
+        assert!(lei(100).checked_add(euro).is_err());
+        assert!(lei(100).compare(&euro).is_err());
+    }
+}
@@ -1,40 +1,208 @@
-use crate::repo::traits::Serializable;
+use crate::domain::money::Money;
+use crate::repo::traits::{
+    csv_fields, parse_field, parse_optional_field, Identifiable, Serializable,
+};
 use crate::validators::common_validators::id_validator;
+use crate::validators::context;
 use crate::validators::movie_validator::{
-    movie_validator, price_validator, release_year_validator, title_validator,
+    genres_validator, language_validator, movie_validator, price_validator, release_year_validator,
+    runtime_validator, title_validator,
 };
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+
+/// Movies saved before running times were recorded are taken to last two
+/// hours.
+pub const DEFAULT_RUNTIME: u32 = 120;
+
+/// The Romanian age classification of a movie.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AgeRating {
+    /// audiență generală, for everyone
+    #[default]
+    Ag,
+    /// under 12 only with a parent or guardian
+    Ap12,
+    /// not recommended under 15
+    N15,
+    /// under 18 not allowed
+    Im18,
+}
+
+impl AgeRating {
+    /// The age from which the movie can be seen alone.
+    pub fn min_age(&self) -> u32 {
+        match self {
+            AgeRating::Ag => 0,
+            AgeRating::Ap12 => 12,
+            AgeRating::N15 => 15,
+            AgeRating::Im18 => 18,
+        }
+    }
+
+    /// Whether someone younger than `min_age` may still see the movie with a
+    /// supervising adult.
+    pub fn allows_supervised(&self) -> bool {
+        *self != AgeRating::Im18
+    }
+}
+
+impl fmt::Display for AgeRating {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            AgeRating::Ag => "AG",
+            AgeRating::Ap12 => "AP-12",
+            AgeRating::N15 => "N-15",
+            AgeRating::Im18 => "IM-18",
+        };
+        write!(f, "{}", name)
+    }
+}
 
-#[derive(Debug)]
+impl FromStr for AgeRating {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<AgeRating, String> {
+        match s.trim().to_uppercase().as_str() {
+            "AG" => Ok(AgeRating::Ag),
+            "AP-12" | "AP12" => Ok(AgeRating::Ap12),
+            "N-15" | "N15" => Ok(AgeRating::N15),
+            "IM-18" | "IM18" => Ok(AgeRating::Im18),
+            _ => Err(format!(
+                "The age rating must be one of AG, AP-12, N-15 and IM-18, not {}.",
+                s
+            )),
+        }
+    }
+}
+
+/// How the movie is projected.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum MovieFormat {
+    #[default]
+    TwoD,
+    ThreeD,
+    Imax,
+}
+
+impl fmt::Display for MovieFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            MovieFormat::TwoD => "2D",
+            MovieFormat::ThreeD => "3D",
+            MovieFormat::Imax => "IMAX",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl TryFrom<String> for MovieFormat {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<MovieFormat, String> {
+        s.parse()
+    }
+}
+
+impl FromStr for MovieFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<MovieFormat, String> {
+        match s.trim().to_uppercase().as_str() {
+            "2D" => Ok(MovieFormat::TwoD),
+            "3D" => Ok(MovieFormat::ThreeD),
+            "IMAX" => Ok(MovieFormat::Imax),
+            _ => Err(format!("The format must be 2D, 3D or IMAX, not {}.", s)),
+        }
+    }
+}
+
+/// A film the cinema shows. Only movies in program can be reserved.
+#[derive(Debug, Clone)]
 pub struct Movie {
     id: u32,
     title: String,
     release_year: u32,
-    price: u32,
+    price: Money,
     in_program: bool,
+    /// minutes
+    runtime: u32,
+    genres: Vec<String>,
+    age_rating: AgeRating,
+    /// original language, as a two letter code such as "en"
+    language: Option<String>,
+    /// language of the subtitles, if there are any
+    subtitles: Option<String>,
+    format: MovieFormat,
 }
 
 impl Serializable for Movie {
     fn to_csv(&self) -> String {
         format!(
-            "\"{}\",\"{}\",\"{}\",\"{}\",\"{}\"",
-            self.id, self.title, self.release_year, self.price, self.in_program,
+            "\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\"",
+            self.id,
+            self.title,
+            self.release_year,
+            self.price.to_plain_string(),
+            self.in_program,
+            self.runtime,
+            self.genres.join(";"),
+            self.age_rating,
+            self.language.as_deref().unwrap_or(""),
+            self.subtitles.as_deref().unwrap_or(""),
+            self.format,
         )
     }
 
-    fn from_csv_to_obj(s: &str) -> Movie {
-        let mut parts = s.trim().trim_matches('"').split("\",\"");
+    fn from_csv_to_obj(s: &str) -> Result<Movie, String> {
+        let mut parts = csv_fields(s);
+
+        let id: u32 = parse_field(&mut parts, "id")?;
 
-        let id: u32 = parts.next().unwrap_or("0").parse().unwrap_or(0);
+        let title: String = parse_field(&mut parts, "title")?;
 
-        let title: String = parts.next().unwrap_or("placeholder").to_string();
+        let release_year: u32 = parse_field(&mut parts, "release year")?;
 
-        let release_year: u32 = parts.next().unwrap_or("1926").parse().unwrap_or(1926);
+        // older files have a whole number of lei
+        let price: Money = parse_field(&mut parts, "price")?;
 
-        let price: u32 = parts.next().unwrap_or("0").parse().unwrap_or(0);
+        let in_program: bool = parse_field(&mut parts, "in program flag")?;
 
-        let in_program: bool = parts.next().unwrap_or("false").parse().unwrap_or(false);
+        // the columns from here on were added over time, older files stop
+        // after the in program flag or after the runtime
+        let runtime: u32 = parse_optional_field(&mut parts, "runtime", DEFAULT_RUNTIME)?;
+
+        let genres: String = parse_optional_field(&mut parts, "genres", String::new())?;
+
+        let age_rating: AgeRating = match parts.next() {
+            None => AgeRating::default(),
+            Some(x) => x.parse()?,
+        };
+
+        let language: String = parse_optional_field(&mut parts, "language", String::new())?;
+
+        let subtitles: String = parse_optional_field(&mut parts, "subtitles", String::new())?;
+
+        let format: MovieFormat = match parts.next() {
+            None => MovieFormat::default(),
+            Some(x) => x.parse()?,
+        };
 
-        Movie::new(id, &title, release_year, price, in_program).unwrap()
+        let mut movie = Movie::new(id, &title, release_year, price, in_program, runtime)?;
+        movie.set_genres(parse_genres(&genres))?;
+        movie.set_age_rating(age_rating)?;
+        movie.set_language(optional(&language))?;
+        movie.set_subtitles(optional(&subtitles))?;
+        movie.set_format(format)?;
+        Ok(movie)
+    }
+}
+
+impl Identifiable for Movie {
+    fn id(&self) -> u32 {
+        self.id
     }
 }
 
@@ -43,18 +211,25 @@ impl Movie {
         id: u32,
         title: &str,
         release_year: u32,
-        price: u32,
+        price: Money,
         in_program: bool,
+        runtime: u32,
     ) -> Result<Movie, String> {
         let movie = Movie {
             id,
             release_year,
             price,
             in_program,
+            runtime,
             title: title.to_string(),
+            genres: vec![],
+            age_rating: AgeRating::default(),
+            language: None,
+            subtitles: None,
+            format: MovieFormat::default(),
         };
 
-        match movie_validator(&movie) {
+        match movie_validator(&movie, &*context::clock()) {
             Result::Ok(_) => Result::Ok(movie),
             Result::Err(err) => Result::Err(err),
         }
@@ -72,7 +247,7 @@ impl Movie {
         self.release_year
     }
 
-    pub fn price(&self) -> u32 {
+    pub fn price(&self) -> Money {
         self.price
     }
 
@@ -80,6 +255,31 @@ impl Movie {
         self.in_program
     }
 
+    /// The running time in minutes.
+    pub fn runtime(&self) -> u32 {
+        self.runtime
+    }
+
+    pub fn genres(&self) -> &Vec<String> {
+        &self.genres
+    }
+
+    pub fn age_rating(&self) -> AgeRating {
+        self.age_rating
+    }
+
+    pub fn language(&self) -> Option<&str> {
+        self.language.as_deref()
+    }
+
+    pub fn subtitles(&self) -> Option<&str> {
+        self.subtitles.as_deref()
+    }
+
+    pub fn format(&self) -> MovieFormat {
+        self.format
+    }
+
     pub fn set_id(&mut self, id: u32) -> Result<(), String> {
         match id_validator(id) {
             Result::Ok(_) => {
@@ -101,7 +301,7 @@ impl Movie {
     }
 
     pub fn set_release_year(&mut self, release_year: u32) -> Result<(), String> {
-        match release_year_validator(release_year) {
+        match release_year_validator(release_year, &*context::clock()) {
             Result::Ok(_) => {
                 self.release_year = release_year;
                 Result::Ok(())
@@ -110,7 +310,7 @@ impl Movie {
         }
     }
 
-    pub fn set_price(&mut self, price: u32) -> Result<(), String> {
+    pub fn set_price(&mut self, price: Money) -> Result<(), String> {
         match price_validator(price) {
             Result::Ok(_) => {
                 self.price = price;
@@ -124,4 +324,124 @@ impl Movie {
         self.in_program = in_program;
         Result::Ok(())
     }
+
+    pub fn set_runtime(&mut self, runtime: u32) -> Result<(), String> {
+        match runtime_validator(runtime) {
+            Result::Ok(_) => {
+                self.runtime = runtime;
+                Result::Ok(())
+            }
+            Result::Err(err) => Result::Err(err),
+        }
+    }
+
+    pub fn set_genres(&mut self, genres: Vec<String>) -> Result<(), String> {
+        match genres_validator(&genres) {
+            Result::Ok(_) => {
+                self.genres = genres;
+                Result::Ok(())
+            }
+            Result::Err(err) => Result::Err(err),
+        }
+    }
+
+    pub fn set_age_rating(&mut self, age_rating: AgeRating) -> Result<(), String> {
+        self.age_rating = age_rating;
+        Result::Ok(())
+    }
+
+    pub fn set_language(&mut self, language: Option<String>) -> Result<(), String> {
+        match language_validator(language.as_deref(), "language") {
+            Result::Ok(_) => {
+                self.language = language;
+                Result::Ok(())
+            }
+            Result::Err(err) => Result::Err(err),
+        }
+    }
+
+    pub fn set_subtitles(&mut self, subtitles: Option<String>) -> Result<(), String> {
+        match language_validator(subtitles.as_deref(), "subtitle language") {
+            Result::Ok(_) => {
+                self.subtitles = subtitles;
+                Result::Ok(())
+            }
+            Result::Err(err) => Result::Err(err),
+        }
+    }
+
+    pub fn set_format(&mut self, format: MovieFormat) -> Result<(), String> {
+        self.format = format;
+        Result::Ok(())
+    }
+}
+
+/// "Drama;Sci-Fi" -> ["Drama", "Sci-Fi"], the way genres are written in the
+/// data files.
+pub fn parse_genres(genres: &str) -> Vec<String> {
+    genres
+        .split(';')
+        .filter(|genre| !genre.is_empty())
+        .map(|genre| genre.to_string())
+        .collect()
+}
+
+// Empty columns stand for "none".
+fn optional(value: &str) -> Option<String> {
+    if value.is_empty() {
+        None
+    } else {
+        Some(value.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::clock::FixedClock;
+    use crate::domain::date::Date;
+    use crate::domain::money::Currency;
+    use std::sync::Arc;
+
+    fn read(line: &str) -> Movie {
+        context::set_clock(Arc::new(FixedClock::new(Date::new(1, 6, 2026).unwrap())));
+        Movie::from_csv_to_obj(line).unwrap()
+    }
+
+    #[test]
+    fn reads_the_first_format_with_whole_lei() {
+        let movie = read(r#""1","Dune","2021","25","true""#);
+
+        assert_eq!(movie.price(), Money::new(2500, Currency::Ron));
+        assert_eq!(movie.runtime(), DEFAULT_RUNTIME);
+        assert!(movie.genres().is_empty());
+        assert_eq!(movie.age_rating(), AgeRating::Ag);
+        assert_eq!(movie.language(), None);
+        assert_eq!(movie.format(), MovieFormat::TwoD);
+    }
+
+    #[test]
+    fn reads_the_format_with_runtimes() {
+        let movie = read(r#""1","Dune","2021","25.50 RON","false","155""#);
+
+        assert_eq!(movie.price(), Money::new(2550, Currency::Ron));
+        assert!(!movie.in_program());
+        assert_eq!(movie.runtime(), 155);
+        assert_eq!(movie.age_rating(), AgeRating::Ag);
+    }
+
+    #[test]
+    fn csv_round_trip_keeps_the_details() {
+        let written =
+            r#""1","Dune","2021","25.00 RON","true","155","Drama;Sci-Fi","N-15","en","ro","IMAX""#;
+        let movie = read(written);
+
+        assert_eq!(movie.to_csv(), written);
+        assert_eq!(
+            movie.genres(),
+            &vec!["Drama".to_string(), "Sci-Fi".to_string()]
+        );
+        assert_eq!(movie.age_rating(), AgeRating::N15);
+        assert_eq!(movie.subtitles(), Some("ro"));
+    }
 }
@@ -0,0 +1,399 @@
+use crate::domain::date::Date;
+use crate::domain::money::{Money, Rounding};
+use crate::domain::movie::{Movie, MovieFormat};
+use crate::domain::reservation::minutes_of_day;
+use crate::domain::screening::Screening;
+use chrono::Datelike;
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Weekday {
+    Mon,
+    Tue,
+    Wed,
+    Thu,
+    Fri,
+    Sat,
+    Sun,
+}
+
+impl fmt::Display for Weekday {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Weekday::Mon => "mon",
+            Weekday::Tue => "tue",
+            Weekday::Wed => "wed",
+            Weekday::Thu => "thu",
+            Weekday::Fri => "fri",
+            Weekday::Sat => "sat",
+            Weekday::Sun => "sun",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Weekday {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Weekday, String> {
+        match s.trim().to_lowercase().as_str() {
+            "mon" => Ok(Weekday::Mon),
+            "tue" => Ok(Weekday::Tue),
+            "wed" => Ok(Weekday::Wed),
+            "thu" => Ok(Weekday::Thu),
+            "fri" => Ok(Weekday::Fri),
+            "sat" => Ok(Weekday::Sat),
+            "sun" => Ok(Weekday::Sun),
+            _ => Err(format!(
+                "{} is not a day of the week, write mon, tue, ...",
+                s
+            )),
+        }
+    }
+}
+
+impl Weekday {
+    /// None if the date does not exist.
+    pub fn of(date: Date) -> Option<Weekday> {
+        date.to_naive().map(|date| Weekday::from(date.weekday()))
+    }
+}
+
+impl From<chrono::Weekday> for Weekday {
+    fn from(day: chrono::Weekday) -> Weekday {
+        match day {
+            chrono::Weekday::Mon => Weekday::Mon,
+            chrono::Weekday::Tue => Weekday::Tue,
+            chrono::Weekday::Wed => Weekday::Wed,
+            chrono::Weekday::Thu => Weekday::Thu,
+            chrono::Weekday::Fri => Weekday::Fri,
+            chrono::Weekday::Sat => Weekday::Sat,
+            chrono::Weekday::Sun => Weekday::Sun,
+        }
+    }
+}
+
+/// A day the cinema counts as a public holiday: "25.12" every year, or
+/// "13.4.2026" for a holiday that moves, like Easter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Holiday {
+    day: u8,
+    month: u8,
+    year: Option<u32>,
+}
+
+impl Holiday {
+    pub fn falls_on(&self, date: Date) -> bool {
+        self.day == date.day()
+            && self.month == date.month()
+            && self.year.is_none_or(|year| year == date.year())
+    }
+}
+
+impl TryFrom<String> for Holiday {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Holiday, String> {
+        s.parse()
+    }
+}
+
+impl FromStr for Holiday {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Holiday, String> {
+        let invalid = || format!("{} is not a holiday, write it as d.m or d.m.yyyy.", s);
+        let parts: Vec<&str> = s.trim().split('.').collect();
+
+        let year = match parts.len() {
+            2 => None,
+            3 => Some(parts[2].parse().map_err(|_| invalid())?),
+            _ => return Err(invalid()),
+        };
+        let day: u8 = parts[0].parse().map_err(|_| invalid())?;
+        let month: u8 = parts[1].parse().map_err(|_| invalid())?;
+
+        // 29.2 is a holiday only in leap years, so any leap year will do
+        if Date::new(day, month, year.unwrap_or(2024))
+            .ok()
+            .and_then(|date| date.to_naive())
+            .is_none()
+        {
+            return Err(invalid());
+        }
+
+        Ok(Holiday { day, month, year })
+    }
+}
+
+/// How a rule changes the price it gets.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PriceChange {
+    /// a share of the price, negative for cheaper
+    Percent(i32),
+    /// a fixed amount, e.g. "-5 lei" for cheaper
+    Amount(Money),
+}
+
+impl fmt::Display for PriceChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PriceChange::Percent(percent) => write!(f, "{:+}%", percent),
+            PriceChange::Amount(amount) if amount.is_negative() => write!(f, "{}", amount),
+            PriceChange::Amount(amount) => write!(f, "+{}", amount),
+        }
+    }
+}
+
+/// A price rule from the configuration. It fires for a screening that meets
+/// all of its conditions; one without any fires for every screening.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PriceRule {
+    pub name: String,
+    /// days of the week it fires on
+    #[serde(default)]
+    pub days: Vec<Weekday>,
+    /// screenings starting at this time or later, "hh:mm"
+    #[serde(default)]
+    pub from: Option<String>,
+    /// screenings starting before this time; before `from` means the range
+    /// goes past midnight
+    #[serde(default)]
+    pub until: Option<String>,
+    /// only on holidays if true, only on other days if false
+    #[serde(default)]
+    pub holiday: Option<bool>,
+    #[serde(default)]
+    pub formats: Vec<MovieFormat>,
+    pub change: PriceChange,
+    /// later rules are skipped when this one fires
+    #[serde(default)]
+    pub stop: bool,
+}
+
+impl PriceRule {
+    fn fires(&self, screening: &Screening, format: MovieFormat, holiday: bool) -> bool {
+        let weekday = match Weekday::of(screening.date()) {
+            Some(weekday) => weekday,
+            None => return false,
+        };
+        let start = minutes_of_day(screening.start());
+        let from = self.from.as_deref().map(minutes_of_day);
+        let until = self.until.as_deref().map(minutes_of_day);
+
+        let in_time = match (from, until) {
+            (None, None) => true,
+            (Some(from), None) => start >= from,
+            (None, Some(until)) => start < until,
+            (Some(from), Some(until)) if from < until => start >= from && start < until,
+            (Some(from), Some(until)) => start >= from || start < until,
+        };
+
+        (self.days.is_empty() || self.days.contains(&weekday))
+            && in_time
+            && self.holiday.is_none_or(|wanted| wanted == holiday)
+            && (self.formats.is_empty() || self.formats.contains(&format))
+    }
+
+    // The price after this rule; it never goes below zero.
+    fn apply(&self, price: Money, rounding: Rounding) -> Result<Money, String> {
+        let changed = match self.change {
+            PriceChange::Percent(percent) => {
+                let share = price.percent(percent.unsigned_abs(), rounding)?;
+                if percent < 0 {
+                    price.checked_sub(share)?
+                } else {
+                    price.checked_add(share)?
+                }
+            }
+            PriceChange::Amount(amount) => price.checked_add(amount)?,
+        };
+
+        if changed.is_negative() {
+            Ok(Money::zero(price.currency()))
+        } else {
+            Ok(changed)
+        }
+    }
+}
+
+/// A screening's ticket price and the rules that made it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PriceExplanation {
+    /// the movie's price
+    pub base: Money,
+    /// every rule that fired, with the price right after it
+    pub steps: Vec<(PriceRule, Money)>,
+    pub price: Money,
+}
+
+/// Base price: 30,00 lei
+/// weekend night (+15%): 34,50 lei
+/// Price: 34,50 lei
+impl fmt::Display for PriceExplanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Base price: {}", self.base)?;
+        for (rule, price) in &self.steps {
+            writeln!(f, "{} ({}): {}", rule.name, rule.change, price)?;
+        }
+        write!(f, "Price: {}", self.price)
+    }
+}
+
+/// Runs the movie's price through the rules, in order, for the screening.
+pub fn explain_price(
+    movie: &Movie,
+    screening: &Screening,
+    rules: &[PriceRule],
+    holidays: &[Holiday],
+    rounding: Rounding,
+) -> Result<PriceExplanation, String> {
+    let holiday = holidays.iter().any(|h| h.falls_on(screening.date()));
+    let mut price = movie.price();
+    let mut steps = vec![];
+
+    for rule in rules {
+        if !rule.fires(screening, movie.format(), holiday) {
+            continue;
+        }
+
+        price = rule
+            .apply(price, rounding)
+            .map_err(|err| format!("Price rule {}: {}", rule.name, err))?;
+        steps.push((rule.clone(), price));
+        if rule.stop {
+            break;
+        }
+    }
+
+    Ok(PriceExplanation {
+        base: movie.price(),
+        steps,
+        price,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::money::Currency;
+
+    fn lei(minor: i64) -> Money {
+        Money::new(minor, Currency::Ron)
+    }
+
+    fn rule(name: &str, change: PriceChange) -> PriceRule {
+        PriceRule {
+            name: name.into(),
+            days: vec![],
+            from: None,
+            until: None,
+            holiday: None,
+            formats: vec![],
+            change,
+            stop: false,
+        }
+    }
+
+    // Friday 23.10.2026, Wednesday 21.10.2026
+    fn screening(day: u8, start: &str) -> Screening {
+        Screening::new(1, 1, 1, Date::new(day, 10, 2026).unwrap(), start.into()).unwrap()
+    }
+
+    fn movie() -> Movie {
+        Movie::new(1, "Dune", 2021, lei(3000), true, 155).unwrap()
+    }
+
+    fn price(screening: &Screening, rules: &[PriceRule], holidays: &[Holiday]) -> Money {
+        explain_price(&movie(), screening, rules, holidays, Rounding::HalfUp)
+            .unwrap()
+            .price
+    }
+
+    #[test]
+    fn rules_fire_on_their_days_and_times() {
+        let weekend_night = PriceRule {
+            days: vec![Weekday::Fri, Weekday::Sat],
+            from: Some("18:00".into()),
+            ..rule("weekend night", PriceChange::Percent(15))
+        };
+        let rules = [weekend_night];
+
+        assert_eq!(price(&screening(23, "20:00"), &rules, &[]), lei(3450));
+        assert_eq!(price(&screening(23, "17:59"), &rules, &[]), lei(3000));
+        assert_eq!(price(&screening(21, "20:00"), &rules, &[]), lei(3000));
+    }
+
+    #[test]
+    fn a_time_range_can_go_past_midnight() {
+        let late = PriceRule {
+            from: Some("23:00".into()),
+            until: Some("02:00".into()),
+            ..rule("late", PriceChange::Amount(lei(-500)))
+        };
+        let rules = [late];
+
+        assert_eq!(price(&screening(21, "23:30"), &rules, &[]), lei(2500));
+        assert_eq!(price(&screening(21, "01:00"), &rules, &[]), lei(2500));
+        assert_eq!(price(&screening(21, "02:00"), &rules, &[]), lei(3000));
+    }
+
+    #[test]
+    fn rules_apply_in_order_until_one_stops_them() {
+        let holiday = PriceRule {
+            holiday: Some(true),
+            stop: true,
+            ..rule("holiday", PriceChange::Percent(20))
+        };
+        let matinee = PriceRule {
+            until: Some("13:00".into()),
+            ..rule("matinee", PriceChange::Percent(-20))
+        };
+        let rules = [holiday, matinee];
+        let holidays: Vec<Holiday> = vec!["23.10".parse().unwrap()];
+
+        assert_eq!(price(&screening(23, "11:00"), &rules, &holidays), lei(3600));
+        assert_eq!(price(&screening(21, "11:00"), &rules, &holidays), lei(2400));
+
+        let explanation = explain_price(
+            &movie(),
+            &screening(21, "11:00"),
+            &rules,
+            &holidays,
+            Rounding::HalfUp,
+        )
+        .unwrap();
+        assert_eq!(explanation.base, lei(3000));
+        assert_eq!(explanation.steps.len(), 1);
+        assert_eq!(explanation.steps[0].0.name, "matinee");
+    }
+
+    #[test]
+    fn the_price_never_goes_below_zero() {
+        let free = rule("free", PriceChange::Amount(lei(-5000)));
+        assert_eq!(price(&screening(21, "18:00"), &[free], &[]), lei(0));
+    }
+
+    #[test]
+    fn a_rule_for_a_format_skips_the_others() {
+        let imax = PriceRule {
+            formats: vec![MovieFormat::Imax],
+            ..rule("IMAX", PriceChange::Amount(lei(1000)))
+        };
+        let mut imax_movie = movie();
+        imax_movie.set_format(MovieFormat::Imax).unwrap();
+        let screening = screening(21, "18:00");
+        let rules = [imax];
+
+        assert_eq!(price(&screening, &rules, &[]), lei(3000));
+        let explanation =
+            explain_price(&imax_movie, &screening, &rules, &[], Rounding::HalfUp).unwrap();
+        assert_eq!(explanation.price, lei(4000));
+    }
+}
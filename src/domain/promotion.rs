@@ -0,0 +1,314 @@
+use crate::domain::date::Date;
+use crate::domain::money::Money;
+use crate::domain::pricing::Weekday;
+use crate::domain::ticket::{parse_tickets, TicketCategory};
+use crate::repo::traits::{csv_fields, parse_field, Identifiable, Serializable};
+use crate::validators::promotion_validator::{promo_code_validator, promotion_validator};
+use std::fmt;
+use std::str::FromStr;
+
+/// What a promo code takes off a reservation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PromoBenefit {
+    /// a share of the tickets it applies to, rounded down to the ban
+    Percent(u32),
+    /// a fixed amount off the reservation
+    Amount(Money),
+    /// of the tickets it applies to, every n-th is free, the cheapest ones
+    /// first; 2 is "2-for-1"
+    FreeTicket(u32),
+}
+
+/// percent:10, amount:5.00 RON or free:2
+impl fmt::Display for PromoBenefit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PromoBenefit::Percent(percent) => write!(f, "percent:{}", percent),
+            PromoBenefit::Amount(amount) => write!(f, "amount:{}", amount.to_plain_string()),
+            PromoBenefit::FreeTicket(every) => write!(f, "free:{}", every),
+        }
+    }
+}
+
+impl FromStr for PromoBenefit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<PromoBenefit, String> {
+        let invalid = || format!("Invalid promo benefit: {}", s);
+        match s.trim().split_once(':') {
+            Some(("percent", x)) => Ok(PromoBenefit::Percent(x.parse().map_err(|_| invalid())?)),
+            Some(("amount", x)) => Ok(PromoBenefit::Amount(x.parse()?)),
+            Some(("free", x)) => Ok(PromoBenefit::FreeTicket(x.parse().map_err(|_| invalid())?)),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// A promo code marketing hands out. It can be used on reservations booked
+/// between `valid_from` and `valid_until`, both included, for screenings on
+/// its days, and only takes off the tickets of its categories. Without
+/// days or categories it is good for any.
+#[derive(Clone)]
+pub struct Promotion {
+    id: u32,
+    /// in capitals, e.g. "STUDENT10"
+    code: String,
+    benefit: PromoBenefit,
+    valid_from: Date,
+    valid_until: Date,
+    days: Vec<Weekday>,
+    categories: Vec<TicketCategory>,
+    /// reservations that can use the code, altogether and on the same card
+    max_uses: Option<u32>,
+    max_uses_per_card: Option<u32>,
+    /// whether the code can be used together with other codes
+    stackable: bool,
+    /// least the tickets must cost, after their category discounts
+    min_spend: Option<Money>,
+}
+
+impl Serializable for Promotion {
+    fn to_csv(&self) -> String {
+        let optional = |x: Option<u32>| x.map(|x| x.to_string()).unwrap_or_default();
+
+        format!(
+            "\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\"",
+            self.id,
+            self.code,
+            self.benefit,
+            self.valid_from,
+            self.valid_until,
+            join(&self.days),
+            join(&self.categories),
+            optional(self.max_uses),
+            optional(self.max_uses_per_card),
+            self.stackable,
+            self.min_spend
+                .map(|x| x.to_plain_string())
+                .unwrap_or_default()
+        )
+    }
+
+    fn from_csv_to_obj(s: &str) -> Result<Promotion, String> {
+        let mut parts = csv_fields(s);
+
+        let id: u32 = parse_field(&mut parts, "id")?;
+
+        let code: String = parse_field(&mut parts, "code")?;
+
+        let benefit: String = parse_field(&mut parts, "benefit")?;
+
+        let valid_from: String = parse_field(&mut parts, "start of validity")?;
+
+        let valid_until: String = parse_field(&mut parts, "end of validity")?;
+
+        let days: String = parse_field(&mut parts, "days")?;
+
+        let categories: String = parse_field(&mut parts, "ticket categories")?;
+
+        let max_uses: String = parse_field(&mut parts, "usage limit")?;
+
+        let max_uses_per_card: String = parse_field(&mut parts, "usage limit per card")?;
+
+        let stackable: bool = parse_field(&mut parts, "stackable flag")?;
+
+        let min_spend: String = parse_field(&mut parts, "minimum spend")?;
+
+        let mut promotion = Promotion::new(
+            id,
+            &code,
+            benefit.parse()?,
+            Date::from_string(&valid_from)?,
+            Date::from_string(&valid_until)?,
+        )?;
+        promotion.set_days(parse_days(&days)?)?;
+        promotion.set_categories(parse_tickets(&categories)?)?;
+        promotion.set_max_uses(optional_number(&max_uses, "usage limit")?)?;
+        promotion
+            .set_max_uses_per_card(optional_number(&max_uses_per_card, "usage limit per card")?)?;
+        promotion.set_stackable(stackable)?;
+        promotion.set_min_spend(match min_spend.as_str() {
+            "" => None,
+            x => Some(x.parse()?),
+        })?;
+        Ok(promotion)
+    }
+}
+
+impl Identifiable for Promotion {
+    fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+impl Promotion {
+    /// A code for any day and ticket, without limits, that can't be combined
+    /// with other codes. The code is kept in capitals.
+    pub fn new(
+        id: u32,
+        code: &str,
+        benefit: PromoBenefit,
+        valid_from: Date,
+        valid_until: Date,
+    ) -> Result<Promotion, String> {
+        let promotion = Promotion {
+            id,
+            code: normalize_code(code),
+            benefit,
+            valid_from,
+            valid_until,
+            days: vec![],
+            categories: vec![],
+            max_uses: None,
+            max_uses_per_card: None,
+            stackable: false,
+            min_spend: None,
+        };
+
+        match promotion_validator(&promotion) {
+            Result::Ok(_) => Result::Ok(promotion),
+            Result::Err(err) => Result::Err(err),
+        }
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    pub fn benefit(&self) -> PromoBenefit {
+        self.benefit
+    }
+
+    pub fn valid_from(&self) -> Date {
+        self.valid_from
+    }
+
+    pub fn valid_until(&self) -> Date {
+        self.valid_until
+    }
+
+    pub fn days(&self) -> &Vec<Weekday> {
+        &self.days
+    }
+
+    pub fn categories(&self) -> &Vec<TicketCategory> {
+        &self.categories
+    }
+
+    pub fn max_uses(&self) -> Option<u32> {
+        self.max_uses
+    }
+
+    pub fn max_uses_per_card(&self) -> Option<u32> {
+        self.max_uses_per_card
+    }
+
+    pub fn stackable(&self) -> bool {
+        self.stackable
+    }
+
+    pub fn min_spend(&self) -> Option<Money> {
+        self.min_spend
+    }
+
+    pub fn is_valid_on(&self, date: Date) -> bool {
+        self.valid_from <= date && date <= self.valid_until
+    }
+
+    pub fn applies_to(&self, category: TicketCategory) -> bool {
+        self.categories.is_empty() || self.categories.contains(&category)
+    }
+
+    pub fn set_code(&mut self, code: &str) -> Result<(), String> {
+        let code = normalize_code(code);
+        match promo_code_validator(&code) {
+            Result::Ok(_) => {
+                self.code = code;
+                Result::Ok(())
+            }
+            Result::Err(err) => Result::Err(err),
+        }
+    }
+
+    pub fn set_days(&mut self, days: Vec<Weekday>) -> Result<(), String> {
+        self.days = days;
+        Result::Ok(())
+    }
+
+    pub fn set_categories(&mut self, categories: Vec<TicketCategory>) -> Result<(), String> {
+        self.categories = categories;
+        Result::Ok(())
+    }
+
+    pub fn set_max_uses(&mut self, max_uses: Option<u32>) -> Result<(), String> {
+        self.max_uses = max_uses;
+        Result::Ok(())
+    }
+
+    pub fn set_max_uses_per_card(&mut self, max_uses: Option<u32>) -> Result<(), String> {
+        self.max_uses_per_card = max_uses;
+        Result::Ok(())
+    }
+
+    pub fn set_stackable(&mut self, stackable: bool) -> Result<(), String> {
+        self.stackable = stackable;
+        Result::Ok(())
+    }
+
+    pub fn set_min_spend(&mut self, min_spend: Option<Money>) -> Result<(), String> {
+        match min_spend {
+            Some(amount) if amount.is_negative() => {
+                Result::Err("The minimum spend can't be negative.".into())
+            }
+            _ => {
+                self.min_spend = min_spend;
+                Result::Ok(())
+            }
+        }
+    }
+}
+
+/// Codes are compared in capitals and without the spaces around them.
+pub fn normalize_code(code: &str) -> String {
+    code.trim().to_uppercase()
+}
+
+/// "tue;wed" -> [Tue, Wed]
+pub fn parse_days(days: &str) -> Result<Vec<Weekday>, String> {
+    days.split(';')
+        .filter(|day| !day.is_empty())
+        .map(|day| day.parse())
+        .collect()
+}
+
+/// "SPRING;STUDENT10" -> ["SPRING", "STUDENT10"], the way a reservation's
+/// codes are written in the data files.
+pub fn parse_codes(codes: &str) -> Vec<String> {
+    codes
+        .split(';')
+        .filter(|code| !code.is_empty())
+        .map(|code| code.to_string())
+        .collect()
+}
+
+fn join<T: fmt::Display>(items: &[T]) -> String {
+    items
+        .iter()
+        .map(|item| item.to_string())
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+fn optional_number(value: &str, name: &str) -> Result<Option<u32>, String> {
+    match value {
+        "" => Ok(None),
+        x => x
+            .parse()
+            .map(Some)
+            .map_err(|_| format!("Invalid {}: {}", name, x)),
+    }
+}
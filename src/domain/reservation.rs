@@ -1,64 +1,314 @@
 use crate::domain::date::Date;
-use crate::repo::traits::Serializable;
-use crate::validators::common_validators::id_validator;
-use crate::validators::reservation_validator::{reservation_validator, time_validator};
+use crate::domain::money::Money;
+use crate::domain::promotion::{normalize_code, parse_codes};
+use crate::domain::screening::Screening;
+use crate::domain::ticket::{parse_tickets, TicketCategory};
+use crate::repo::traits::{
+    csv_fields, parse_field, parse_optional_field, Identifiable, Serializable,
+};
+use crate::validators::context;
+use crate::validators::promotion_validator::promo_codes_validator;
+use crate::validators::reservation_validator::{
+    price_snapshot_validator, reservation_validator, seats_validator,
+};
 
+/// What a reservation cost when it was booked, so that later price changes
+/// don't change past revenue or refunds.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PriceSnapshot {
+    /// the price of one place
+    unit_price: Money,
+    /// taken off the whole reservation
+    discount: Money,
+    /// credited to the card
+    points: u32,
+    /// the part of the discount each of the reservation's promo codes took,
+    /// in the same order as the codes
+    promotions: Vec<Money>,
+    /// the part of the price paid with the card's points
+    redeemed: Money,
+    /// the part of the price paid with gift cards, after the reservation was
+    /// booked
+    gift_card: Money,
+}
+
+impl PriceSnapshot {
+    pub fn new(unit_price: Money, discount: Money, points: u32) -> PriceSnapshot {
+        PriceSnapshot {
+            unit_price,
+            discount,
+            points,
+            promotions: vec![],
+            redeemed: Money::zero(unit_price.currency()),
+            gift_card: Money::zero(unit_price.currency()),
+        }
+    }
+
+    pub fn with_promotions(self, promotions: Vec<Money>) -> PriceSnapshot {
+        PriceSnapshot { promotions, ..self }
+    }
+
+    pub fn with_redeemed(self, redeemed: Money) -> PriceSnapshot {
+        PriceSnapshot { redeemed, ..self }
+    }
+
+    pub fn with_points(self, points: u32) -> PriceSnapshot {
+        PriceSnapshot { points, ..self }
+    }
+
+    pub fn with_gift_card(self, gift_card: Money) -> PriceSnapshot {
+        PriceSnapshot { gift_card, ..self }
+    }
+
+    pub fn promotions(&self) -> &Vec<Money> {
+        &self.promotions
+    }
+
+    pub fn unit_price(&self) -> Money {
+        self.unit_price
+    }
+
+    pub fn discount(&self) -> Money {
+        self.discount
+    }
+
+    pub fn points(&self) -> u32 {
+        self.points
+    }
+
+    pub fn redeemed(&self) -> Money {
+        self.redeemed
+    }
+
+    pub fn gift_card(&self) -> Money {
+        self.gift_card
+    }
+
+    /// What `places` places cost, however they were paid.
+    pub fn total(&self, places: u32) -> Result<Money, String> {
+        self.unit_price
+            .checked_mul(places as i64)?
+            .checked_sub(self.discount)
+    }
+
+    /// What was paid in money for `places` places, gift cards included,
+    /// without the part paid with points.
+    pub fn paid(&self, places: u32) -> Result<Money, String> {
+        self.total(places)?.checked_sub(self.redeemed)
+    }
+
+    /// What is left to pay for `places` places once the gift cards have
+    /// paid their part.
+    pub fn due(&self, places: u32) -> Result<Money, String> {
+        self.paid(places)?.checked_sub(self.gift_card)
+    }
+}
+
+/// The screening of reservations read from files written before there were
+/// screenings, until `reservation_service::link_to_screenings` links them.
+pub const NO_SCREENING: u32 = 0;
+
+/// A reservation for a screening, optionally on a client card. The screening's
+/// movie, date and hour are copied onto the reservation, so the reports can be
+/// made from the reservations alone.
+///
+/// A reservation holds one or more seats, e.g. "C7" and "C8". One made
+/// without seats, before the hall had a seat map, takes a single unnumbered
+/// place. A supervised reservation lets a card holder under the movie's age
+/// rating in with a parent or guardian; the flag stays on the reservation as
+/// a record of that.
+///
+/// Every place has a ticket category; a reservation without any is all adult
+/// tickets. Reservations booked since prices were recorded keep a
+/// `PriceSnapshot`; older ones have none. Promo codes are checked and
+/// applied when the reservation is booked. A reservation on a card can be
+/// paid partly or fully with the card's points; the points stay on the
+/// reservation so they can be given back if it is cancelled.
+#[derive(Clone)]
 pub struct Reservation {
     id: u32,
+    screening_id: u32,
     movie_id: u32,
     card_id: Option<u32>,
     date: Date,
     hour: String,
+    seats: Vec<String>,
+    supervised: bool,
+    price: Option<PriceSnapshot>,
+    tickets: Vec<TicketCategory>,
+    promo_codes: Vec<String>,
+    redeemed_points: u32,
 }
 
 impl Serializable for Reservation {
     fn to_csv(&self) -> String {
-        let card_id = if self.card_id.is_none() {
-            "None".to_string()
-        } else {
-            self.card_id.unwrap().to_string()
+        let card_id = match self.card_id {
+            None => "None".to_string(),
+            Some(x) => x.to_string(),
+        };
+
+        // an empty price means the reservation has no snapshot
+        let (unit_price, discount, points, promotions, gift_card, redeemed) = match &self.price {
+            None => (
+                String::new(),
+                String::new(),
+                String::new(),
+                String::new(),
+                String::new(),
+                String::new(),
+            ),
+            Some(price) => (
+                price.unit_price.to_plain_string(),
+                price.discount.to_plain_string(),
+                price.points.to_string(),
+                price
+                    .promotions
+                    .iter()
+                    .map(|amount| amount.to_plain_string())
+                    .collect::<Vec<_>>()
+                    .join(";"),
+                price.gift_card.to_plain_string(),
+                price.redeemed.to_plain_string(),
+            ),
         };
 
         format!(
-            "\"{}\",\"{}\",\"{}\",\"{}\",\"{}\"",
+            "\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\"",
             self.id,
+            self.screening_id,
             self.movie_id,
             card_id,
-            self.date.to_string(),
-            self.hour
+            self.date,
+            self.hour,
+            self.seats.join(";"),
+            self.supervised,
+            unit_price,
+            discount,
+            points,
+            self.tickets
+                .iter()
+                .map(|ticket| ticket.to_string())
+                .collect::<Vec<_>>()
+                .join(";"),
+            self.promo_codes.join(";"),
+            promotions,
+            gift_card,
+            self.redeemed_points,
+            redeemed
         )
     }
 
-    fn from_csv_to_obj(s: &str) -> Reservation {
-        let mut parts = s.trim().trim_matches('"').split("\",\"");
+    fn from_csv_to_obj(s: &str) -> Result<Reservation, String> {
+        let mut parts = csv_fields(s);
 
-        let id: u32 = parts.next().unwrap_or("0").parse().unwrap_or(0);
+        // files written before screenings have five columns: id, movie id,
+        // card id, date and hour
+        if parts.clone().count() == 5 {
+            return Reservation::from_legacy_csv(s);
+        }
 
-        let movie_id: u32 = parts.next().unwrap_or("0").parse().unwrap_or(0);
+        let id: u32 = parse_field(&mut parts, "id")?;
 
-        let card_id = parts.next().unwrap_or("none");
+        let screening_id: u32 = parse_field(&mut parts, "screening id")?;
 
-        let date: String = parts.next().unwrap_or("01.01.2000").to_string();
-        let date = Date::from_string(&date).unwrap_or_default();
+        let movie_id: u32 = parse_field(&mut parts, "movie id")?;
 
-        let hour: String = parts.next().unwrap_or("00:00").to_string();
+        let card_id: String = parse_field(&mut parts, "card id")?;
+        let card_id = parse_card_id(&card_id)?;
 
-        Reservation {
-            id,
-            movie_id,
-            date,
-            hour,
-            card_id: match card_id.parse::<u32>() {
-                Ok(x) => Option::Some(x),
-                Err(_) => Option::None,
-            },
-        }
+        let date: String = parse_field(&mut parts, "date")?;
+        let date = Date::from_string(&date)?;
+
+        let hour: String = parse_field(&mut parts, "hour")?;
+
+        // files written before seat maps have no seats column
+        let seats: String = parse_optional_field(&mut parts, "seats", String::new())?;
+
+        let supervised: bool = parse_optional_field(&mut parts, "supervised flag", false)?;
+
+        let unit_price: String = parse_optional_field(&mut parts, "unit price", String::new())?;
+        let discount: String = parse_optional_field(&mut parts, "discount", String::new())?;
+        let points: String = parse_optional_field(&mut parts, "points", String::new())?;
+        let price = if unit_price.is_empty() {
+            None
+        } else {
+            Some(PriceSnapshot::new(
+                unit_price
+                    .parse()
+                    .map_err(|_| format!("Invalid unit price: {}", unit_price))?,
+                discount
+                    .parse()
+                    .map_err(|_| format!("Invalid discount: {}", discount))?,
+                points
+                    .parse()
+                    .map_err(|_| format!("Invalid points: {}", points))?,
+            ))
+        };
+
+        let tickets: String = parse_optional_field(&mut parts, "tickets", String::new())?;
+
+        let codes: String = parse_optional_field(&mut parts, "promo codes", String::new())?;
+        let promotions: String =
+            parse_optional_field(&mut parts, "promo discounts", String::new())?;
+        let price = match price {
+            None => None,
+            Some(price) => Some(
+                price.with_promotions(
+                    promotions
+                        .split(';')
+                        .filter(|amount| !amount.is_empty())
+                        .map(|amount| amount.parse())
+                        .collect::<Result<Vec<Money>, String>>()?,
+                ),
+            ),
+        };
+
+        let gift_card: String = parse_optional_field(&mut parts, "gift card value", String::new())?;
+        let price = match price {
+            Some(price) if !gift_card.is_empty() => Some(
+                price.with_gift_card(
+                    gift_card
+                        .parse()
+                        .map_err(|_| format!("Invalid gift card value: {}", gift_card))?,
+                ),
+            ),
+            price => price,
+        };
+
+        let redeemed_points: u32 = parse_optional_field(&mut parts, "redeemed points", 0)?;
+        let redeemed: String = parse_optional_field(&mut parts, "redeemed value", String::new())?;
+        let price = match price {
+            Some(price) if !redeemed.is_empty() => Some(
+                price.with_redeemed(
+                    redeemed
+                        .parse()
+                        .map_err(|_| format!("Invalid redeemed value: {}", redeemed))?,
+                ),
+            ),
+            price => price,
+        };
+
+        let mut reservation = Reservation::new(id, screening_id, movie_id, card_id, date, hour)?;
+        reservation.set_seats(parse_seats(&seats))?;
+        reservation.set_supervised(supervised)?;
+        reservation.set_tickets(parse_tickets(&tickets)?)?;
+        reservation.set_promo_codes(parse_codes(&codes))?;
+        reservation.set_redeemed_points(redeemed_points)?;
+        reservation.set_price(price)?;
+        Ok(reservation)
+    }
+}
+
+impl Identifiable for Reservation {
+    fn id(&self) -> u32 {
+        self.id
     }
 }
 
 impl Reservation {
     pub fn new(
         id: u32,
+        screening_id: u32,
         movie_id: u32,
         card_id: Option<u32>,
         date: Date,
@@ -66,22 +316,72 @@ impl Reservation {
     ) -> Result<Reservation, String> {
         let reservation = Reservation {
             id,
+            screening_id,
             movie_id,
             card_id,
             date,
             hour,
+            seats: vec![],
+            supervised: false,
+            price: None,
+            tickets: vec![],
+            promo_codes: vec![],
+            redeemed_points: 0,
         };
 
-        match reservation_validator(&reservation) {
+        match reservation_validator(&reservation, &*context::clock()) {
             Result::Ok(_) => Result::Ok(reservation),
             Result::Err(err) => Result::Err(err),
         }
     }
 
+    // A line in the format from before screenings. The reservation gets no
+    // screening, until it is linked to one.
+    fn from_legacy_csv(s: &str) -> Result<Reservation, String> {
+        let mut parts = s.trim().trim_matches('"').split("\",\"");
+
+        let id: u32 = parse_field(&mut parts, "id")?;
+
+        let movie_id: u32 = parse_field(&mut parts, "movie id")?;
+
+        let card_id: String = parse_field(&mut parts, "card id")?;
+        let card_id = parse_card_id(&card_id)?;
+
+        let date: String = parse_field(&mut parts, "date")?;
+        let date = Date::from_string(&date)?;
+
+        let hour: String = parse_field(&mut parts, "hour")?;
+
+        Reservation::new(id, NO_SCREENING, movie_id, card_id, date, hour)
+    }
+
+    /// A reservation of the given seats at a screening.
+    pub fn for_screening(
+        id: u32,
+        screening: &Screening,
+        card_id: Option<u32>,
+        seats: Vec<String>,
+    ) -> Result<Reservation, String> {
+        let mut reservation = Reservation::new(
+            id,
+            screening.id(),
+            screening.movie_id(),
+            card_id,
+            screening.date(),
+            screening.start().to_string(),
+        )?;
+        reservation.set_seats(seats)?;
+        Ok(reservation)
+    }
+
     pub fn id(&self) -> u32 {
         self.id
     }
 
+    pub fn screening_id(&self) -> u32 {
+        self.screening_id
+    }
+
     pub fn movie_id(&self) -> u32 {
         self.movie_id
     }
@@ -97,4 +397,265 @@ impl Reservation {
     pub fn hour(&self) -> &str {
         &self.hour
     }
+
+    pub fn seats(&self) -> &Vec<String> {
+        &self.seats
+    }
+
+    pub fn supervised(&self) -> bool {
+        self.supervised
+    }
+
+    pub fn price(&self) -> Option<&PriceSnapshot> {
+        self.price.as_ref()
+    }
+
+    pub fn tickets(&self) -> &Vec<TicketCategory> {
+        &self.tickets
+    }
+
+    /// The category of every place, adult where none was given.
+    pub fn ticket_categories(&self) -> Vec<TicketCategory> {
+        if self.tickets.is_empty() {
+            vec![TicketCategory::Adult; self.places() as usize]
+        } else {
+            self.tickets.clone()
+        }
+    }
+
+    pub fn promo_codes(&self) -> &Vec<String> {
+        &self.promo_codes
+    }
+
+    /// The points asked for, or used, to pay for the reservation.
+    pub fn redeemed_points(&self) -> u32 {
+        self.redeemed_points
+    }
+
+    /// How many places the reservation takes from the hall's capacity.
+    pub fn places(&self) -> u32 {
+        self.seats.len().max(1) as u32
+    }
+
+    pub fn set_supervised(&mut self, supervised: bool) -> Result<(), String> {
+        self.supervised = supervised;
+        Result::Ok(())
+    }
+
+    pub fn set_price(&mut self, price: Option<PriceSnapshot>) -> Result<(), String> {
+        let checked = match &price {
+            None => Result::Ok(()),
+            Some(price) => price_snapshot_validator(
+                price,
+                self.places(),
+                self.promo_codes.len(),
+                self.redeemed_points,
+            ),
+        };
+
+        match checked {
+            Result::Ok(_) => {
+                self.price = price;
+                Result::Ok(())
+            }
+            Result::Err(err) => Result::Err(err),
+        }
+    }
+
+    /// One category per place, in no particular order, e.g. an adult and a
+    /// child ticket for two seats.
+    pub fn set_tickets(&mut self, tickets: Vec<TicketCategory>) -> Result<(), String> {
+        self.tickets = tickets;
+        Result::Ok(())
+    }
+
+    /// The codes the client brought, kept in capitals.
+    pub fn set_promo_codes(&mut self, codes: Vec<String>) -> Result<(), String> {
+        let codes: Vec<String> = codes.iter().map(|code| normalize_code(code)).collect();
+        match promo_codes_validator(&codes) {
+            Result::Ok(_) => {
+                self.promo_codes = codes;
+                Result::Ok(())
+            }
+            Result::Err(err) => Result::Err(err),
+        }
+    }
+
+    /// Only a reservation on a card can be paid with points. How much they
+    /// pay for is worked out when the reservation is booked.
+    pub fn set_redeemed_points(&mut self, points: u32) -> Result<(), String> {
+        if points > 0 && self.card_id.is_none() {
+            return Result::Err("Only a reservation on a card can be paid with points.".into());
+        }
+
+        self.redeemed_points = points;
+        Result::Ok(())
+    }
+
+    pub fn set_seats(&mut self, seats: Vec<String>) -> Result<(), String> {
+        match seats_validator(&seats) {
+            Result::Ok(_) => {
+                self.seats = seats;
+                Result::Ok(())
+            }
+            Result::Err(err) => Result::Err(err),
+        }
+    }
+
+    /// Links the reservation to the screening it was made for, which must
+    /// show its movie at its date and hour.
+    pub fn set_screening(&mut self, screening: &Screening) -> Result<(), String> {
+        if screening.movie_id() != self.movie_id
+            || screening.date() != self.date
+            || hour_key(screening.start()) != hour_key(&self.hour)
+        {
+            return Result::Err(
+                "The screening doesn't show the reservation's movie at its date and hour.".into(),
+            );
+        }
+
+        self.screening_id = screening.id();
+        Result::Ok(())
+    }
+}
+
+fn parse_card_id(card_id: &str) -> Result<Option<u32>, String> {
+    match card_id {
+        "None" => Ok(None),
+        x => Ok(Some(
+            x.parse().map_err(|_| format!("Invalid card id: {}", x))?,
+        )),
+    }
+}
+
+/// "hh:mm" -> "hhmm", so hours compare correctly whatever separator was used
+pub fn hour_key(hour: &str) -> String {
+    hour.chars().filter(|c| c.is_ascii_digit()).collect()
+}
+
+/// "hh:mm" -> minutes since midnight. The hour must already be valid.
+pub fn minutes_of_day(hour: &str) -> u32 {
+    let key = hour_key(hour);
+    let hours: u32 = key[0..2].parse().unwrap_or(0);
+    let minutes: u32 = key[2..4].parse().unwrap_or(0);
+
+    hours * 60 + minutes
+}
+
+/// "C7;C8" -> ["C7", "C8"], the way seats are written in the data files.
+pub fn parse_seats(seats: &str) -> Vec<String> {
+    seats
+        .split(';')
+        .filter(|seat| !seat.is_empty())
+        .map(|seat| seat.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::money::Currency;
+
+    fn reservation(tickets: Vec<TicketCategory>) -> Reservation {
+        let date = Date::new(1, 1, 2026).unwrap();
+        let mut reservation = Reservation::new(1, 1, 1, Some(1), date, "18:00".into()).unwrap();
+        reservation
+            .set_seats(vec!["A1".into(), "A2".into()])
+            .unwrap();
+        reservation.set_tickets(tickets).unwrap();
+        reservation
+            .set_price(Some(PriceSnapshot::new(
+                Money::new(2500, Currency::Ron),
+                Money::zero(Currency::Ron),
+                2,
+            )))
+            .unwrap();
+        reservation
+    }
+
+    #[test]
+    fn csv_round_trip_keeps_an_empty_last_field() {
+        let mut reservation = reservation(vec![]);
+        reservation.set_price(None).unwrap();
+        let written = reservation.to_csv();
+        assert!(written.ends_with(",\"\""));
+
+        let read = Reservation::from_csv_to_obj(&written).unwrap();
+        assert_eq!(read.to_csv(), written);
+        assert!(read.tickets().is_empty());
+        assert!(read.price().is_none());
+    }
+
+    #[test]
+    fn csv_round_trip_keeps_the_tickets() {
+        let written = reservation(vec![TicketCategory::Adult, TicketCategory::Student]).to_csv();
+
+        let read = Reservation::from_csv_to_obj(&written).unwrap();
+        assert_eq!(read.to_csv(), written);
+        assert_eq!(
+            read.tickets(),
+            &vec![TicketCategory::Adult, TicketCategory::Student]
+        );
+    }
+
+    #[test]
+    fn csv_round_trip_keeps_the_gift_card_part() {
+        let mut reservation = reservation(vec![]);
+        let price = reservation.price().unwrap().clone();
+        reservation
+            .set_price(Some(price.with_gift_card(Money::new(1000, Currency::Ron))))
+            .unwrap();
+        let written = reservation.to_csv();
+
+        let read = Reservation::from_csv_to_obj(&written).unwrap();
+        assert_eq!(read.to_csv(), written);
+        assert_eq!(
+            read.price().unwrap().due(2).unwrap(),
+            Money::new(4000, Currency::Ron)
+        );
+    }
+
+    #[test]
+    fn gift_cards_pay_no_less_than_nothing_and_no_more_than_the_price() {
+        // the gift card part comes before the two redeemed columns
+        let written = reservation(vec![]).to_csv();
+        let start = written.rsplitn(4, ',').last().unwrap();
+        let line = |gift_card: &str| format!("{},\"{}\",\"0\",\"\"", start, gift_card);
+
+        assert_eq!(
+            Reservation::from_csv_to_obj(&line("-1.00 RON"))
+                .err()
+                .unwrap(),
+            "The part paid with gift cards can't be negative."
+        );
+        assert_eq!(
+            Reservation::from_csv_to_obj(&line("51.00 RON"))
+                .err()
+                .unwrap(),
+            "The gift cards can't pay for more than the price."
+        );
+        assert!(Reservation::from_csv_to_obj(&line("50.00 RON")).is_ok());
+    }
+
+    #[test]
+    fn reads_a_line_ending_in_an_empty_field() {
+        let line = r#""1","1","1","1","1.1.2026","18:00","","false","25.00 RON","0.00 RON","2","""#;
+
+        let read = Reservation::from_csv_to_obj(line).unwrap();
+        assert_eq!(read.price().unwrap().points(), 2);
+        assert!(read.tickets().is_empty());
+    }
+
+    #[test]
+    fn reads_a_line_from_before_screenings() {
+        let line = "\"7\",\"3\",\"None\",\"1.1.2026\",\"18:00\"";
+
+        let reservation = Reservation::from_csv_to_obj(line).unwrap();
+        assert_eq!(reservation.id(), 7);
+        assert_eq!(reservation.screening_id(), NO_SCREENING);
+        assert_eq!(reservation.movie_id(), 3);
+        assert_eq!(reservation.card_id(), None);
+        assert_eq!(reservation.hour(), "18:00");
+        assert_eq!(reservation.places(), 1);
+    }
 }
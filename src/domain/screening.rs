@@ -0,0 +1,104 @@
+use crate::domain::date::Date;
+use crate::domain::reservation::minutes_of_day;
+use crate::repo::traits::{csv_fields, parse_field, Identifiable, Serializable};
+use crate::validators::context;
+use crate::validators::screening_validator::screening_validator;
+use chrono::Datelike;
+
+/// A scheduled showing of a movie in a hall. Reservations are made for a
+/// screening rather than for a movie.
+#[derive(Clone)]
+pub struct Screening {
+    id: u32,
+    movie_id: u32,
+    hall_id: u32,
+    date: Date,
+    start: String,
+}
+
+impl Serializable for Screening {
+    fn to_csv(&self) -> String {
+        format!(
+            "\"{}\",\"{}\",\"{}\",\"{}\",\"{}\"",
+            self.id, self.movie_id, self.hall_id, self.date, self.start
+        )
+    }
+
+    fn from_csv_to_obj(s: &str) -> Result<Screening, String> {
+        let mut parts = csv_fields(s);
+
+        let id: u32 = parse_field(&mut parts, "id")?;
+
+        let movie_id: u32 = parse_field(&mut parts, "movie id")?;
+
+        let hall_id: u32 = parse_field(&mut parts, "hall id")?;
+
+        let date: String = parse_field(&mut parts, "date")?;
+        let date = Date::from_string(&date)?;
+
+        let start: String = parse_field(&mut parts, "start")?;
+
+        Screening::new(id, movie_id, hall_id, date, start)
+    }
+}
+
+impl Identifiable for Screening {
+    fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+impl Screening {
+    pub fn new(
+        id: u32,
+        movie_id: u32,
+        hall_id: u32,
+        date: Date,
+        start: String,
+    ) -> Result<Screening, String> {
+        let screening = Screening {
+            id,
+            movie_id,
+            hall_id,
+            date,
+            start,
+        };
+
+        match screening_validator(&screening, &*context::clock()) {
+            Result::Ok(_) => Result::Ok(screening),
+            Result::Err(err) => Result::Err(err),
+        }
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn movie_id(&self) -> u32 {
+        self.movie_id
+    }
+
+    pub fn hall_id(&self) -> u32 {
+        self.hall_id
+    }
+
+    pub fn date(&self) -> Date {
+        self.date
+    }
+
+    /// "hh:mm"
+    pub fn start(&self) -> &str {
+        &self.start
+    }
+
+    /// When the screening starts, in minutes from a fixed point in the past,
+    /// so that screenings on different days, or running past midnight, can be
+    /// compared.
+    pub fn start_minute(&self) -> i64 {
+        let days = match self.date.to_naive() {
+            Some(date) => date.num_days_from_ce() as i64,
+            None => 0,
+        };
+        days * 24 * 60 + minutes_of_day(&self.start) as i64
+    }
+}
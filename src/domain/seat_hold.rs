@@ -0,0 +1,88 @@
+use crate::domain::reservation::parse_seats;
+use crate::repo::traits::{csv_fields, parse_field, Identifiable, Serializable};
+use crate::validators::seat_hold_validator::seat_hold_validator;
+
+/// Seats kept aside for a screening while a client makes up their mind. The
+/// hold counts against the hall until it is confirmed into a reservation,
+/// released, or it expires.
+#[derive(Clone)]
+pub struct SeatHold {
+    id: u32,
+    screening_id: u32,
+    seats: Vec<String>,
+    /// seconds since the Unix epoch
+    expires_at: i64,
+}
+
+impl Serializable for SeatHold {
+    fn to_csv(&self) -> String {
+        format!(
+            "\"{}\",\"{}\",\"{}\",\"{}\"",
+            self.id,
+            self.screening_id,
+            self.seats.join(";"),
+            self.expires_at
+        )
+    }
+
+    fn from_csv_to_obj(s: &str) -> Result<SeatHold, String> {
+        let mut parts = csv_fields(s);
+
+        let id: u32 = parse_field(&mut parts, "id")?;
+
+        let screening_id: u32 = parse_field(&mut parts, "screening id")?;
+
+        let seats: String = parse_field(&mut parts, "seats")?;
+
+        let expires_at: i64 = parse_field(&mut parts, "expiry time")?;
+
+        SeatHold::new(id, screening_id, parse_seats(&seats), expires_at)
+    }
+}
+
+impl Identifiable for SeatHold {
+    fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+impl SeatHold {
+    pub fn new(
+        id: u32,
+        screening_id: u32,
+        seats: Vec<String>,
+        expires_at: i64,
+    ) -> Result<SeatHold, String> {
+        let hold = SeatHold {
+            id,
+            screening_id,
+            seats,
+            expires_at,
+        };
+
+        match seat_hold_validator(&hold) {
+            Result::Ok(_) => Result::Ok(hold),
+            Result::Err(err) => Result::Err(err),
+        }
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn screening_id(&self) -> u32 {
+        self.screening_id
+    }
+
+    pub fn seats(&self) -> &Vec<String> {
+        &self.seats
+    }
+
+    pub fn expires_at(&self) -> i64 {
+        self.expires_at
+    }
+
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at <= now
+    }
+}
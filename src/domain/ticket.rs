@@ -0,0 +1,77 @@
+use crate::domain::money::{Money, Rounding};
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+
+/// Who a ticket is for. Child and senior tickets depend on the card holder's
+/// age; students show their card at the entrance.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum TicketCategory {
+    #[default]
+    Adult,
+    Child,
+    Student,
+    Senior,
+}
+
+impl fmt::Display for TicketCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            TicketCategory::Adult => "adult",
+            TicketCategory::Child => "child",
+            TicketCategory::Student => "student",
+            TicketCategory::Senior => "senior",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for TicketCategory {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<TicketCategory, String> {
+        match s.trim().to_lowercase().as_str() {
+            "adult" => Ok(TicketCategory::Adult),
+            "child" => Ok(TicketCategory::Child),
+            "student" => Ok(TicketCategory::Student),
+            "senior" => Ok(TicketCategory::Senior),
+            _ => Err(format!(
+                "The ticket category must be adult, child, student or senior, not {}.",
+                s
+            )),
+        }
+    }
+}
+
+/// What a ticket category takes off the movie's price.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TicketDiscount {
+    /// a share of the price, rounded down to the ban
+    Percent(u32),
+    /// a fixed amount, at most the whole price
+    Amount(Money),
+}
+
+impl TicketDiscount {
+    /// The discount on a ticket that costs `price`.
+    pub fn on(&self, price: Money) -> Result<Money, String> {
+        match self {
+            TicketDiscount::Percent(percent) => price.percent(*percent, Rounding::Down),
+            TicketDiscount::Amount(amount) => match amount.compare(&price)? {
+                std::cmp::Ordering::Greater => Ok(price),
+                _ => Ok(*amount),
+            },
+        }
+    }
+}
+
+/// "adult;child" -> [Adult, Child], the way tickets are written in the data
+/// files.
+pub fn parse_tickets(tickets: &str) -> Result<Vec<TicketCategory>, String> {
+    tickets
+        .split(';')
+        .filter(|ticket| !ticket.is_empty())
+        .map(|ticket| ticket.parse())
+        .collect()
+}
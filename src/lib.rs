@@ -0,0 +1,21 @@
+//! Management of a cinema: movies, client cards and reservations, with the
+//! reports the ticket desks need.
+//!
+//! The crate is split in layers:
+//! - [`domain`] holds the entities, which validate themselves on construction;
+//! - [`repo`] stores them, in CSV files ([`repo::repo_file::RepoFile`]) or, with
+//!   the `sqlite` feature, in a SQLite database ([`repo::repo_sqlite::RepoSqlite`]);
+//! - [`service`] holds the business rules and works over any storage backend
+//!   through the traits in [`repo::traits`];
+//! - [`config`] reads `cinema.toml`, [`validators`] the checks behind the domain;
+//! - [`ui`] formats things for the console, such as a hall's seat map.
+
+pub mod config;
+pub mod domain;
+pub mod repo;
+pub mod service;
+pub mod ui;
+pub mod validators;
+
+#[cfg(test)]
+mod test_util;
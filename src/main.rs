@@ -1,35 +1,560 @@
-mod domain;
-mod repo;
-mod service;
-mod validators;
-
-use domain::card::Card;
-use domain::date::Date;
-use domain::movie::Movie;
-use repo::repo_file::RepoFile;
-use repo::traits::Serializable;
-use service::card_service::CardService;
+use cinema::config::{Config, DataConfig, StorageFormat};
+use cinema::domain::card::Card;
+use cinema::domain::date::Date;
+use cinema::domain::gift_card::{GiftCard, GiftCardEntry};
+use cinema::domain::hall::Hall;
+use cinema::domain::money::Money;
+use cinema::domain::movie::Movie;
+use cinema::domain::promotion::Promotion;
+use cinema::domain::reservation::Reservation;
+use cinema::domain::screening::Screening;
+use cinema::domain::seat_hold::SeatHold;
+use cinema::repo::repo_file::RepoFile;
+#[cfg(feature = "sqlite")]
+use cinema::repo::repo_sqlite::{Database, RepoSqlite, SqlEntity};
+use cinema::repo::traits::{
+    CardRepository, Identifiable, Repository, ReservationRepository, Serializable,
+};
+use cinema::service::card_service::CardService;
+use cinema::service::gift_card_service::GiftCardService;
+use cinema::service::movie_service::MovieService;
+use cinema::service::program_planner::ProgramPlanner;
+use cinema::service::reservation_service::{link_to_screenings, ReservationService};
+use cinema::service::screening_service::ScreeningService;
+use cinema::ui::schedule::render_schedule;
+use cinema::ui::seat_map::render_seat_map;
+use cinema::validators::context;
+use std::env;
+use std::fs;
+use std::process;
+
+const USAGE: &str = "Usage: cinema <command>
+
+Commands:
+  schedule                the screenings, by day and hall
+  conflicts               screenings that overlap in the same hall
+  seats <screening id>    the seat map of a screening
+  price <screening id>    a screening's ticket price and the rules behind it
+  book <screening id> <card id|none> <seat>...
+                          books seats, e.g. book 3 none A1 A2
+  hold <screening id> <seat>...
+                          holds seats for a while, e.g. hold 3 A1 A2
+  confirm <hold id> <card id|none>
+                          books the seats of a hold
+  release <hold id>       gives the seats of a hold back
+  cancel <reservation id> cancels a reservation and shows the refund
+  pay <reservation id> <gift card code>
+                          pays what is left of a reservation with a gift card
+  promotions              how often each promo code was used and what it took off
+  gift-card <code>        a gift card's balance and ledger
+  issue-gift-card <code> <value> <expires>
+                          adds a gift card, e.g. issue-gift-card GIFT-0001 100 31.12.2026
+  import-movies <file>    adds the movies in a JSON file
+  plan-week <first day> [add]
+                          proposes a week of screenings, and adds them with add
+  resolve ours|theirs     settles the changes that conflict with another
+                          process's, keeping ours or theirs";
+
+// How `resolve` settles the conflicts found when the files are loaded.
+#[derive(Clone, Copy)]
+enum Resolution {
+    Ours,
+    Theirs,
+}
+
+// Every repository the console works with, in whichever storage format the
+// configuration picks. CSV files are saved when they are dropped.
+struct Repos {
+    cards: Box<dyn CardRepository>,
+    movies: Box<dyn Repository<Movie>>,
+    halls: Box<dyn Repository<Hall>>,
+    screenings: Box<dyn Repository<Screening>>,
+    reservations: Box<dyn ReservationRepository>,
+    holds: Box<dyn Repository<SeatHold>>,
+    promotions: Box<dyn Repository<Promotion>>,
+    gift_cards: Box<dyn Repository<GiftCard>>,
+    gift_card_ledger: Box<dyn Repository<GiftCardEntry>>,
+}
+
+fn exit_with(err: String) -> ! {
+    eprintln!("{}", err);
+    process::exit(1);
+}
 
 fn main() {
-    let mut repo = RepoFile::<Card>::new("cards.csv");
-    repo.load_from_file();
+    let config = Config::load().unwrap_or_else(|err| exit_with(err));
+    context::set_limits(config.validation.clone());
+
+    let data = &config.data;
+    fs::create_dir_all(&data.dir)
+        .unwrap_or_else(|err| exit_with(format!("Unable to create {}: {}", data.dir, err)));
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    let resolution = match (args.first().map(|arg| arg.as_str()), args.get(1)) {
+        (Some("resolve"), Some(side)) if side == "ours" => Some(Resolution::Ours),
+        (Some("resolve"), Some(side)) if side == "theirs" => Some(Resolution::Theirs),
+        (Some("resolve"), _) => exit_with(USAGE.into()),
+        _ => None,
+    };
+
+    let mut repos = match data.format {
+        StorageFormat::Csv => Repos {
+            cards: Box::new(open_file::<Card>(data, &data.cards_file, resolution)),
+            movies: Box::new(open_file::<Movie>(data, &data.movies_file, resolution)),
+            halls: Box::new(open_file::<Hall>(data, &data.halls_file, resolution)),
+            screenings: Box::new(open_file::<Screening>(
+                data,
+                &data.screenings_file,
+                resolution,
+            )),
+            reservations: Box::new(open_file::<Reservation>(
+                data,
+                &data.reservations_file,
+                resolution,
+            )),
+            holds: Box::new(open_file::<SeatHold>(data, &data.holds_file, resolution)),
+            promotions: Box::new(open_file::<Promotion>(
+                data,
+                &data.promotions_file,
+                resolution,
+            )),
+            gift_cards: Box::new(open_file::<GiftCard>(
+                data,
+                &data.gift_cards_file,
+                resolution,
+            )),
+            gift_card_ledger: Box::new(open_file::<GiftCardEntry>(
+                data,
+                &data.gift_card_ledger_file,
+                resolution,
+            )),
+        },
+        #[cfg(feature = "sqlite")]
+        StorageFormat::Sqlite => {
+            let database = Database::open(&data.path(&data.database_file))
+                .unwrap_or_else(|err| exit_with(format!("Unable to open the database: {}", err)));
+            Repos {
+                cards: Box::new(open_table::<Card>(&database)),
+                movies: Box::new(open_table::<Movie>(&database)),
+                halls: Box::new(open_table::<Hall>(&database)),
+                screenings: Box::new(open_table::<Screening>(&database)),
+                reservations: Box::new(open_table::<Reservation>(&database)),
+                holds: Box::new(open_table::<SeatHold>(&database)),
+                promotions: Box::new(open_table::<Promotion>(&database)),
+                gift_cards: Box::new(open_table::<GiftCard>(&database)),
+                gift_card_ledger: Box::new(open_table::<GiftCardEntry>(&database)),
+            }
+        }
+        #[cfg(not(feature = "sqlite"))]
+        StorageFormat::Sqlite => exit_with("This build does not support SQLite storage.".into()),
+    };
+
+    // reservations from before screenings get theirs, as the SQLite
+    // migration gave them
+    if let Err(err) = link_to_screenings(
+        &mut *repos.reservations,
+        &mut *repos.screenings,
+        &mut *repos.halls,
+    ) {
+        exit_with(format!(
+            "Unable to link the reservations to screenings: {}",
+            err
+        ));
+    }
+
+    let result = run(&config, &mut repos, &args);
+    // the files are saved before the process exits, whatever the result
+    drop(repos);
+
+    match result {
+        Ok(output) => print!("{}", output),
+        Err(err) => exit_with(err),
+    }
+}
+
+// Changes left over from a run whose save ran into another process's are
+// reported, or settled if the command is `resolve`.
+fn open_file<T>(data: &DataConfig, filename: &str, resolution: Option<Resolution>) -> RepoFile<T>
+where
+    T: Serializable + Identifiable,
+{
+    let mut repo = RepoFile::<T>::new(&data.path(filename));
+    repo.set_strict(data.strict);
+    repo.set_flush_policy(data.flush_policy());
+    if let Err(err) = repo.load_from_file() {
+        exit_with(format!("Unable to load {}: {}", filename, err));
+    }
+
+    let conflicts = repo.conflicts();
+    if conflicts.is_empty() {
+        return repo;
+    }
+    let ids: Vec<String> = conflicts.iter().map(|id| id.to_string()).collect();
+    let settled = match resolution {
+        None => {
+            eprintln!(
+                "Warning: {} has changes to the IDs {} that conflict with another process's. \
+                 Run `cinema resolve ours` or `cinema resolve theirs`.",
+                filename,
+                ids.join(", ")
+            );
+            return repo;
+        }
+        Some(Resolution::Ours) => {
+            repo.keep_ours();
+            "ours"
+        }
+        Some(Resolution::Theirs) => {
+            if let Err(err) = repo.keep_theirs() {
+                exit_with(format!("Unable to load {}: {}", filename, err));
+            }
+            "theirs"
+        }
+    };
+    if let Err(err) = repo.save_to_file() {
+        exit_with(format!("Unable to save {}: {}", filename, err));
+    }
+    eprintln!(
+        "{}: kept {} for the IDs {}",
+        filename,
+        settled,
+        ids.join(", ")
+    );
+
+    repo
+}
+
+#[cfg(feature = "sqlite")]
+fn open_table<T>(database: &Database) -> RepoSqlite<T>
+where
+    T: SqlEntity,
+{
+    RepoSqlite::<T>::new(database)
+        .unwrap_or_else(|err| exit_with(format!("Unable to load the {} table: {}", T::TABLE, err)))
+}
+
+fn run(config: &Config, repos: &mut Repos, args: &[String]) -> Result<String, String> {
+    let card_service = CardService::new(&mut *repos.cards);
+    for (id, err) in card_service.rule_violations() {
+        eprintln!("Warning: card {} needs fixing: {}", id, err.trim());
+    }
+
+    match args.first().map(|arg| arg.as_str()) {
+        Some("schedule") => Ok(render_schedule(
+            repos.screenings.get_all(),
+            repos.movies.get_all(),
+            repos.halls.get_all(),
+        )),
+        Some("conflicts") => {
+            let mut text = String::new();
+            for (first, second) in screening_service(config, repos).conflicts()? {
+                text.push_str(&format!(
+                    "Screening {} ({} {}) overlaps screening {} ({} {})\n",
+                    first.id(),
+                    first.date(),
+                    first.start(),
+                    second.id(),
+                    second.date(),
+                    second.start()
+                ));
+            }
+            Ok(text)
+        }
+        Some("seats") => seat_map(config, repos, id_arg(args, 1, "screening id")?),
+        Some("price") => {
+            let screening_id = id_arg(args, 1, "screening id")?;
+            let explanation = reservation_service(config, repos).explain_price(screening_id)?;
+            Ok(format!("{}\n", explanation))
+        }
+        Some("book") => book(config, repos, args),
+        Some("hold") => {
+            let screening_id = id_arg(args, 1, "screening id")?;
+            let hold = reservation_service(config, repos).hold(screening_id, args[2..].to_vec())?;
+            Ok(format!(
+                "Hold {}: {} for {} minutes\n",
+                hold.id(),
+                hold.seats().join(" "),
+                config.holds.timeout_secs.div_ceil(60)
+            ))
+        }
+        Some("confirm") => confirm(config, repos, args),
+        Some("release") => {
+            let hold_id = id_arg(args, 1, "hold id")?;
+            reservation_service(config, repos).release(hold_id)?;
+            Ok(format!("Hold {} released\n", hold_id))
+        }
+        Some("cancel") => {
+            let reservation_id = id_arg(args, 1, "reservation id")?;
+            let refund = reservation_service(config, repos).cancel(reservation_id)?;
+            let mut text = format!(
+                "Reservation {} cancelled, refund: {}\n",
+                reservation_id, refund.money
+            );
+            if !refund.gift_cards.is_zero() {
+                text.push_str(&format!("Back onto gift cards: {}\n", refund.gift_cards));
+            }
+            Ok(text)
+        }
+        Some("pay") => {
+            let reservation_id = id_arg(args, 1, "reservation id")?;
+            let code = match args.get(2) {
+                Some(code) => code,
+                None => return Err(format!("Missing the gift card code.\n\n{}", USAGE)),
+            };
+            let mut service = reservation_service(config, repos);
+            let paid = service.pay_with_gift_card(reservation_id, code)?;
+            Ok(format!(
+                "The gift card paid {} of reservation {}\n",
+                paid, reservation_id
+            ))
+        }
+        Some("promotions") => {
+            let mut text = String::new();
+            for (promotion, uses, discount) in
+                reservation_service(config, repos).promotion_redemptions()?
+            {
+                text.push_str(&format!(
+                    "{}: used {} times, {} off\n",
+                    promotion.code(),
+                    uses,
+                    discount
+                ));
+            }
+            Ok(text)
+        }
+        Some("gift-card") => {
+            let code = match args.get(1) {
+                Some(code) => code,
+                None => return Err(format!("Missing the gift card code.\n\n{}", USAGE)),
+            };
+            let service =
+                GiftCardService::new(&mut *repos.gift_cards, &mut *repos.gift_card_ledger);
+            let mut text = format!("Balance: {}\n", service.balance(code)?);
+            for entry in service.ledger(code)? {
+                let reservation = match entry.reservation_id() {
+                    Some(id) => format!(", reservation {}", id),
+                    None => String::new(),
+                };
+                text.push_str(&format!(
+                    "{} {} {}{}\n",
+                    entry.date(),
+                    entry.movement(),
+                    entry.amount(),
+                    reservation
+                ));
+            }
+            Ok(text)
+        }
+        Some("issue-gift-card") => issue_gift_card(repos, args),
+        // settled while the files were loaded
+        Some("resolve") => Ok(String::new()),
+        Some("import-movies") => {
+            let filename = match args.get(1) {
+                Some(filename) => filename,
+                None => return Err(format!("Missing the file.\n\n{}", USAGE)),
+            };
+            let json = fs::read_to_string(filename)
+                .map_err(|err| format!("Unable to read {}: {}", filename, err))?;
+            let added = movie_service(config, repos).import_json(&json)?;
+            Ok(format!("{} movies added\n", added))
+        }
+        Some("plan-week") => plan_week(config, repos, args),
+        _ => Err(USAGE.into()),
+    }
+}
+
+// The services, set up the way the configuration says.
+
+fn reservation_service<'a>(config: &Config, repos: &'a mut Repos) -> ReservationService<'a> {
+    let mut service = ReservationService::new(
+        &mut *repos.reservations,
+        &mut *repos.holds,
+        &*repos.screenings,
+        &*repos.halls,
+        &*repos.movies,
+        &mut *repos.cards,
+        &*repos.promotions,
+    );
+    service.set_loyalty(config.loyalty.clone());
+    service.set_holds(config.holds.clone());
+    service.set_pricing(config.pricing.clone());
+    service.set_tickets(config.tickets.clone());
+    service.set_gift_cards(&mut *repos.gift_cards, &mut *repos.gift_card_ledger);
+    service
+}
 
-    let mut service = CardService::new(&mut repo);
+fn screening_service<'a>(config: &Config, repos: &'a mut Repos) -> ScreeningService<'a> {
+    let mut service = ScreeningService::new(&mut *repos.screenings, &*repos.movies, &*repos.halls);
+    service.set_schedule(config.schedule.clone());
+    service
+}
 
+fn movie_service<'a>(config: &Config, repos: &'a mut Repos) -> MovieService<'a> {
+    let mut service = MovieService::new(&mut *repos.movies, &*repos.screenings);
+    service.set_config(config.movies.clone());
+    service.set_schedule(config.schedule.clone());
     service
-        .add(
-            Card::new(
-                1,
-                "Adrian",
-                "Placinta",
-                "1234567890123",
-                Date::new(26, 4, 2000).unwrap_or_default(),
-                Date::new(30, 5, 2003).unwrap_or_default(),
-                0,
-            )
-            .unwrap(),
-        )
-        .unwrap();
-
-    repo.save_to_file();
+}
+
+fn id_arg(args: &[String], position: usize, name: &str) -> Result<u32, String> {
+    match args.get(position) {
+        None => Err(format!("Missing the {}.\n\n{}", name, USAGE)),
+        Some(arg) => arg
+            .parse()
+            .map_err(|_| format!("Invalid {}: {}", name, arg)),
+    }
+}
+
+fn book(config: &Config, repos: &mut Repos, args: &[String]) -> Result<String, String> {
+    let screening_id = id_arg(args, 1, "screening id")?;
+    let card_id = match args.get(2).map(|arg| arg.as_str()) {
+        None => return Err(format!("Missing the card id.\n\n{}", USAGE)),
+        Some("none") => None,
+        Some(_) => Some(id_arg(args, 2, "card id")?),
+    };
+    let seats: Vec<String> = args[3..].to_vec();
+
+    let screening = match repos
+        .screenings
+        .get_all()
+        .iter()
+        .find(|s| s.id() == screening_id)
+    {
+        Some(screening) => screening.clone(),
+        None => return Err("There is no screening with that ID.".into()),
+    };
+    let id = match repos.reservations.get_all().iter().map(|r| r.id()).max() {
+        Some(id) => id + 1,
+        None => 1,
+    };
+    let reservation = Reservation::for_screening(id, &screening, card_id, seats)?;
+
+    let mut service = reservation_service(config, repos);
+    let price = service.quote(&reservation)?;
+    let text = match service.add(reservation)? {
+        None => format!("Reservation {} booked, {}\n", id, price),
+        Some(points) => format!(
+            "Reservation {} booked, {}; the card now has {} points\n",
+            id, price, points
+        ),
+    };
+
+    Ok(text)
+}
+
+fn confirm(config: &Config, repos: &mut Repos, args: &[String]) -> Result<String, String> {
+    let hold_id = id_arg(args, 1, "hold id")?;
+    let card_id = match args.get(2).map(|arg| arg.as_str()) {
+        None => return Err(format!("Missing the card id.\n\n{}", USAGE)),
+        Some("none") => None,
+        Some(_) => Some(id_arg(args, 2, "card id")?),
+    };
+
+    let hold = match repos.holds.get_all().iter().find(|h| h.id() == hold_id) {
+        Some(hold) => hold.clone(),
+        None => return Err("There is no hold with that ID, or it has expired.".into()),
+    };
+    let screening = match repos
+        .screenings
+        .get_all()
+        .iter()
+        .find(|s| s.id() == hold.screening_id())
+    {
+        Some(screening) => screening.clone(),
+        None => return Err("There is no screening with that ID.".into()),
+    };
+    let id = match repos.reservations.get_all().iter().map(|r| r.id()).max() {
+        Some(id) => id + 1,
+        None => 1,
+    };
+    let reservation = Reservation::for_screening(id, &screening, card_id, hold.seats().clone())?;
+
+    let mut service = reservation_service(config, repos);
+    let price = service.quote(&reservation)?;
+    let text = match service.confirm(hold_id, reservation)? {
+        None => format!("Reservation {} booked, {}\n", id, price),
+        Some(points) => format!(
+            "Reservation {} booked, {}; the card now has {} points\n",
+            id, price, points
+        ),
+    };
+
+    Ok(text)
+}
+
+// The plan is only shown, unless the command ends with `add`.
+fn plan_week(config: &Config, repos: &mut Repos, args: &[String]) -> Result<String, String> {
+    let first_day = match args.get(1) {
+        Some(day) => Date::from_string(day)?,
+        None => return Err(format!("Missing the first day.\n\n{}", USAGE)),
+    };
+    let add = match args.get(2).map(|arg| arg.as_str()) {
+        None => false,
+        Some("add") => true,
+        Some(arg) => return Err(format!("Unknown option: {}\n\n{}", arg, USAGE)),
+    };
+
+    let mut planner = ProgramPlanner::new(
+        &*repos.movies,
+        &*repos.halls,
+        &*repos.screenings,
+        &*repos.reservations,
+    );
+    planner.set_schedule(config.schedule.clone());
+    let plan = planner.plan_week(first_day)?;
+
+    let text = render_schedule(&plan, repos.movies.get_all(), repos.halls.get_all());
+    if !add {
+        return Ok(text);
+    }
+    let added = screening_service(config, repos).add_plan(plan)?;
+    Ok(format!("{}{} screenings added\n", text, added))
+}
+
+fn issue_gift_card(repos: &mut Repos, args: &[String]) -> Result<String, String> {
+    let (code, value, expires) = match args.get(1..4) {
+        Some([code, value, expires]) => (code, value, expires),
+        _ => {
+            return Err(format!(
+                "Missing the code, value or expiry date.\n\n{}",
+                USAGE
+            ))
+        }
+    };
+    let value: Money = value.parse()?;
+    let expires = Date::from_string(expires)?;
+
+    let id = match repos.gift_cards.get_all().iter().map(|g| g.id()).max() {
+        Some(id) => id + 1,
+        None => 1,
+    };
+    let gift_card = GiftCard::new(id, code, value, expires)?;
+    let text = format!(
+        "Gift card {} issued, {} until {}\n",
+        gift_card.code(),
+        gift_card.value(),
+        expires
+    );
+    GiftCardService::new(&mut *repos.gift_cards, &mut *repos.gift_card_ledger).issue(gift_card)?;
+
+    Ok(text)
+}
+
+fn seat_map(config: &Config, repos: &mut Repos, screening_id: u32) -> Result<String, String> {
+    let hall_id = match repos
+        .screenings
+        .get_all()
+        .iter()
+        .find(|s| s.id() == screening_id)
+    {
+        Some(screening) => screening.hall_id(),
+        None => return Err("There is no screening with that ID.".into()),
+    };
+
+    let taken = reservation_service(config, repos).taken_seats(screening_id);
+
+    match repos.halls.get_all().iter().find(|h| h.id() == hall_id) {
+        Some(hall) => Ok(render_seat_map(hall, &taken)),
+        None => Err("The screening's hall doesn't exist.".into()),
+    }
 }
@@ -0,0 +1,70 @@
+use std::fmt;
+
+/// Why a `RepoFile` could not be loaded or saved.
+#[derive(Debug)]
+pub enum RepoError {
+    // the file exists (or should be created) but could not be read or written
+    Io {
+        filename: String,
+        source: std::io::Error,
+    },
+    // a line of the file could not be turned back into an object
+    Corrupt {
+        filename: String,
+        line: usize,
+        reason: String,
+    },
+    // another process changed the file in a way that can't be merged with
+    // our own changes, such as adding a different element with the same id
+    Conflict {
+        filename: String,
+        reason: String,
+    },
+}
+
+impl RepoError {
+    pub fn io(filename: &str, source: std::io::Error) -> Self {
+        RepoError::Io {
+            filename: filename.to_string(),
+            source,
+        }
+    }
+}
+
+impl fmt::Display for RepoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RepoError::Io { filename, source } => write!(f, "{}: {}", filename, source),
+            RepoError::Corrupt {
+                filename,
+                line,
+                reason,
+            } => write!(f, "{}, line {}: {}", filename, line, reason),
+            RepoError::Conflict { filename, reason } => write!(f, "{}: {}", filename, reason),
+        }
+    }
+}
+
+impl std::error::Error for RepoError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RepoError::Io { source, .. } => Some(source),
+            RepoError::Corrupt { .. } | RepoError::Conflict { .. } => None,
+        }
+    }
+}
+
+impl From<RepoError> for String {
+    fn from(err: RepoError) -> String {
+        err.to_string()
+    }
+}
+
+/// What load_from_file found on disk.
+#[derive(Debug)]
+pub enum LoadStatus {
+    // no data file yet, the repository starts out empty
+    NotFound,
+    // the corrupt lines that were skipped, always empty in strict mode
+    Loaded { skipped: Vec<RepoError> },
+}
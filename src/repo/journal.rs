@@ -0,0 +1,345 @@
+use super::error::RepoError;
+use super::traits::{parse_field, Identifiable, Serializable};
+use std::fs::{self, File, OpenOptions};
+use std::io::{ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+/// One mutation made since the last full save. Entries refer to elements by
+/// id rather than by position, so replaying a journal on top of a file that
+/// already contains some of its changes gives the same result.
+///
+/// Before the first mutation of an element, a `Base` entry records its line
+/// as it was read, None if it did not exist, so that a replay can tell
+/// whether another process changed it in the meantime.
+pub enum JournalEntry<T> {
+    Add(T),
+    Update(u32, T),
+    Remove(u32),
+    Base(u32, Option<String>),
+}
+
+impl<T> JournalEntry<T>
+where
+    T: Serializable + Identifiable,
+{
+    pub fn to_line(&self) -> String {
+        match self {
+            JournalEntry::Add(elem) => format!("add\t{}", elem.to_csv()),
+            JournalEntry::Update(id, elem) => format!("update\t{}\t{}", id, elem.to_csv()),
+            JournalEntry::Remove(id) => format!("remove\t{}", id),
+            JournalEntry::Base(id, None) => format!("base\t{}", id),
+            JournalEntry::Base(id, Some(line)) => format!("base\t{}\t{}", id, line),
+        }
+    }
+
+    pub fn from_line(line: &str) -> Result<JournalEntry<T>, String> {
+        let mut parts = line.splitn(3, '\t');
+
+        match parts.next() {
+            Some("add") => Ok(JournalEntry::Add(T::from_csv_to_obj(
+                parts.next().unwrap_or(""),
+            )?)),
+            Some("update") => {
+                let id = parse_field(&mut parts, "id")?;
+                Ok(JournalEntry::Update(
+                    id,
+                    T::from_csv_to_obj(parts.next().unwrap_or(""))?,
+                ))
+            }
+            Some("remove") => Ok(JournalEntry::Remove(parse_field(&mut parts, "id")?)),
+            Some("base") => {
+                let id = parse_field(&mut parts, "id")?;
+                Ok(JournalEntry::Base(id, parts.next().map(|x| x.to_string())))
+            }
+            _ => Err(format!("Unknown journal entry: {}", line)),
+        }
+    }
+
+    /// Whether a journal line is a `Base` entry rather than a change.
+    pub fn is_base_line(line: &str) -> bool {
+        line.starts_with("base\t")
+    }
+
+    /// The id of the element the entry is about.
+    pub fn id(&self) -> u32 {
+        match self {
+            JournalEntry::Add(elem) => elem.id(),
+            JournalEntry::Update(id, _) | JournalEntry::Remove(id) | JournalEntry::Base(id, _) => {
+                *id
+            }
+        }
+    }
+
+    pub fn apply(self, elems: &mut Vec<T>) {
+        match self {
+            JournalEntry::Add(elem) => match elems.iter().position(|x| x.id() == elem.id()) {
+                Some(index) => elems[index] = elem,
+                None => elems.push(elem),
+            },
+            JournalEntry::Update(id, elem) => match elems.iter().position(|x| x.id() == id) {
+                Some(index) => elems[index] = elem,
+                None => JournalEntry::Add(elem).apply(elems),
+            },
+            JournalEntry::Remove(id) => elems.retain(|x| x.id() != id),
+            JournalEntry::Base(_, _) => {}
+        }
+    }
+}
+
+/// Every process writes its own journal, `<data file>.journal.<pid>`, and keeps
+/// it locked for as long as it is open. A journal nobody holds a lock on was
+/// left behind by a process that died before saving. If a dead process with
+/// the same pid left one behind, ours gets a `.<n>` suffix instead.
+pub struct Journal {
+    data_filename: String,
+    filename: String,
+    file: Option<File>,
+}
+
+/// A journal left behind by a dead process, still locked by us so that no
+/// other process recovers it at the same time.
+pub struct Orphan<T> {
+    path: PathBuf,
+    pub entries: Vec<Result<JournalEntry<T>, RepoError>>,
+    _file: File,
+}
+
+impl<T> Orphan<T> {
+    pub fn remove(self) -> Result<(), RepoError> {
+        remove_file(&self.path)
+    }
+}
+
+impl Journal {
+    pub fn new(data_filename: &str) -> Self {
+        Journal {
+            data_filename: data_filename.to_string(),
+            filename: format!("{}.journal.{}", data_filename, process::id()),
+            file: None,
+        }
+    }
+
+    pub fn filename(&self) -> &str {
+        &self.filename
+    }
+
+    pub fn append<T>(&mut self, entry: &JournalEntry<T>) -> Result<(), RepoError>
+    where
+        T: Serializable + Identifiable,
+    {
+        self.append_line(&entry.to_line())
+    }
+
+    pub fn append_line(&mut self, line: &str) -> Result<(), RepoError> {
+        if self.file.is_none() {
+            self.file = Some(self.create()?);
+        }
+
+        let f = self.file.as_mut().unwrap();
+        writeln!(f, "{}", line)
+            .and_then(|_| f.sync_data())
+            .map_err(|err| RepoError::io(&self.filename, err))
+    }
+
+    /// Called once everything in the journal made it into the data file.
+    pub fn clear(&mut self) -> Result<(), RepoError> {
+        match &self.file {
+            None => Ok(()),
+            Some(f) => f
+                .set_len(0)
+                .and_then(|_| f.sync_data())
+                .map_err(|err| RepoError::io(&self.filename, err)),
+        }
+    }
+
+    /// Replaces the journal's entries, for when some of them were given up.
+    pub fn rewrite(&mut self, lines: &[String]) -> Result<(), RepoError> {
+        let f = match self.file.as_mut() {
+            None => return Ok(()),
+            Some(f) => f,
+        };
+
+        let mut content = String::new();
+        for line in lines {
+            content.push_str(line);
+            content.push('\n');
+        }
+
+        f.set_len(0)
+            .and_then(|_| f.write_all(content.as_bytes()))
+            .and_then(|_| f.sync_data())
+            .map_err(|err| RepoError::io(&self.filename, err))
+    }
+
+    pub fn has_orphans(&self) -> Result<bool, RepoError> {
+        for path in self.other_journals()? {
+            if try_lock(&path)?.is_some() {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// The journals of dead processes, oldest first. A torn last line, from a
+    /// crash in the middle of an append, is ignored. Other lines that can't be
+    /// read are returned as errors next to the good entries.
+    pub fn take_orphans<T>(&self) -> Result<Vec<Orphan<T>>, RepoError>
+    where
+        T: Serializable + Identifiable,
+    {
+        let mut orphans = vec![];
+        for path in self.other_journals()? {
+            let mut f = match try_lock(&path)? {
+                Some(f) => f,
+                None => continue,
+            };
+
+            let filename = path.display().to_string();
+            let mut content = String::new();
+            f.read_to_string(&mut content)
+                .map_err(|err| RepoError::io(&filename, err))?;
+
+            let mut lines: Vec<&str> = content.split('\n').collect();
+            // whatever follows the last newline was never completely written
+            lines.pop();
+
+            let entries = lines
+                .into_iter()
+                .enumerate()
+                .map(|(i, line)| {
+                    JournalEntry::from_line(line).map_err(|reason| RepoError::Corrupt {
+                        filename: filename.clone(),
+                        line: i + 1,
+                        reason,
+                    })
+                })
+                .collect();
+
+            let modified = f.metadata().and_then(|m| m.modified()).ok();
+            orphans.push((
+                modified,
+                Orphan {
+                    path,
+                    entries,
+                    _file: f,
+                },
+            ));
+        }
+
+        orphans.sort_by_key(|(modified, _)| *modified);
+        Ok(orphans.into_iter().map(|(_, orphan)| orphan).collect())
+    }
+
+    fn other_journals(&self) -> Result<Vec<PathBuf>, RepoError> {
+        let data_path = Path::new(&self.data_filename);
+        let dir = match data_path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let prefix = match data_path.file_name() {
+            Some(name) => format!("{}.journal", name.to_string_lossy()),
+            None => return Ok(vec![]),
+        };
+
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(vec![]),
+            Err(err) => return Err(RepoError::io(&dir.display().to_string(), err)),
+        };
+
+        // until ours exists, a journal under our name belongs to a dead process
+        let own = match self.file {
+            Some(_) => Path::new(&self.filename).file_name(),
+            None => None,
+        };
+        let mut paths = vec![];
+        for entry in entries {
+            let entry = entry.map_err(|err| RepoError::io(&dir.display().to_string(), err))?;
+            let name = entry.file_name();
+            if name.to_string_lossy().starts_with(&prefix) && Some(name.as_os_str()) != own {
+                paths.push(entry.path());
+            }
+        }
+
+        Ok(paths)
+    }
+
+    // The journal is created and locked under a name no other process looks
+    // at, and only then linked under its real name, so nobody can take it
+    // for an orphan in between. Linking never replaces an existing journal.
+    fn create(&mut self) -> Result<File, RepoError> {
+        let tmp_filename = format!("{}.new-journal.{}", self.data_filename, process::id());
+        // left behind by a dead process with our pid, already linked or empty
+        remove_file(Path::new(&tmp_filename))?;
+
+        let f = OpenOptions::new()
+            .create_new(true)
+            .read(true)
+            .append(true)
+            .open(&tmp_filename)
+            .map_err(|err| RepoError::io(&tmp_filename, err))?;
+        f.lock().map_err(|err| RepoError::io(&tmp_filename, err))?;
+
+        let base_filename = format!("{}.journal.{}", self.data_filename, process::id());
+        let mut filename = base_filename.clone();
+        let mut n = 0;
+        loop {
+            match fs::hard_link(&tmp_filename, &filename) {
+                Ok(_) => break,
+                Err(err) if err.kind() == ErrorKind::AlreadyExists => {
+                    n += 1;
+                    filename = format!("{}.{}", base_filename, n);
+                }
+                Err(err) => {
+                    let _ = fs::remove_file(&tmp_filename);
+                    return Err(RepoError::io(&filename, err));
+                }
+            }
+        }
+
+        remove_file(Path::new(&tmp_filename))?;
+        self.filename = filename;
+        Ok(f)
+    }
+}
+
+impl Drop for Journal {
+    fn drop(&mut self) {
+        // an empty journal has nothing left to recover
+        let empty = match &self.file {
+            Some(f) => f.metadata().map(|m| m.len() == 0).unwrap_or(false),
+            None => false,
+        };
+
+        if empty {
+            let _ = fs::remove_file(&self.filename);
+        }
+    }
+}
+
+// None if a live process holds the lock.
+fn try_lock(path: &Path) -> Result<Option<File>, RepoError> {
+    let filename = path.display().to_string();
+    let f = match OpenOptions::new().read(true).write(true).open(path) {
+        Ok(f) => f,
+        // recovered and removed by someone else in the meantime
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(RepoError::io(&filename, err)),
+    };
+
+    match f.try_lock() {
+        Ok(()) => Ok(Some(f)),
+        Err(fs::TryLockError::WouldBlock) => Ok(None),
+        Err(fs::TryLockError::Error(err)) => Err(RepoError::io(&filename, err)),
+    }
+}
+
+fn remove_file(path: &Path) -> Result<(), RepoError> {
+    match fs::remove_file(path) {
+        Ok(_) => Ok(()),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(RepoError::io(&path.display().to_string(), err)),
+    }
+}
@@ -1,2 +1,9 @@
+//! Storage backends and the traits the services use to reach them.
+
+pub mod error;
+pub mod journal;
+pub mod movie_json;
 pub mod repo_file;
+#[cfg(feature = "sqlite")]
+pub mod repo_sqlite;
 pub mod traits;
@@ -0,0 +1,114 @@
+use crate::domain::money::{Currency, Money};
+use crate::domain::movie::{AgeRating, Movie, MovieFormat, DEFAULT_RUNTIME};
+use serde::{Deserialize, Serialize};
+
+// A movie as it appears in JSON. Only the fields the first CSV files had
+// are required, the others get the same defaults as in old CSV files.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MovieRecord {
+    id: u32,
+    title: String,
+    release_year: u32,
+    price: Price,
+    in_program: bool,
+    #[serde(default = "default_runtime")]
+    runtime: u32,
+    #[serde(default)]
+    genres: Vec<String>,
+    #[serde(default)]
+    age_rating: Option<String>,
+    #[serde(default)]
+    language: Option<String>,
+    #[serde(default)]
+    subtitles: Option<String>,
+    #[serde(default)]
+    format: Option<String>,
+}
+
+// Older exports have a whole number of lei, newer ones a string such as
+// "25.50 RON".
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum Price {
+    Lei(u32),
+    Text(String),
+}
+
+impl Price {
+    fn to_money(&self) -> Result<Money, String> {
+        match self {
+            Price::Lei(lei) => Money::from_major(*lei as i64, Currency::Ron),
+            Price::Text(text) => text.parse(),
+        }
+    }
+}
+
+fn default_runtime() -> u32 {
+    DEFAULT_RUNTIME
+}
+
+impl MovieRecord {
+    fn from_movie(movie: &Movie) -> MovieRecord {
+        MovieRecord {
+            id: movie.id(),
+            title: movie.title().to_string(),
+            release_year: movie.release_year(),
+            price: Price::Text(movie.price().to_plain_string()),
+            in_program: movie.in_program(),
+            runtime: movie.runtime(),
+            genres: movie.genres().clone(),
+            age_rating: Some(movie.age_rating().to_string()),
+            language: movie.language().map(|x| x.to_string()),
+            subtitles: movie.subtitles().map(|x| x.to_string()),
+            format: Some(movie.format().to_string()),
+        }
+    }
+
+    fn into_movie(self) -> Result<Movie, String> {
+        let mut movie = Movie::new(
+            self.id,
+            &self.title,
+            self.release_year,
+            self.price.to_money()?,
+            self.in_program,
+            self.runtime,
+        )?;
+
+        movie.set_genres(self.genres)?;
+        movie.set_age_rating(match self.age_rating {
+            None => AgeRating::default(),
+            Some(x) => x.parse()?,
+        })?;
+        movie.set_language(self.language)?;
+        movie.set_subtitles(self.subtitles)?;
+        movie.set_format(match self.format {
+            None => MovieFormat::default(),
+            Some(x) => x.parse()?,
+        })?;
+
+        Ok(movie)
+    }
+}
+
+/// A JSON array with one object per movie.
+pub fn movies_to_json(movies: &[Movie]) -> Result<String, String> {
+    let records: Vec<MovieRecord> = movies.iter().map(MovieRecord::from_movie).collect();
+    serde_json::to_string_pretty(&records).map_err(|err| err.to_string())
+}
+
+/// Reads what `movies_to_json` writes. Every movie is validated; the first
+/// one that is not valid makes the whole import fail.
+pub fn movies_from_json(json: &str) -> Result<Vec<Movie>, String> {
+    let records: Vec<MovieRecord> = serde_json::from_str(json).map_err(|err| err.to_string())?;
+
+    records
+        .into_iter()
+        .enumerate()
+        .map(|(i, record)| {
+            record
+                .into_movie()
+                .map_err(|err| format!("Movie number {}: {}", i + 1, err))
+        })
+        .collect()
+}
@@ -1,83 +1,544 @@
-use super::traits::Serializable;
-use std::fs::File;
-use std::io::{BufRead, BufReader, Write};
+use super::error::{LoadStatus, RepoError};
+use super::journal::{Journal, JournalEntry};
+use super::traits::{
+    CardRepository, Identifiable, Repository, ReservationRepository, Serializable,
+};
+use crate::domain::card::Card;
+use crate::domain::date::Date;
+use crate::domain::reservation::{hour_key, Reservation};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::{ErrorKind, Write};
+use std::path::Path;
+use std::process;
+use std::time::{Duration, Instant};
 
+/// A repository kept in memory and saved to a CSV file, one element per line.
+///
+/// Changes are journaled before they are applied and saves are atomic, so a
+/// crash loses nothing, and several processes can share the same file.
 pub struct RepoFile<T>
 where
-    T: Serializable,
+    T: Serializable + Identifiable,
 {
     elems: Vec<T>,
     filename: String,
+    journal: Journal,
+    strict: bool,
+    flush_policy: FlushPolicy,
+    // our own mutations since the last full save, as journal lines
+    pending: Vec<String>,
+    // for every id we changed since the last full save, its line before our
+    // first change, None if it did not exist yet
+    base: HashMap<u32, Option<String>>,
+    // the ids of the last conflict and their lines on disk, until resolved
+    conflicts: Vec<(u32, Option<String>)>,
+    // why the last save made by the flush policy failed
+    flush_error: Option<RepoError>,
+    last_save: Instant,
+    // hash of the file as we last read or wrote it, None if it did not exist
+    disk_hash: Option<u64>,
+}
+
+/// When changes are saved to the CSV file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FlushPolicy {
+    // save after every add, update and remove
+    WriteThrough,
+    // save once `ops` mutations have piled up, or on the first mutation after
+    // `interval` has passed since the last save
+    Batched { ops: usize, interval: Duration },
+    // only save when save_to_file is called, or when the repo is dropped
+    Manual,
 }
 
 impl<T> RepoFile<T>
 where
-    T: Serializable,
+    T: Serializable + Identifiable,
 {
     pub fn new(filename: &str) -> Self {
         Self {
             elems: vec![],
             filename: filename.to_string(),
+            journal: Journal::new(filename),
+            strict: false,
+            flush_policy: FlushPolicy::Manual,
+            pending: vec![],
+            base: HashMap::new(),
+            conflicts: vec![],
+            flush_error: None,
+            last_save: Instant::now(),
+            disk_hash: None,
         }
     }
 
-    pub fn load_from_file(&mut self) {
-        let f = File::open(&self.filename);
+    pub fn set_flush_policy(&mut self, flush_policy: FlushPolicy) {
+        self.flush_policy = flush_policy;
+    }
+
+    /// In strict mode a corrupt line fails the whole load instead of being skipped.
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    /// Reads the file and puts our unsaved changes, if any, back on top of it,
+    /// so it also serves to pick up what other processes saved in the meantime.
+    /// Journals left behind by processes that crashed are replayed and saved,
+    /// unless another process changed an element the dead one changed too:
+    /// then its changes are taken over as ours and the conflict waits for
+    /// `keep_ours` or `keep_theirs`, like one found when saving.
+    pub fn load_from_file(&mut self) -> Result<LoadStatus, RepoError> {
+        let mut lock = self.lock(false)?;
+        let recover = self.journal.has_orphans()?;
+        if recover {
+            drop(lock);
+            lock = self.lock(true)?;
+        }
+
+        let (found, mut elems, mut skipped) = match self.read_disk()? {
+            Some((elems, skipped)) => (true, elems, skipped),
+            None => (false, vec![], vec![]),
+        };
+
+        let mut orphans = if recover {
+            self.journal.take_orphans::<T>()?
+        } else {
+            vec![]
+        };
+
+        let mut replayed = false;
+        for orphan in orphans.iter_mut() {
+            let mut entries = vec![];
+            for entry in std::mem::take(&mut orphan.entries) {
+                match entry {
+                    Ok(entry) => entries.push(entry),
+                    Err(err) => self.skip(err, &mut skipped)?,
+                }
+            }
+
+            let conflicts = replay_conflicts(&elems, &entries);
+            if conflicts.is_empty() {
+                for entry in entries {
+                    entry.apply(&mut elems);
+                }
+                replayed = true;
+                continue;
+            }
 
-        match f {
-            Result::Ok(_) => (),
-            Result::Err(_) => {
-                return;
+            // the dead process's changes become ours, base lines included
+            for entry in entries {
+                self.journal.append(&entry)?;
+                self.pending.push(entry.to_line());
+                if let JournalEntry::Base(id, before) = entry {
+                    self.base.entry(id).or_insert(before);
+                }
             }
+            self.conflicts.extend(conflicts);
         }
 
-        let f = BufReader::new(f.unwrap());
+        self.elems = elems;
+        if replayed {
+            self.write_file()?;
+        }
+        for entry in self.pending_entries()? {
+            entry.apply(&mut self.elems);
+        }
+        for orphan in orphans {
+            orphan.remove()?;
+        }
+        drop(lock);
 
-        for line in f.lines() {
-            let line = line.expect("Unable to read line.");
-            self.elems.push(T::from_csv_to_obj(&line));
+        if found || replayed {
+            Ok(LoadStatus::Loaded { skipped })
+        } else {
+            Ok(LoadStatus::NotFound)
         }
     }
 
-    pub fn save_to_file(&self) {
-        let mut f = File::create(&self.filename).expect("Unable to create file.");
+    /// Saves everything. If another process saved the file since we last read
+    /// it, its version is reloaded and our changes are redone on top of it
+    /// instead of overwriting it. If it changed an element we changed too, or
+    /// added one with an id we added, nothing is saved: the conflict is
+    /// reported and our changes stay in memory and in the journal until
+    /// `keep_ours` or `keep_theirs` settles it.
+    pub fn save_to_file(&mut self) -> Result<(), RepoError> {
+        let lock = self.lock(true)?;
 
-        for i in &self.elems {
-            write!(f, "{}\n", i.to_csv());
+        // a load may have read the other process's version since we made our
+        // changes, so the hash alone doesn't say there is nothing to check
+        if !self.base.is_empty() || self.has_changed_on_disk()? {
+            self.merge_from_disk()?;
         }
+
+        self.write_file()?;
+        self.journal.clear()?;
+        drop(lock);
+
+        self.pending.clear();
+        self.base.clear();
+        self.conflicts.clear();
+        self.last_save = Instant::now();
+
+        Ok(())
+    }
+
+    /// The ids of the elements in conflict, until `keep_ours` or
+    /// `keep_theirs` settles it.
+    pub fn conflicts(&self) -> Vec<u32> {
+        self.conflicts.iter().map(|(id, _)| *id).collect()
+    }
+
+    /// The error of the last save the flush policy made after a change, if
+    /// it failed. The change itself stands: it is in memory and in the
+    /// journal, and the next save writes it.
+    pub fn take_flush_error(&mut self) -> Option<RepoError> {
+        self.flush_error.take()
     }
 
-    pub fn add_elem(&mut self, elem: T) -> Result<(), String> {
-        self.elems.push(elem);
+    /// Settles the last conflict in favour of our changes: the next save
+    /// writes them over what the other process saved for those ids.
+    pub fn keep_ours(&mut self) {
+        for (id, theirs) in std::mem::take(&mut self.conflicts) {
+            self.base.insert(id, theirs);
+        }
+    }
+
+    /// Settles the last conflict in favour of the other process: our changes
+    /// to those ids are given up and its version is loaded.
+    pub fn keep_theirs(&mut self) -> Result<(), RepoError> {
+        let ids: Vec<u32> = self.conflicts.iter().map(|(id, _)| *id).collect();
+
+        let mut pending = vec![];
+        for (line, entry) in self.pending.iter().zip(self.pending_entries()?) {
+            if !ids.contains(&entry.id()) {
+                pending.push(line.clone());
+            }
+        }
+        self.journal.rewrite(&pending)?;
+        self.pending = pending;
+        for id in &ids {
+            self.base.remove(id);
+        }
+        self.conflicts.clear();
+
+        self.load_from_file()?;
         Ok(())
     }
 
-    pub fn remove_elem(&mut self, index: usize) -> Result<(), String> {
-        if index > 0 && index < self.elems.len() {
-            self.elems.remove(index);
-            Ok(())
+    /// Whether another process saved the file since we last read or wrote it.
+    pub fn has_changed_on_disk(&self) -> Result<bool, RepoError> {
+        let hash = match fs::read_to_string(&self.filename) {
+            Ok(content) => Some(hash(&content)),
+            Err(err) if err.kind() == ErrorKind::NotFound => None,
+            Err(err) => return Err(RepoError::io(&self.filename, err)),
+        };
+
+        Ok(hash != self.disk_hash)
+    }
+
+    // Redoes our changes on top of the file as it is now. An element we
+    // changed conflicts when the other process changed it too since we read
+    // it, unless both of us ended up with the same line.
+    fn merge_from_disk(&mut self) -> Result<(), RepoError> {
+        let mut elems = match self.read_disk()? {
+            Some((elems, _)) => elems,
+            None => vec![],
+        };
+
+        let line_of =
+            |elems: &[T], id: u32| elems.iter().find(|x| x.id() == id).map(|x| x.to_csv());
+        let mut conflicts = vec![];
+        for (id, before) in &self.base {
+            let theirs = line_of(&elems, *id);
+            if theirs != *before && theirs != line_of(&self.elems, *id) {
+                conflicts.push((*id, theirs));
+            }
+        }
+
+        if !conflicts.is_empty() {
+            conflicts.sort_by_key(|(id, _)| *id);
+            let ids: Vec<String> = conflicts.iter().map(|(id, _)| id.to_string()).collect();
+            self.conflicts = conflicts;
+
+            return Err(RepoError::Conflict {
+                filename: self.filename.clone(),
+                reason: format!(
+                    "Another process changed the elements with the IDs {} since they were read.",
+                    ids.join(", ")
+                ),
+            });
+        }
+
+        for entry in self.pending_entries()? {
+            entry.apply(&mut elems);
+        }
+
+        self.elems = elems;
+        Ok(())
+    }
+
+    // Our own journal lines, so they always parse unless the entity's CSV
+    // format does not round-trip.
+    fn pending_entries(&self) -> Result<Vec<JournalEntry<T>>, RepoError> {
+        self.pending
+            .iter()
+            .enumerate()
+            .map(|(i, line)| {
+                JournalEntry::from_line(line).map_err(|reason| RepoError::Corrupt {
+                    filename: self.journal.filename().to_string(),
+                    line: i + 1,
+                    reason,
+                })
+            })
+            .collect()
+    }
+
+    fn read_disk(&mut self) -> Result<Option<ReadResult<T>>, RepoError> {
+        let content = match fs::read_to_string(&self.filename) {
+            Ok(content) => content,
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                self.disk_hash = None;
+                return Ok(None);
+            }
+            Err(err) => return Err(RepoError::io(&self.filename, err)),
+        };
+
+        let mut elems = vec![];
+        let mut skipped = vec![];
+        for (i, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            match T::from_csv_to_obj(line) {
+                Ok(elem) => elems.push(elem),
+                Err(reason) => self.skip(
+                    RepoError::Corrupt {
+                        filename: self.filename.clone(),
+                        line: i + 1,
+                        reason,
+                    },
+                    &mut skipped,
+                )?,
+            }
+        }
+
+        self.disk_hash = Some(hash(&content));
+        Ok(Some((elems, skipped)))
+    }
+
+    fn skip(&self, err: RepoError, skipped: &mut Vec<RepoError>) -> Result<(), RepoError> {
+        if self.strict {
+            Err(err)
         } else {
-            Err("Index out of bounds".into())
+            skipped.push(err);
+            Ok(())
         }
     }
 
-    pub fn update_elem(&mut self, index: usize, elem: T) -> Result<(), String> {
-        let existing_elem = self.elems.get_mut(index);
+    // Writes everything to a temporary file next to the real one and renames
+    // it over the original, so a crash leaves either the old or the new file.
+    fn write_file(&mut self) -> Result<(), RepoError> {
+        let tmp_filename = format!("{}.tmp.{}", self.filename, process::id());
 
-        match existing_elem {
+        let mut content = String::new();
+        for i in &self.elems {
+            content.push_str(&i.to_csv());
+            content.push('\n');
+        }
+
+        let write = || -> std::io::Result<()> {
+            let mut f = File::create(&tmp_filename)?;
+            f.write_all(content.as_bytes())?;
+            f.sync_all()?;
+            fs::rename(&tmp_filename, &self.filename)?;
+            sync_parent_dir(&self.filename);
+
+            Ok(())
+        };
+
+        write().map_err(|err| RepoError::io(&self.filename, err))?;
+        self.disk_hash = Some(hash(&content));
+
+        Ok(())
+    }
+
+    // Advisory lock on "<data file>.lock": shared while reading, exclusive
+    // while writing. It is released when the returned file is dropped.
+    fn lock(&self, exclusive: bool) -> Result<File, RepoError> {
+        let lock_filename = format!("{}.lock", self.filename);
+        let f = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_filename)
+            .map_err(|err| RepoError::io(&lock_filename, err))?;
+
+        if exclusive { f.lock() } else { f.lock_shared() }
+            .map_err(|err| RepoError::io(&lock_filename, err))?;
+
+        Ok(f)
+    }
+
+    // Every mutation goes through here: it is made durable in the journal
+    // first, then applied, then saved if the flush policy says so. Once it is
+    // in the journal the change stands, so a failed save doesn't fail the
+    // mutation; the error is kept for `take_flush_error` and the change is
+    // saved again with the next save, or when the repo is dropped.
+    fn record(&mut self, entry: JournalEntry<T>) -> Result<(), RepoError> {
+        let id = entry.id();
+        let first = !self.base.contains_key(&id);
+        if first {
+            let before = self.elems.iter().find(|x| x.id() == id).map(|x| x.to_csv());
+            let base = JournalEntry::<T>::Base(id, before.clone());
+            self.journal.append(&base)?;
+            self.pending.push(base.to_line());
+            self.base.insert(id, before);
+        }
+
+        if let Err(err) = self.journal.append(&entry) {
+            if first {
+                self.pending.pop();
+                self.base.remove(&id);
+            }
+            return Err(err);
+        }
+        self.pending.push(entry.to_line());
+        entry.apply(&mut self.elems);
+
+        let due = match self.flush_policy {
+            FlushPolicy::WriteThrough => true,
+            FlushPolicy::Batched { ops, interval } => {
+                let changes = self
+                    .pending
+                    .iter()
+                    .filter(|line| !JournalEntry::<T>::is_base_line(line))
+                    .count();
+                changes >= ops || self.last_save.elapsed() >= interval
+            }
+            FlushPolicy::Manual => false,
+        };
+
+        if due {
+            if let Err(err) = self.save_to_file() {
+                self.flush_error = Some(err);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn id_at(&self, index: usize) -> Result<u32, String> {
+        match self.elems.get(index) {
             None => Err("Index out of bounds".into()),
-            Some(x) => {
-                *x = elem;
-                Ok(())
+            Some(x) => Ok(x.id()),
+        }
+    }
+}
+
+// the elements that could be read and the corrupt lines that were skipped
+type ReadResult<T> = (Vec<T>, Vec<RepoError>);
+
+// The elements a dead process's journal changes that someone else changed
+// too since the dead process read them, with their lines in `elems`. Those
+// whose line ends up the same either way don't count.
+fn replay_conflicts<T>(elems: &[T], entries: &[JournalEntry<T>]) -> Vec<(u32, Option<String>)>
+where
+    T: Serializable + Identifiable,
+{
+    let line_of = |id: u32| elems.iter().find(|x| x.id() == id).map(|x| x.to_csv());
+
+    let mut conflicts = vec![];
+    for entry in entries {
+        let (id, before) = match entry {
+            JournalEntry::Base(id, before) => (*id, before),
+            _ => continue,
+        };
+
+        // the element's line once the journal is replayed
+        let mut replayed = line_of(id);
+        let mut changed = false;
+        for entry in entries.iter().filter(|e| e.id() == id) {
+            match entry {
+                JournalEntry::Add(elem) | JournalEntry::Update(_, elem) => {
+                    replayed = Some(elem.to_csv())
+                }
+                JournalEntry::Remove(_) => replayed = None,
+                JournalEntry::Base(_, _) => continue,
             }
+            changed = true;
+        }
+
+        let theirs = line_of(id);
+        if changed && theirs != *before && theirs != replayed {
+            conflicts.push((id, theirs));
         }
     }
 
-    pub fn get_all(&self) -> &Vec<T> {
+    conflicts.sort_by_key(|(id, _)| *id);
+    conflicts.dedup_by_key(|(id, _)| *id);
+    conflicts
+}
+
+fn hash(content: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+// Whatever the flush policy, nothing is left only in the journal when the
+// repo goes away, including when the program exits through an error path.
+impl<T> Drop for RepoFile<T>
+where
+    T: Serializable + Identifiable,
+{
+    fn drop(&mut self) {
+        if !self.pending.is_empty() {
+            if let Err(err) = self.save_to_file() {
+                eprintln!("Unable to save {}", err);
+            }
+        }
+    }
+}
+
+// Makes the rename itself durable. Not every platform lets a directory be
+// opened like this, and the data is already synced, so failures are ignored.
+fn sync_parent_dir(filename: &str) {
+    let dir = match Path::new(filename).parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+
+    if let Ok(dir) = File::open(dir) {
+        let _ = dir.sync_all();
+    }
+}
+
+impl<T> Repository<T> for RepoFile<T>
+where
+    T: Serializable + Identifiable,
+{
+    fn add_elem(&mut self, elem: T) -> Result<(), String> {
+        Ok(self.record(JournalEntry::Add(elem))?)
+    }
+
+    fn remove_elem(&mut self, index: usize) -> Result<(), String> {
+        let id = self.id_at(index)?;
+        Ok(self.record(JournalEntry::Remove(id))?)
+    }
+
+    fn update_elem(&mut self, index: usize, elem: T) -> Result<(), String> {
+        let id = self.id_at(index)?;
+        Ok(self.record(JournalEntry::Update(id, elem))?)
+    }
+
+    fn get_all(&self) -> &Vec<T> {
         &self.elems
     }
 
-    pub fn get_elem(&self, index: usize) -> Result<&T, String> {
+    fn get_elem(&self, index: usize) -> Result<&T, String> {
         let existing_elem = self.elems.get(index);
 
         match existing_elem {
@@ -86,3 +547,365 @@ where
         }
     }
 }
+
+impl CardRepository for RepoFile<Card> {
+    fn sorted_by_points(&self) -> Result<Vec<Card>, String> {
+        let mut cards = self.elems.clone();
+        cards.sort_by_key(|card| Reverse(card.points()));
+        Ok(cards)
+    }
+
+    fn add_points_for_birthdays(
+        &mut self,
+        start: Date,
+        end: Date,
+        points: u32,
+    ) -> Result<usize, String> {
+        let mut changed = 0;
+        for index in 0..self.elems.len() {
+            let card = &self.elems[index];
+            if card.birthday() >= start && card.birthday() <= end {
+                let mut card = card.clone();
+                card.set_points(card.points() + points)?;
+                self.update_elem(index, card)?;
+                changed += 1;
+            }
+        }
+
+        Ok(changed)
+    }
+}
+
+impl ReservationRepository for RepoFile<Reservation> {
+    fn in_hour_range(&self, start: &str, end: &str) -> Result<Vec<Reservation>, String> {
+        let (start, end) = (hour_key(start), hour_key(end));
+
+        Ok(self
+            .elems
+            .iter()
+            .filter(|r| {
+                let hour = hour_key(r.hour());
+                hour >= start && hour <= end
+            })
+            .cloned()
+            .collect())
+    }
+
+    fn count_by_movie(&self) -> Result<Vec<(u32, usize)>, String> {
+        let mut counts: Vec<(u32, usize)> = vec![];
+        for reservation in &self.elems {
+            match counts
+                .iter_mut()
+                .find(|(id, _)| *id == reservation.movie_id())
+            {
+                Some((_, count)) => *count += 1,
+                None => counts.push((reservation.movie_id(), 1)),
+            }
+        }
+
+        counts.sort_by_key(|(_, count)| Reverse(*count));
+        Ok(counts)
+    }
+
+    fn remove_in_date_range(&mut self, start: Date, end: Date) -> Result<usize, String> {
+        let ids: Vec<u32> = self
+            .elems
+            .iter()
+            .filter(|r| r.date() >= start && r.date() <= end)
+            .map(|r| r.id())
+            .collect();
+
+        for id in &ids {
+            self.record(JournalEntry::Remove(*id))?;
+        }
+
+        Ok(ids.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    // The simplest element there is: an id and a word.
+    struct Note {
+        id: u32,
+        text: String,
+    }
+
+    impl Serializable for Note {
+        fn to_csv(&self) -> String {
+            format!("\"{}\",\"{}\"", self.id, self.text)
+        }
+
+        fn from_csv_to_obj(s: &str) -> Result<Note, String> {
+            let mut parts = s.trim().trim_matches('"').split("\",\"");
+            let id = crate::repo::traits::parse_field(&mut parts, "id")?;
+            let text = crate::repo::traits::parse_field(&mut parts, "text")?;
+            Ok(Note { id, text })
+        }
+    }
+
+    impl Identifiable for Note {
+        fn id(&self) -> u32 {
+            self.id
+        }
+    }
+
+    fn note(id: u32, text: &str) -> Note {
+        Note {
+            id,
+            text: text.to_string(),
+        }
+    }
+
+    fn open(filename: &str) -> RepoFile<Note> {
+        let mut repo = RepoFile::new(filename);
+        repo.load_from_file().unwrap();
+        repo
+    }
+
+    fn texts(repo: &RepoFile<Note>) -> Vec<String> {
+        repo.get_all().iter().map(|n| n.text.clone()).collect()
+    }
+
+    fn journals(dir: &TempDir) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir.path(""))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .filter(|name| name.contains(".journal"))
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn saving_replaces_the_file_and_leaves_nothing_else_behind() {
+        let dir = TempDir::new("repo-file-save");
+        let filename = dir.path("notes.csv");
+        fs::write(&filename, "\"1\",\"old\"\n").unwrap();
+
+        let mut repo = open(&filename);
+        repo.update_elem(0, note(1, "new")).unwrap();
+        repo.add_elem(note(2, "more")).unwrap();
+        repo.save_to_file().unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&filename).unwrap(),
+            "\"1\",\"new\"\n\"2\",\"more\"\n"
+        );
+        let names: Vec<String> = fs::read_dir(dir.path(""))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .filter(|name| name.contains(".tmp"))
+            .collect();
+        assert!(names.is_empty());
+    }
+
+    #[test]
+    fn the_journal_of_a_dead_process_is_replayed_without_its_torn_line() {
+        let dir = TempDir::new("repo-file-replay");
+        let filename = dir.path("notes.csv");
+        fs::write(&filename, "\"1\",\"one\"\n\"2\",\"two\"\n").unwrap();
+        // pid 0 is never a user process, so nobody holds this journal
+        fs::write(
+            format!("{}.journal.0", filename),
+            "base\t1\t\"1\",\"one\"\nupdate\t1\t\"1\",\"uno\"\nbase\t3\nadd\t\"3\",\"three\"\nremove\t2\nadd\t\"4\",\"fo",
+        )
+        .unwrap();
+
+        let repo = open(&filename);
+
+        assert_eq!(texts(&repo), vec!["uno", "three"]);
+        assert_eq!(
+            fs::read_to_string(&filename).unwrap(),
+            "\"1\",\"uno\"\n\"3\",\"three\"\n"
+        );
+        assert!(journals(&dir).is_empty());
+    }
+
+    #[test]
+    fn a_dead_process_does_not_overwrite_what_another_one_saved() {
+        let dir = TempDir::new("repo-file-replay-conflict");
+        let filename = dir.path("notes.csv");
+        // the dead process read "one" and made it "uno"; since then someone
+        // else saved "eins"
+        fs::write(&filename, "\"1\",\"eins\"\n").unwrap();
+        fs::write(
+            format!("{}.journal.0", filename),
+            "base\t1\t\"1\",\"one\"\nupdate\t1\t\"1\",\"uno\"\n",
+        )
+        .unwrap();
+
+        let mut repo = open(&filename);
+        assert_eq!(repo.conflicts(), vec![1]);
+        assert_eq!(texts(&repo), vec!["uno"]);
+        assert_eq!(fs::read_to_string(&filename).unwrap(), "\"1\",\"eins\"\n");
+        assert!(repo.save_to_file().is_err());
+
+        repo.keep_theirs().unwrap();
+        assert!(repo.conflicts().is_empty());
+        assert_eq!(texts(&repo), vec!["eins"]);
+        drop(repo);
+        assert_eq!(fs::read_to_string(&filename).unwrap(), "\"1\",\"eins\"\n");
+        assert!(journals(&dir).is_empty());
+    }
+
+    #[test]
+    fn the_changes_of_a_dead_process_can_be_kept_over_another_ones() {
+        let dir = TempDir::new("repo-file-replay-ours");
+        let filename = dir.path("notes.csv");
+        fs::write(&filename, "\"1\",\"eins\"\n").unwrap();
+        fs::write(
+            format!("{}.journal.0", filename),
+            "base\t1\t\"1\",\"one\"\nupdate\t1\t\"1\",\"uno\"\n",
+        )
+        .unwrap();
+
+        let mut repo = open(&filename);
+        repo.keep_ours();
+        repo.save_to_file().unwrap();
+
+        assert_eq!(fs::read_to_string(&filename).unwrap(), "\"1\",\"uno\"\n");
+        drop(repo);
+        assert!(journals(&dir).is_empty());
+    }
+
+    #[test]
+    fn corrupt_lines_are_skipped_unless_strict() {
+        let dir = TempDir::new("repo-file-strict");
+        let filename = dir.path("notes.csv");
+        fs::write(&filename, "\"1\",\"one\"\nnot a note\n\"2\",\"two\"\n").unwrap();
+
+        let mut repo = RepoFile::<Note>::new(&filename);
+        match repo.load_from_file().unwrap() {
+            LoadStatus::Loaded { skipped } => {
+                assert_eq!(skipped.len(), 1);
+                assert!(matches!(skipped[0], RepoError::Corrupt { line: 2, .. }));
+            }
+            LoadStatus::NotFound => panic!("the file exists"),
+        }
+        assert_eq!(texts(&repo), vec!["one", "two"]);
+
+        let mut strict = RepoFile::<Note>::new(&filename);
+        strict.set_strict(true);
+        assert!(matches!(
+            strict.load_from_file(),
+            Err(RepoError::Corrupt { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn a_missing_file_loads_empty() {
+        let dir = TempDir::new("repo-file-missing");
+        let mut repo = RepoFile::<Note>::new(&dir.path("notes.csv"));
+        assert!(matches!(repo.load_from_file(), Ok(LoadStatus::NotFound)));
+        assert!(repo.get_all().is_empty());
+    }
+
+    #[test]
+    fn the_flush_policy_says_when_changes_are_saved() {
+        let dir = TempDir::new("repo-file-flush");
+        let filename = dir.path("notes.csv");
+        let on_disk = || fs::read_to_string(&filename).unwrap_or_default();
+
+        let mut manual = open(&filename);
+        manual.add_elem(note(1, "one")).unwrap();
+        assert_eq!(on_disk(), "");
+        assert_eq!(journals(&dir).len(), 1);
+        drop(manual);
+        assert_eq!(on_disk(), "\"1\",\"one\"\n");
+        assert!(journals(&dir).is_empty());
+
+        let mut batched = open(&filename);
+        batched.set_flush_policy(FlushPolicy::Batched {
+            ops: 2,
+            interval: Duration::from_secs(3600),
+        });
+        batched.add_elem(note(2, "two")).unwrap();
+        assert_eq!(on_disk(), "\"1\",\"one\"\n");
+        batched.update_elem(1, note(2, "two")).unwrap();
+        assert_eq!(on_disk(), "\"1\",\"one\"\n\"2\",\"two\"\n");
+
+        let mut write_through = open(&filename);
+        write_through.set_flush_policy(FlushPolicy::WriteThrough);
+        write_through.remove_elem(0).unwrap();
+        assert_eq!(on_disk(), "\"2\",\"two\"\n");
+    }
+
+    #[test]
+    fn saves_of_two_processes_are_merged() {
+        let dir = TempDir::new("repo-file-merge");
+        let filename = dir.path("notes.csv");
+        fs::write(&filename, "\"1\",\"one\"\n\"2\",\"two\"\n").unwrap();
+
+        let mut first = open(&filename);
+        let mut second = open(&filename);
+        first.update_elem(0, note(1, "uno")).unwrap();
+        first.add_elem(note(3, "three")).unwrap();
+        first.save_to_file().unwrap();
+        second.remove_elem(1).unwrap();
+        second.add_elem(note(4, "four")).unwrap();
+        second.save_to_file().unwrap();
+
+        assert_eq!(texts(&second), vec!["uno", "three", "four"]);
+        assert_eq!(
+            fs::read_to_string(&filename).unwrap(),
+            "\"1\",\"uno\"\n\"3\",\"three\"\n\"4\",\"four\"\n"
+        );
+    }
+
+    #[test]
+    fn changes_to_the_same_element_conflict_until_settled() {
+        let dir = TempDir::new("repo-file-conflict");
+        let filename = dir.path("notes.csv");
+        fs::write(&filename, "\"1\",\"one\"\n").unwrap();
+
+        let mut first = open(&filename);
+        let mut second = open(&filename);
+        first.update_elem(0, note(1, "uno")).unwrap();
+        first.save_to_file().unwrap();
+        second.update_elem(0, note(1, "eins")).unwrap();
+
+        assert!(matches!(
+            second.save_to_file(),
+            Err(RepoError::Conflict { .. })
+        ));
+        assert_eq!(second.conflicts(), vec![1]);
+        assert_eq!(fs::read_to_string(&filename).unwrap(), "\"1\",\"uno\"\n");
+
+        second.keep_ours();
+        second.save_to_file().unwrap();
+        assert_eq!(fs::read_to_string(&filename).unwrap(), "\"1\",\"eins\"\n");
+
+        first.update_elem(0, note(1, "un")).unwrap();
+        assert!(first.save_to_file().is_err());
+        first.keep_theirs().unwrap();
+        assert_eq!(texts(&first), vec!["eins"]);
+        first.save_to_file().unwrap();
+        assert_eq!(fs::read_to_string(&filename).unwrap(), "\"1\",\"eins\"\n");
+    }
+
+    #[test]
+    fn a_failed_flush_keeps_the_change() {
+        let dir = TempDir::new("repo-file-flush-error");
+        let filename = dir.path("notes.csv");
+        fs::write(&filename, "\"1\",\"one\"\n").unwrap();
+
+        let mut first = open(&filename);
+        let mut second = open(&filename);
+        second.set_flush_policy(FlushPolicy::WriteThrough);
+        first.update_elem(0, note(1, "uno")).unwrap();
+        first.save_to_file().unwrap();
+
+        assert!(second.update_elem(0, note(1, "eins")).is_ok());
+        assert_eq!(texts(&second), vec!["eins"]);
+        assert!(matches!(
+            second.take_flush_error(),
+            Some(RepoError::Conflict { .. })
+        ));
+        assert!(second.take_flush_error().is_none());
+        second.keep_ours();
+    }
+}
@@ -0,0 +1,1209 @@
+use super::traits::{CardRepository, Identifiable, Repository, ReservationRepository};
+use crate::domain::card::Card;
+use crate::domain::date::Date;
+use crate::domain::gift_card::{GiftCard, GiftCardEntry};
+use crate::domain::hall::{parse_layout, Hall};
+use crate::domain::money::{Currency, Money};
+use crate::domain::movie::{parse_genres, Movie};
+use crate::domain::promotion::{parse_codes, parse_days, Promotion};
+use crate::domain::reservation::{hour_key, parse_seats, PriceSnapshot, Reservation};
+use crate::domain::screening::Screening;
+use crate::domain::seat_hold::SeatHold;
+use crate::domain::ticket::parse_tickets;
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, Row, TransactionBehavior};
+use std::cmp::Reverse;
+use std::rc::Rc;
+
+// Each migration brings the database from the previous version to the next;
+// the number of migrations applied is kept in `PRAGMA user_version`. The
+// first one uses IF NOT EXISTS because databases made before versioning
+// already have those tables. Never edit a migration, add a new one instead.
+const MIGRATIONS: &[&str] = &[
+    "
+    CREATE TABLE IF NOT EXISTS movies (
+        id INTEGER PRIMARY KEY,
+        title TEXT NOT NULL,
+        release_year INTEGER NOT NULL,
+        price INTEGER NOT NULL,
+        in_program INTEGER NOT NULL
+    );
+
+    CREATE TABLE IF NOT EXISTS cards (
+        id INTEGER PRIMARY KEY,
+        first_name TEXT NOT NULL,
+        last_name TEXT NOT NULL,
+        cnp TEXT NOT NULL,
+        birthday TEXT NOT NULL,
+        registration_date TEXT NOT NULL,
+        points INTEGER NOT NULL
+    );
+
+    CREATE UNIQUE INDEX IF NOT EXISTS cards_cnp ON cards (cnp);
+
+    CREATE TABLE IF NOT EXISTS reservations (
+        id INTEGER PRIMARY KEY,
+        movie_id INTEGER NOT NULL REFERENCES movies (id),
+        card_id INTEGER REFERENCES cards (id),
+        date TEXT NOT NULL,
+        hour TEXT NOT NULL
+    );
+    ",
+    // Reservations reference a screening. Every distinct showing of the
+    // existing reservations becomes a screening in hall 1.
+    "
+    CREATE TABLE screenings (
+        id INTEGER PRIMARY KEY,
+        movie_id INTEGER NOT NULL REFERENCES movies (id),
+        hall_id INTEGER NOT NULL,
+        date TEXT NOT NULL,
+        start TEXT NOT NULL
+    );
+
+    INSERT INTO screenings (movie_id, hall_id, date, start)
+        SELECT DISTINCT movie_id, 1, date, hour FROM reservations;
+
+    CREATE TABLE reservations_new (
+        id INTEGER PRIMARY KEY,
+        screening_id INTEGER NOT NULL REFERENCES screenings (id),
+        movie_id INTEGER NOT NULL REFERENCES movies (id),
+        card_id INTEGER REFERENCES cards (id),
+        date TEXT NOT NULL,
+        hour TEXT NOT NULL
+    );
+
+    INSERT INTO reservations_new
+        SELECT r.id, s.id, r.movie_id, r.card_id, r.date, r.hour
+        FROM reservations r
+        JOIN screenings s ON s.movie_id = r.movie_id AND s.date = r.date AND s.start = r.hour;
+
+    DROP TABLE reservations;
+    ALTER TABLE reservations_new RENAME TO reservations;
+    ",
+    // Screenings take place in a hall. The halls the existing screenings
+    // refer to get a default 10 x 10 layout, to be edited afterwards.
+    "
+    CREATE TABLE halls (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL UNIQUE,
+        capacity INTEGER NOT NULL,
+        layout TEXT NOT NULL
+    );
+
+    INSERT INTO halls (id, name, capacity, layout)
+        SELECT DISTINCT hall_id, 'Hall ' || hall_id, 100,
+            'SSSSSSSSSS/SSSSSSSSSS/SSSSSSSSSS/SSSSSSSSSS/SSSSSSSSSS/'
+            || 'SSSSSSSSSS/SSSSSSSSSS/SSSSSSSSSS/SSSSSSSSSS/SSSSSSSSSS'
+        FROM screenings;
+
+    CREATE TABLE screenings_new (
+        id INTEGER PRIMARY KEY,
+        movie_id INTEGER NOT NULL REFERENCES movies (id),
+        hall_id INTEGER NOT NULL REFERENCES halls (id),
+        date TEXT NOT NULL,
+        start TEXT NOT NULL
+    );
+
+    INSERT INTO screenings_new SELECT id, movie_id, hall_id, date, start FROM screenings;
+
+    DROP TABLE screenings;
+    ALTER TABLE screenings_new RENAME TO screenings;
+    ",
+    // Reservations hold seats, written as "C7;C8". The existing ones have none.
+    "
+    ALTER TABLE reservations ADD COLUMN seats TEXT NOT NULL DEFAULT '';
+    ",
+    "
+    CREATE TABLE holds (
+        id INTEGER PRIMARY KEY,
+        screening_id INTEGER NOT NULL REFERENCES screenings (id),
+        seats TEXT NOT NULL,
+        expires_at INTEGER NOT NULL
+    );
+    ",
+    // Running times, in minutes. Movies added before default to two hours,
+    // like in the CSV files (see `DEFAULT_RUNTIME`).
+    "
+    ALTER TABLE movies ADD COLUMN runtime INTEGER NOT NULL DEFAULT 120;
+    ",
+    // Opening hours, defaulting like `DEFAULT_OPENS` and `DEFAULT_CLOSES`.
+    "
+    ALTER TABLE halls ADD COLUMN opens TEXT NOT NULL DEFAULT '10:00';
+    ALTER TABLE halls ADD COLUMN closes TEXT NOT NULL DEFAULT '00:00';
+    ",
+    // Movie details. Genres are written as "Drama;Sci-Fi", a missing
+    // language or subtitle language as ''.
+    "
+    ALTER TABLE movies ADD COLUMN genres TEXT NOT NULL DEFAULT '';
+    ALTER TABLE movies ADD COLUMN age_rating TEXT NOT NULL DEFAULT 'AG';
+    ALTER TABLE movies ADD COLUMN language TEXT NOT NULL DEFAULT '';
+    ALTER TABLE movies ADD COLUMN subtitles TEXT NOT NULL DEFAULT '';
+    ALTER TABLE movies ADD COLUMN format TEXT NOT NULL DEFAULT '2D';
+    ",
+    "
+    ALTER TABLE reservations ADD COLUMN supervised INTEGER NOT NULL DEFAULT 0;
+    ",
+    // Prices were whole lei; they become bani, with the currency next to them.
+    "
+    ALTER TABLE movies RENAME COLUMN price TO price_minor;
+    UPDATE movies SET price_minor = price_minor * 100;
+    ALTER TABLE movies ADD COLUMN currency TEXT NOT NULL DEFAULT 'RON';
+    ",
+    // What each reservation cost. NULL for the ones booked before this.
+    "
+    ALTER TABLE reservations ADD COLUMN unit_price_minor INTEGER;
+    ALTER TABLE reservations ADD COLUMN discount_minor INTEGER;
+    ALTER TABLE reservations ADD COLUMN currency TEXT;
+    ALTER TABLE reservations ADD COLUMN points INTEGER;
+    ",
+    "
+    ALTER TABLE reservations ADD COLUMN tickets TEXT NOT NULL DEFAULT '';
+    ",
+    // Promo codes, and what each took off a reservation in its currency's
+    // minor units, written as "250;100".
+    "
+    CREATE TABLE promotions (
+        id INTEGER PRIMARY KEY,
+        code TEXT NOT NULL UNIQUE,
+        benefit TEXT NOT NULL,
+        valid_from TEXT NOT NULL,
+        valid_until TEXT NOT NULL,
+        days TEXT NOT NULL,
+        categories TEXT NOT NULL,
+        max_uses INTEGER,
+        max_uses_per_card INTEGER,
+        stackable INTEGER NOT NULL,
+        min_spend TEXT
+    );
+    ALTER TABLE reservations ADD COLUMN promo_codes TEXT NOT NULL DEFAULT '';
+    ALTER TABLE reservations ADD COLUMN promo_discounts TEXT NOT NULL DEFAULT '';
+    ",
+    // Gift cards and their ledger. Ledger entries keep the ID of a reservation
+    // after it is cancelled, so it is not a foreign key.
+    "
+    CREATE TABLE gift_cards (
+        id INTEGER PRIMARY KEY,
+        code TEXT NOT NULL UNIQUE,
+        value_minor INTEGER NOT NULL,
+        balance_minor INTEGER NOT NULL,
+        currency TEXT NOT NULL,
+        expires TEXT NOT NULL
+    );
+    CREATE TABLE gift_card_ledger (
+        id INTEGER PRIMARY KEY,
+        gift_card_id INTEGER NOT NULL REFERENCES gift_cards (id),
+        date TEXT NOT NULL,
+        movement TEXT NOT NULL,
+        amount_minor INTEGER NOT NULL,
+        currency TEXT NOT NULL,
+        reservation_id INTEGER
+    );
+    ",
+    // The part of a reservation's price paid with gift cards, in minor units.
+    "
+    ALTER TABLE reservations ADD COLUMN gift_card_minor INTEGER;
+    ",
+    // Points a reservation was paid with, and the part of the price they paid
+    // in minor units; NULL like the other prices when there is no snapshot.
+    "
+    ALTER TABLE reservations ADD COLUMN redeemed_points INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE reservations ADD COLUMN redeemed_minor INTEGER;
+    ",
+];
+
+/// How an entity maps onto its table. Dates are stored as yyyy-mm-dd so that
+/// SQLite can compare them.
+pub trait SqlEntity: Identifiable + Sized {
+    const TABLE: &'static str;
+    const COLUMNS: &'static [&'static str];
+
+    fn to_row(&self) -> Vec<Value>;
+    fn from_row(row: &Row) -> Result<Self, String>;
+}
+
+/// A SQLite database file, brought up to date when it is opened. Its tables
+/// are opened with `RepoSqlite::new`, and share its connection.
+pub struct Database {
+    conn: Rc<Connection>,
+}
+
+impl Database {
+    /// Opens (or creates) the database file and applies the migrations it
+    /// is missing.
+    pub fn open(filename: &str) -> Result<Self, String> {
+        let mut conn = Connection::open(filename).map_err(|err| err.to_string())?;
+
+        conn.execute_batch("PRAGMA foreign_keys = OFF;")
+            .map_err(|err| err.to_string())?;
+        migrate(&mut conn)?;
+        conn.execute_batch("PRAGMA foreign_keys = ON;")
+            .map_err(|err| err.to_string())?;
+
+        Ok(Self {
+            conn: Rc::new(conn),
+        })
+    }
+}
+
+/// A repository backed by a table of a SQLite database. The rows are also
+/// kept in memory, so it can hand out references like `RepoFile` does.
+pub struct RepoSqlite<T>
+where
+    T: SqlEntity,
+{
+    elems: Vec<T>,
+    conn: Rc<Connection>,
+}
+
+impl<T> RepoSqlite<T>
+where
+    T: SqlEntity,
+{
+    /// Loads the entity's table from the database.
+    pub fn new(database: &Database) -> Result<Self, String> {
+        let mut repo = Self {
+            elems: vec![],
+            conn: Rc::clone(&database.conn),
+        };
+        repo.reload()?;
+
+        Ok(repo)
+    }
+
+    fn reload(&mut self) -> Result<(), String> {
+        self.elems = self.select("", &[])?;
+        Ok(())
+    }
+
+    fn select(&self, condition: &str, args: &[Value]) -> Result<Vec<T>, String> {
+        let sql = format!(
+            "SELECT {} FROM {} {} ORDER BY id",
+            T::COLUMNS.join(", "),
+            T::TABLE,
+            condition
+        );
+
+        let mut stmt = self.conn.prepare(&sql).map_err(|err| err.to_string())?;
+        let mut rows = stmt
+            .query(params_from_iter(args.iter()))
+            .map_err(|err| err.to_string())?;
+
+        let mut elems = vec![];
+        while let Some(row) = rows.next().map_err(|err| err.to_string())? {
+            elems.push(T::from_row(row)?);
+        }
+
+        Ok(elems)
+    }
+}
+
+// The version is read inside the write transaction, so two processes opening
+// an old database at the same time don't both migrate it. Foreign keys must
+// still be off while tables are rebuilt, and are checked once at the end.
+fn migrate(conn: &mut Connection) -> Result<(), String> {
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(|err| err.to_string())?;
+
+    let version: usize = tx
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .map_err(|err| err.to_string())?;
+    if version > MIGRATIONS.len() {
+        return Err("The database was made by a newer version of this program.".into());
+    }
+
+    for migration in &MIGRATIONS[version..] {
+        tx.execute_batch(migration).map_err(|err| err.to_string())?;
+    }
+
+    let broken = tx
+        .prepare("PRAGMA foreign_key_check")
+        .and_then(|mut stmt| stmt.exists([]))
+        .map_err(|err| err.to_string())?;
+    if broken {
+        return Err("The database refers to rows that don't exist.".into());
+    }
+
+    tx.pragma_update(None, "user_version", MIGRATIONS.len())
+        .map_err(|err| err.to_string())?;
+    tx.commit().map_err(|err| err.to_string())
+}
+
+impl<T> Repository<T> for RepoSqlite<T>
+where
+    T: SqlEntity,
+{
+    fn add_elem(&mut self, elem: T) -> Result<(), String> {
+        let placeholders: Vec<String> = (1..=T::COLUMNS.len()).map(|i| format!("?{}", i)).collect();
+        let sql = format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            T::TABLE,
+            T::COLUMNS.join(", "),
+            placeholders.join(", ")
+        );
+
+        self.conn
+            .execute(&sql, params_from_iter(elem.to_row()))
+            .map_err(|err| err.to_string())?;
+        self.elems.push(elem);
+
+        Ok(())
+    }
+
+    fn remove_elem(&mut self, index: usize) -> Result<(), String> {
+        let id = match self.elems.get(index) {
+            None => return Err("Index out of bounds".into()),
+            Some(x) => x.id(),
+        };
+
+        let sql = format!("DELETE FROM {} WHERE id = ?1", T::TABLE);
+        self.conn
+            .execute(&sql, params![id])
+            .map_err(|err| err.to_string())?;
+        self.elems.remove(index);
+
+        Ok(())
+    }
+
+    fn update_elem(&mut self, index: usize, elem: T) -> Result<(), String> {
+        let id = match self.elems.get(index) {
+            None => return Err("Index out of bounds".into()),
+            Some(x) => x.id(),
+        };
+
+        let assignments: Vec<String> = T::COLUMNS
+            .iter()
+            .enumerate()
+            .map(|(i, column)| format!("{} = ?{}", column, i + 1))
+            .collect();
+        let sql = format!(
+            "UPDATE {} SET {} WHERE id = ?{}",
+            T::TABLE,
+            assignments.join(", "),
+            T::COLUMNS.len() + 1
+        );
+
+        let mut args = elem.to_row();
+        args.push(Value::Integer(id as i64));
+        self.conn
+            .execute(&sql, params_from_iter(args))
+            .map_err(|err| err.to_string())?;
+        self.elems[index] = elem;
+
+        Ok(())
+    }
+
+    fn get_all(&self) -> &Vec<T> {
+        &self.elems
+    }
+
+    fn get_elem(&self, index: usize) -> Result<&T, String> {
+        match self.elems.get(index) {
+            None => Err("Index out of bounds".into()),
+            Some(x) => Ok(x),
+        }
+    }
+}
+
+impl CardRepository for RepoSqlite<Card> {
+    fn sorted_by_points(&self) -> Result<Vec<Card>, String> {
+        let mut cards = self.select("", &[])?;
+        // select() orders by id, so the stable sort keeps ties in id order,
+        // same as the CSV backend
+        cards.sort_by_key(|card| Reverse(card.points()));
+        Ok(cards)
+    }
+
+    fn add_points_for_birthdays(
+        &mut self,
+        start: Date,
+        end: Date,
+        points: u32,
+    ) -> Result<usize, String> {
+        let changed = self
+            .conn
+            .execute(
+                "UPDATE cards SET points = points + ?3 WHERE birthday BETWEEN ?1 AND ?2",
+                params![start.to_iso_string(), end.to_iso_string(), points],
+            )
+            .map_err(|err| err.to_string())?;
+        self.reload()?;
+
+        Ok(changed)
+    }
+}
+
+// Hours may be written with any of the accepted separators, so they are
+// compared by their digits only.
+const HOUR_KEY: &str = "(substr(hour, 1, 2) || substr(hour, 4, 2))";
+
+impl ReservationRepository for RepoSqlite<Reservation> {
+    fn in_hour_range(&self, start: &str, end: &str) -> Result<Vec<Reservation>, String> {
+        self.select(
+            &format!("WHERE {} BETWEEN ?1 AND ?2", HOUR_KEY),
+            &[Value::Text(hour_key(start)), Value::Text(hour_key(end))],
+        )
+    }
+
+    fn count_by_movie(&self) -> Result<Vec<(u32, usize)>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT movie_id, COUNT(*) FROM reservations
+                 GROUP BY movie_id ORDER BY COUNT(*) DESC, MIN(id)",
+            )
+            .map_err(|err| err.to_string())?;
+
+        let counts = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, u32>(0)?, row.get::<_, i64>(1)? as usize))
+            })
+            .map_err(|err| err.to_string())?;
+
+        counts
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| err.to_string())
+    }
+
+    fn remove_in_date_range(&mut self, start: Date, end: Date) -> Result<usize, String> {
+        let removed = self
+            .conn
+            .execute(
+                "DELETE FROM reservations WHERE date BETWEEN ?1 AND ?2",
+                params![start.to_iso_string(), end.to_iso_string()],
+            )
+            .map_err(|err| err.to_string())?;
+        self.reload()?;
+
+        Ok(removed)
+    }
+}
+
+// '' stands for "none".
+fn optional_text(value: String) -> Option<String> {
+    if value.is_empty() {
+        None
+    } else {
+        Some(value)
+    }
+}
+
+fn column<T: rusqlite::types::FromSql>(row: &Row, index: usize) -> Result<T, String> {
+    row.get(index).map_err(|err| err.to_string())
+}
+
+impl SqlEntity for Movie {
+    const TABLE: &'static str = "movies";
+    const COLUMNS: &'static [&'static str] = &[
+        "id",
+        "title",
+        "release_year",
+        "price_minor",
+        "in_program",
+        "runtime",
+        "genres",
+        "age_rating",
+        "language",
+        "subtitles",
+        "format",
+        "currency",
+    ];
+
+    fn to_row(&self) -> Vec<Value> {
+        vec![
+            Value::Integer(self.id() as i64),
+            Value::Text(self.title().to_string()),
+            Value::Integer(self.release_year() as i64),
+            Value::Integer(self.price().minor()),
+            Value::Integer(self.in_program() as i64),
+            Value::Integer(self.runtime() as i64),
+            Value::Text(self.genres().join(";")),
+            Value::Text(self.age_rating().to_string()),
+            Value::Text(self.language().unwrap_or("").to_string()),
+            Value::Text(self.subtitles().unwrap_or("").to_string()),
+            Value::Text(self.format().to_string()),
+            Value::Text(self.price().currency().code().to_string()),
+        ]
+    }
+
+    fn from_row(row: &Row) -> Result<Movie, String> {
+        let mut movie = Movie::new(
+            column(row, 0)?,
+            &column::<String>(row, 1)?,
+            column(row, 2)?,
+            Money::new(column(row, 3)?, column::<String>(row, 11)?.parse()?),
+            column(row, 4)?,
+            column(row, 5)?,
+        )?;
+        movie.set_genres(parse_genres(&column::<String>(row, 6)?))?;
+        movie.set_age_rating(column::<String>(row, 7)?.parse()?)?;
+        movie.set_language(optional_text(column(row, 8)?))?;
+        movie.set_subtitles(optional_text(column(row, 9)?))?;
+        movie.set_format(column::<String>(row, 10)?.parse()?)?;
+        Ok(movie)
+    }
+}
+
+impl SqlEntity for Card {
+    const TABLE: &'static str = "cards";
+    const COLUMNS: &'static [&'static str] = &[
+        "id",
+        "first_name",
+        "last_name",
+        "cnp",
+        "birthday",
+        "registration_date",
+        "points",
+    ];
+
+    fn to_row(&self) -> Vec<Value> {
+        vec![
+            Value::Integer(self.id() as i64),
+            Value::Text(self.first_name().to_string()),
+            Value::Text(self.last_name().to_string()),
+            Value::Text(self.cnp().to_string()),
+            Value::Text(self.birthday().to_iso_string()),
+            Value::Text(self.registration_date().to_iso_string()),
+            Value::Integer(self.points() as i64),
+        ]
+    }
+
+    fn from_row(row: &Row) -> Result<Card, String> {
+        Card::from_stored(
+            column(row, 0)?,
+            &column::<String>(row, 1)?,
+            &column::<String>(row, 2)?,
+            &column::<String>(row, 3)?,
+            Date::from_iso_string(&column::<String>(row, 4)?)?,
+            Date::from_iso_string(&column::<String>(row, 5)?)?,
+            column(row, 6)?,
+        )
+    }
+}
+
+impl SqlEntity for Reservation {
+    const TABLE: &'static str = "reservations";
+    const COLUMNS: &'static [&'static str] = &[
+        "id",
+        "screening_id",
+        "movie_id",
+        "card_id",
+        "date",
+        "hour",
+        "seats",
+        "supervised",
+        "unit_price_minor",
+        "discount_minor",
+        "currency",
+        "points",
+        "tickets",
+        "promo_codes",
+        "promo_discounts",
+        "gift_card_minor",
+        "redeemed_points",
+        "redeemed_minor",
+    ];
+
+    fn to_row(&self) -> Vec<Value> {
+        let mut row = vec![
+            Value::Integer(self.id() as i64),
+            Value::Integer(self.screening_id() as i64),
+            Value::Integer(self.movie_id() as i64),
+            match self.card_id() {
+                None => Value::Null,
+                Some(x) => Value::Integer(x as i64),
+            },
+            Value::Text(self.date().to_iso_string()),
+            Value::Text(self.hour().to_string()),
+            Value::Text(self.seats().join(";")),
+            Value::Integer(self.supervised() as i64),
+        ];
+
+        match self.price() {
+            None => row.extend([Value::Null, Value::Null, Value::Null, Value::Null]),
+            Some(price) => row.extend([
+                Value::Integer(price.unit_price().minor()),
+                Value::Integer(price.discount().minor()),
+                Value::Text(price.unit_price().currency().code().to_string()),
+                Value::Integer(price.points() as i64),
+            ]),
+        }
+        row.push(Value::Text(
+            self.tickets()
+                .iter()
+                .map(|ticket| ticket.to_string())
+                .collect::<Vec<_>>()
+                .join(";"),
+        ));
+        row.push(Value::Text(self.promo_codes().join(";")));
+        row.push(Value::Text(match self.price() {
+            None => String::new(),
+            Some(price) => price
+                .promotions()
+                .iter()
+                .map(|amount| amount.minor().to_string())
+                .collect::<Vec<_>>()
+                .join(";"),
+        }));
+        row.push(match self.price() {
+            None => Value::Null,
+            Some(price) => Value::Integer(price.gift_card().minor()),
+        });
+        row.push(Value::Integer(self.redeemed_points() as i64));
+        row.push(match self.price() {
+            None => Value::Null,
+            Some(price) => Value::Integer(price.redeemed().minor()),
+        });
+        row
+    }
+
+    fn from_row(row: &Row) -> Result<Reservation, String> {
+        let mut reservation = Reservation::new(
+            column(row, 0)?,
+            column(row, 1)?,
+            column(row, 2)?,
+            column(row, 3)?,
+            Date::from_iso_string(&column::<String>(row, 4)?)?,
+            column(row, 5)?,
+        )?;
+        reservation.set_seats(parse_seats(&column::<String>(row, 6)?))?;
+        reservation.set_supervised(column(row, 7)?)?;
+        reservation.set_tickets(parse_tickets(&column::<String>(row, 12)?)?)?;
+        reservation.set_promo_codes(parse_codes(&column::<String>(row, 13)?))?;
+        reservation.set_redeemed_points(column(row, 16)?)?;
+
+        let unit_price: Option<i64> = column(row, 8)?;
+        if let Some(unit_price) = unit_price {
+            let currency: Currency = column::<String>(row, 10)?.parse()?;
+            let promotions = column::<String>(row, 14)?
+                .split(';')
+                .filter(|amount| !amount.is_empty())
+                .map(|amount| match amount.parse() {
+                    Ok(minor) => Ok(Money::new(minor, currency)),
+                    Err(_) => Err(format!("Invalid promo discount: {}", amount)),
+                })
+                .collect::<Result<Vec<Money>, String>>()?;
+            reservation.set_price(Some(
+                PriceSnapshot::new(
+                    Money::new(unit_price, currency),
+                    Money::new(column(row, 9)?, currency),
+                    column(row, 11)?,
+                )
+                .with_promotions(promotions)
+                .with_gift_card(Money::new(
+                    column::<Option<i64>>(row, 15)?.unwrap_or(0),
+                    currency,
+                ))
+                .with_redeemed(Money::new(
+                    column::<Option<i64>>(row, 17)?.unwrap_or(0),
+                    currency,
+                )),
+            ))?;
+        }
+        Ok(reservation)
+    }
+}
+
+impl SqlEntity for Screening {
+    const TABLE: &'static str = "screenings";
+    const COLUMNS: &'static [&'static str] = &["id", "movie_id", "hall_id", "date", "start"];
+
+    fn to_row(&self) -> Vec<Value> {
+        vec![
+            Value::Integer(self.id() as i64),
+            Value::Integer(self.movie_id() as i64),
+            Value::Integer(self.hall_id() as i64),
+            Value::Text(self.date().to_iso_string()),
+            Value::Text(self.start().to_string()),
+        ]
+    }
+
+    fn from_row(row: &Row) -> Result<Screening, String> {
+        Screening::new(
+            column(row, 0)?,
+            column(row, 1)?,
+            column(row, 2)?,
+            Date::from_iso_string(&column::<String>(row, 3)?)?,
+            column(row, 4)?,
+        )
+    }
+}
+
+impl SqlEntity for Hall {
+    const TABLE: &'static str = "halls";
+    const COLUMNS: &'static [&'static str] =
+        &["id", "name", "capacity", "layout", "opens", "closes"];
+
+    fn to_row(&self) -> Vec<Value> {
+        vec![
+            Value::Integer(self.id() as i64),
+            Value::Text(self.name().to_string()),
+            Value::Integer(self.capacity() as i64),
+            Value::Text(self.layout().join("/")),
+            Value::Text(self.opens().to_string()),
+            Value::Text(self.closes().to_string()),
+        ]
+    }
+
+    fn from_row(row: &Row) -> Result<Hall, String> {
+        let mut hall = Hall::new(
+            column(row, 0)?,
+            &column::<String>(row, 1)?,
+            column(row, 2)?,
+            parse_layout(&column::<String>(row, 3)?),
+        )?;
+        hall.set_opening_hours(&column::<String>(row, 4)?, &column::<String>(row, 5)?)?;
+        Ok(hall)
+    }
+}
+
+impl SqlEntity for Promotion {
+    const TABLE: &'static str = "promotions";
+    const COLUMNS: &'static [&'static str] = &[
+        "id",
+        "code",
+        "benefit",
+        "valid_from",
+        "valid_until",
+        "days",
+        "categories",
+        "max_uses",
+        "max_uses_per_card",
+        "stackable",
+        "min_spend",
+    ];
+
+    fn to_row(&self) -> Vec<Value> {
+        let optional = |x: Option<u32>| match x {
+            None => Value::Null,
+            Some(x) => Value::Integer(x as i64),
+        };
+
+        vec![
+            Value::Integer(self.id() as i64),
+            Value::Text(self.code().to_string()),
+            Value::Text(self.benefit().to_string()),
+            Value::Text(self.valid_from().to_iso_string()),
+            Value::Text(self.valid_until().to_iso_string()),
+            Value::Text(
+                self.days()
+                    .iter()
+                    .map(|day| day.to_string())
+                    .collect::<Vec<_>>()
+                    .join(";"),
+            ),
+            Value::Text(
+                self.categories()
+                    .iter()
+                    .map(|category| category.to_string())
+                    .collect::<Vec<_>>()
+                    .join(";"),
+            ),
+            optional(self.max_uses()),
+            optional(self.max_uses_per_card()),
+            Value::Integer(self.stackable() as i64),
+            match self.min_spend() {
+                None => Value::Null,
+                Some(x) => Value::Text(x.to_plain_string()),
+            },
+        ]
+    }
+
+    fn from_row(row: &Row) -> Result<Promotion, String> {
+        let mut promotion = Promotion::new(
+            column(row, 0)?,
+            &column::<String>(row, 1)?,
+            column::<String>(row, 2)?.parse()?,
+            Date::from_iso_string(&column::<String>(row, 3)?)?,
+            Date::from_iso_string(&column::<String>(row, 4)?)?,
+        )?;
+        promotion.set_days(parse_days(&column::<String>(row, 5)?)?)?;
+        promotion.set_categories(parse_tickets(&column::<String>(row, 6)?)?)?;
+        promotion.set_max_uses(column(row, 7)?)?;
+        promotion.set_max_uses_per_card(column(row, 8)?)?;
+        promotion.set_stackable(column(row, 9)?)?;
+        promotion.set_min_spend(match column::<Option<String>>(row, 10)? {
+            None => None,
+            Some(x) => Some(x.parse()?),
+        })?;
+        Ok(promotion)
+    }
+}
+
+impl SqlEntity for GiftCard {
+    const TABLE: &'static str = "gift_cards";
+    const COLUMNS: &'static [&'static str] = &[
+        "id",
+        "code",
+        "value_minor",
+        "balance_minor",
+        "currency",
+        "expires",
+    ];
+
+    fn to_row(&self) -> Vec<Value> {
+        vec![
+            Value::Integer(self.id() as i64),
+            Value::Text(self.code().to_string()),
+            Value::Integer(self.value().minor()),
+            Value::Integer(self.balance().minor()),
+            Value::Text(self.value().currency().code().to_string()),
+            Value::Text(self.expires().to_iso_string()),
+        ]
+    }
+
+    fn from_row(row: &Row) -> Result<GiftCard, String> {
+        let currency: Currency = column::<String>(row, 4)?.parse()?;
+        let mut gift_card = GiftCard::new(
+            column(row, 0)?,
+            &column::<String>(row, 1)?,
+            Money::new(column(row, 2)?, currency),
+            Date::from_iso_string(&column::<String>(row, 5)?)?,
+        )?;
+        gift_card.set_balance(Money::new(column(row, 3)?, currency))?;
+        Ok(gift_card)
+    }
+}
+
+impl SqlEntity for GiftCardEntry {
+    const TABLE: &'static str = "gift_card_ledger";
+    const COLUMNS: &'static [&'static str] = &[
+        "id",
+        "gift_card_id",
+        "date",
+        "movement",
+        "amount_minor",
+        "currency",
+        "reservation_id",
+    ];
+
+    fn to_row(&self) -> Vec<Value> {
+        vec![
+            Value::Integer(self.id() as i64),
+            Value::Integer(self.gift_card_id() as i64),
+            Value::Text(self.date().to_iso_string()),
+            Value::Text(self.movement().to_string()),
+            Value::Integer(self.amount().minor()),
+            Value::Text(self.amount().currency().code().to_string()),
+            match self.reservation_id() {
+                None => Value::Null,
+                Some(x) => Value::Integer(x as i64),
+            },
+        ]
+    }
+
+    fn from_row(row: &Row) -> Result<GiftCardEntry, String> {
+        GiftCardEntry::new(
+            column(row, 0)?,
+            column(row, 1)?,
+            Date::from_iso_string(&column::<String>(row, 2)?)?,
+            column::<String>(row, 3)?.parse()?,
+            Money::new(column(row, 4)?, column::<String>(row, 5)?.parse()?),
+            column(row, 6)?,
+        )
+    }
+}
+
+impl SqlEntity for SeatHold {
+    const TABLE: &'static str = "holds";
+    const COLUMNS: &'static [&'static str] = &["id", "screening_id", "seats", "expires_at"];
+
+    fn to_row(&self) -> Vec<Value> {
+        vec![
+            Value::Integer(self.id() as i64),
+            Value::Integer(self.screening_id() as i64),
+            Value::Text(self.seats().join(";")),
+            Value::Integer(self.expires_at()),
+        ]
+    }
+
+    fn from_row(row: &Row) -> Result<SeatHold, String> {
+        SeatHold::new(
+            column(row, 0)?,
+            column(row, 1)?,
+            parse_seats(&column::<String>(row, 2)?),
+            column(row, 3)?,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::clock::FixedClock;
+    use crate::domain::money::Currency;
+    use crate::repo::repo_file::RepoFile;
+    use crate::test_util::TempDir;
+    use crate::validators::context;
+    use std::sync::Arc;
+
+    fn date(day: u8, month: u8, year: u32) -> Date {
+        Date::new(day, month, year).unwrap()
+    }
+
+    fn cards() -> Vec<Card> {
+        let registered = date(1, 6, 2026);
+        vec![
+            Card::new(
+                1,
+                "Ana",
+                "Pop",
+                "6120502123456",
+                date(2, 5, 2012),
+                registered,
+                0,
+            )
+            .unwrap(),
+            Card::new(
+                2,
+                "Ion",
+                "Rusu",
+                "5010101123456",
+                date(1, 1, 2001),
+                registered,
+                5,
+            )
+            .unwrap(),
+            Card::new(
+                3,
+                "Dan",
+                "Mihai",
+                "1850315123456",
+                date(15, 3, 1985),
+                registered,
+                5,
+            )
+            .unwrap(),
+        ]
+    }
+
+    // Two movies and their screenings on 10 and 11.6.2026, the second movie
+    // at noon and in the evening, and a reservation for each of them.
+    fn fill(
+        movies: &mut dyn Repository<Movie>,
+        halls: &mut dyn Repository<Hall>,
+        screenings: &mut dyn Repository<Screening>,
+        reservations: &mut dyn Repository<Reservation>,
+    ) {
+        let price = Money::new(2500, Currency::Ron);
+        movies
+            .add_elem(Movie::new(1, "Dune", 2021, price, true, 155).unwrap())
+            .unwrap();
+        movies
+            .add_elem(Movie::new(2, "Arrival", 2016, price, true, 116).unwrap())
+            .unwrap();
+        halls
+            .add_elem(Hall::new(1, "Mare", 10, vec!["SSSSSSSSSS".into()]).unwrap())
+            .unwrap();
+
+        let shows = [
+            Screening::new(1, 1, 1, date(10, 6, 2026), "18:00".into()).unwrap(),
+            Screening::new(2, 2, 1, date(10, 6, 2026), "12:00".into()).unwrap(),
+            Screening::new(3, 2, 1, date(11, 6, 2026), "21:00".into()).unwrap(),
+        ];
+        for (id, screening) in shows.iter().enumerate() {
+            screenings.add_elem(screening.clone()).unwrap();
+            let seat = format!("A{}", id + 1);
+            let card_id = if id == 0 { Some(1) } else { None };
+            reservations
+                .add_elem(
+                    Reservation::for_screening(id as u32 + 1, screening, card_id, vec![seat])
+                        .unwrap(),
+                )
+                .unwrap();
+        }
+    }
+
+    fn ids(reservations: &[Reservation]) -> Vec<u32> {
+        reservations.iter().map(|r| r.id()).collect()
+    }
+
+    #[test]
+    fn a_new_database_gets_every_migration() {
+        let dir = TempDir::new("sqlite-new");
+        let database = Database::open(&dir.path("cinema.db")).unwrap();
+
+        let version: usize = database
+            .conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, MIGRATIONS.len());
+
+        // opening it again changes nothing
+        drop(database);
+        assert!(Database::open(&dir.path("cinema.db")).is_ok());
+    }
+
+    #[test]
+    fn an_old_database_is_upgraded_with_its_rows() {
+        let dir = TempDir::new("sqlite-upgrade");
+        let conn = Connection::open(dir.path("cinema.db")).unwrap();
+        conn.execute_batch(MIGRATIONS[0]).unwrap();
+        conn.execute_batch(
+            "
+            INSERT INTO movies VALUES (1, 'Dune', 2021, 25, 1);
+            INSERT INTO reservations VALUES (1, 1, NULL, '2026-06-10', '18:00');
+            INSERT INTO reservations VALUES (2, 1, NULL, '2026-06-10', '18:00');
+            INSERT INTO reservations VALUES (3, 1, NULL, '2026-06-11', '18:00');
+            PRAGMA user_version = 1;
+            ",
+        )
+        .unwrap();
+        drop(conn);
+
+        let database = Database::open(&dir.path("cinema.db")).unwrap();
+        let movies = RepoSqlite::<Movie>::new(&database).unwrap();
+        let halls = RepoSqlite::<Hall>::new(&database).unwrap();
+        let screenings = RepoSqlite::<Screening>::new(&database).unwrap();
+        let reservations = RepoSqlite::<Reservation>::new(&database).unwrap();
+
+        let movie = movies.get_elem(0).unwrap();
+        assert_eq!(movie.price(), Money::new(2500, Currency::Ron));
+        assert_eq!(movie.runtime(), 120);
+
+        // one screening per showing, in a hall 1 made for them
+        assert_eq!(halls.get_all().len(), 1);
+        assert_eq!(halls.get_elem(0).unwrap().capacity(), 100);
+        let shows: Vec<(u32, String)> = screenings
+            .get_all()
+            .iter()
+            .map(|s| (s.hall_id(), s.date().to_string()))
+            .collect();
+        assert_eq!(
+            shows,
+            vec![
+                (1, date(10, 6, 2026).to_string()),
+                (1, date(11, 6, 2026).to_string())
+            ]
+        );
+        let linked: Vec<u32> = reservations
+            .get_all()
+            .iter()
+            .map(|r| r.screening_id())
+            .collect();
+        assert_eq!(linked, vec![1, 1, 2]);
+        assert!(reservations.get_elem(0).unwrap().price().is_none());
+    }
+
+    #[test]
+    fn a_database_from_a_newer_version_is_refused() {
+        let dir = TempDir::new("sqlite-newer");
+        let conn = Connection::open(dir.path("cinema.db")).unwrap();
+        conn.pragma_update(None, "user_version", MIGRATIONS.len() + 1)
+            .unwrap();
+        drop(conn);
+
+        assert_eq!(
+            Database::open(&dir.path("cinema.db")).err().unwrap(),
+            "The database was made by a newer version of this program."
+        );
+    }
+
+    #[test]
+    fn rows_must_refer_to_rows_that_exist() {
+        context::set_clock(Arc::new(FixedClock::new(date(1, 6, 2026))));
+        let dir = TempDir::new("sqlite-foreign-keys");
+        let database = Database::open(&dir.path("cinema.db")).unwrap();
+        let mut screenings = RepoSqlite::<Screening>::new(&database).unwrap();
+        let mut reservations = RepoSqlite::<Reservation>::new(&database).unwrap();
+
+        let screening = Screening::new(1, 1, 1, date(10, 6, 2026), "18:00".into()).unwrap();
+        assert!(screenings.add_elem(screening.clone()).is_err());
+        let reservation = Reservation::for_screening(1, &screening, None, vec![]).unwrap();
+        assert!(reservations.add_elem(reservation).is_err());
+        assert!(screenings.get_all().is_empty());
+        assert!(reservations.get_all().is_empty());
+    }
+
+    #[test]
+    fn two_cards_cant_have_the_same_cnp() {
+        context::set_clock(Arc::new(FixedClock::new(date(1, 6, 2026))));
+        let dir = TempDir::new("sqlite-cnp");
+        let database = Database::open(&dir.path("cinema.db")).unwrap();
+        let mut repo = RepoSqlite::<Card>::new(&database).unwrap();
+
+        let first = cards().remove(0);
+        let mut twin = first.clone();
+        twin.set_id(2).unwrap();
+        repo.add_elem(first).unwrap();
+        assert!(repo.add_elem(twin).is_err());
+        assert_eq!(repo.get_all().len(), 1);
+    }
+
+    #[test]
+    fn reports_match_the_csv_files() {
+        context::set_clock(Arc::new(FixedClock::new(date(1, 6, 2026))));
+        let dir = TempDir::new("sqlite-reports");
+        let database = Database::open(&dir.path("cinema.db")).unwrap();
+        let mut sql_movies = RepoSqlite::<Movie>::new(&database).unwrap();
+        let mut sql_halls = RepoSqlite::<Hall>::new(&database).unwrap();
+        let mut sql_screenings = RepoSqlite::<Screening>::new(&database).unwrap();
+        let mut sql_reservations = RepoSqlite::<Reservation>::new(&database).unwrap();
+        let mut sql_cards = RepoSqlite::<Card>::new(&database).unwrap();
+        let mut movies = RepoFile::<Movie>::new(&dir.path("movies.csv"));
+        let mut halls = RepoFile::<Hall>::new(&dir.path("halls.csv"));
+        let mut screenings = RepoFile::<Screening>::new(&dir.path("screenings.csv"));
+        let mut reservations = RepoFile::<Reservation>::new(&dir.path("reservations.csv"));
+        let mut file_cards = RepoFile::<Card>::new(&dir.path("cards.csv"));
+        for card in cards() {
+            sql_cards.add_elem(card.clone()).unwrap();
+            file_cards.add_elem(card).unwrap();
+        }
+        fill(
+            &mut sql_movies,
+            &mut sql_halls,
+            &mut sql_screenings,
+            &mut sql_reservations,
+        );
+        fill(&mut movies, &mut halls, &mut screenings, &mut reservations);
+
+        for (start, end) in [("11:00", "19:00"), ("18.00", "23:59"), ("00:00", "09:00")] {
+            assert_eq!(
+                ids(&sql_reservations.in_hour_range(start, end).unwrap()),
+                ids(&reservations.in_hour_range(start, end).unwrap())
+            );
+        }
+        assert_eq!(
+            sql_reservations.count_by_movie().unwrap(),
+            reservations.count_by_movie().unwrap()
+        );
+        assert_eq!(
+            sql_reservations.count_by_movie().unwrap(),
+            vec![(2, 2), (1, 1)]
+        );
+
+        // everyone born before 2002, so not the first card
+        let (start, end) = (date(1, 1, 1925), date(31, 12, 2001));
+        assert_eq!(
+            sql_cards.add_points_for_birthdays(start, end, 10).unwrap(),
+            file_cards.add_points_for_birthdays(start, end, 10).unwrap()
+        );
+        let points = |cards: &dyn Repository<Card>| -> Vec<u32> {
+            cards.get_all().iter().map(|c| c.points()).collect()
+        };
+        assert_eq!(points(&sql_cards), points(&file_cards));
+        assert_eq!(points(&sql_cards), vec![0, 15, 15]);
+        assert_eq!(
+            sql_cards
+                .sorted_by_points()
+                .unwrap()
+                .iter()
+                .map(|c| c.id())
+                .collect::<Vec<_>>(),
+            file_cards
+                .sorted_by_points()
+                .unwrap()
+                .iter()
+                .map(|c| c.id())
+                .collect::<Vec<_>>()
+        );
+
+        let day = date(10, 6, 2026);
+        assert_eq!(
+            sql_reservations.remove_in_date_range(day, day).unwrap(),
+            reservations.remove_in_date_range(day, day).unwrap()
+        );
+        assert_eq!(ids(sql_reservations.get_all()), ids(reservations.get_all()));
+        assert_eq!(ids(sql_reservations.get_all()), vec![3]);
+    }
+}
@@ -1,4 +1,83 @@
-pub trait Serializable {
+use crate::domain::card::Card;
+use crate::domain::date::Date;
+use crate::domain::reservation::Reservation;
+use std::str::FromStr;
+
+/// Conversion to and from one line of a CSV data file.
+pub trait Serializable: Sized {
     fn to_csv(&self) -> String;
-    fn from_csv_to_obj(s: &str) -> Self;
+    fn from_csv_to_obj(s: &str) -> Result<Self, String>;
+}
+
+/// The fields of a line written as "a","b","c". Only the outer quotes are
+/// taken off, so an empty last field is still there.
+pub fn csv_fields(line: &str) -> std::str::Split<'_, &str> {
+    let line = line.trim();
+    let line = line.strip_prefix('"').unwrap_or(line);
+    let line = line.strip_suffix('"').unwrap_or(line);
+    line.split("\",\"")
+}
+
+/// Takes the next CSV field and parses it, naming the field if that fails.
+pub fn parse_field<'a, F, I>(parts: &mut I, name: &str) -> Result<F, String>
+where
+    F: FromStr,
+    I: Iterator<Item = &'a str>,
+{
+    match parts.next() {
+        None => Err(format!("The {} is missing.", name)),
+        Some(x) => x.parse().map_err(|_| format!("Invalid {}: {}", name, x)),
+    }
+}
+
+/// Like `parse_field`, for a column that older files don't have yet.
+pub fn parse_optional_field<'a, F, I>(parts: &mut I, name: &str, default: F) -> Result<F, String>
+where
+    F: FromStr,
+    I: Iterator<Item = &'a str>,
+{
+    match parts.next() {
+        None => Ok(default),
+        Some(x) => x.parse().map_err(|_| format!("Invalid {}: {}", name, x)),
+    }
+}
+
+/// Entities are told apart by their id, which the services keep unique.
+pub trait Identifiable {
+    fn id(&self) -> u32;
+}
+
+/// Implemented by every storage backend, so the services don't care
+/// whether the data lives in a CSV file or in a database.
+pub trait Repository<T> {
+    fn add_elem(&mut self, elem: T) -> Result<(), String>;
+    fn remove_elem(&mut self, index: usize) -> Result<(), String>;
+    fn update_elem(&mut self, index: usize, elem: T) -> Result<(), String>;
+    fn get_all(&self) -> &Vec<T>;
+    fn get_elem(&self, index: usize) -> Result<&T, String>;
+}
+
+/// Card storage, with the card reports done by the backend.
+pub trait CardRepository: Repository<Card> {
+    /// Report 2.7: all cards, the most points first.
+    fn sorted_by_points(&self) -> Result<Vec<Card>, String>;
+    /// Report 2.9: adds `points` to every card whose holder was born between
+    /// `start` and `end`, inclusive. Returns how many cards were changed.
+    fn add_points_for_birthdays(
+        &mut self,
+        start: Date,
+        end: Date,
+        points: u32,
+    ) -> Result<usize, String>;
+}
+
+/// Reservation storage, with the reservation reports done by the backend.
+pub trait ReservationRepository: Repository<Reservation> {
+    /// Report 2.5: reservations between two "hh:mm" hours, inclusive, on any day.
+    fn in_hour_range(&self, start: &str, end: &str) -> Result<Vec<Reservation>, String>;
+    /// Report 2.6: (movie id, number of reservations), most reserved first.
+    fn count_by_movie(&self) -> Result<Vec<(u32, usize)>, String>;
+    /// Report 2.8: removes the reservations between two dates, inclusive.
+    /// Returns how many were removed.
+    fn remove_in_date_range(&mut self, start: Date, end: Date) -> Result<usize, String>;
 }
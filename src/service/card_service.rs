@@ -1,13 +1,29 @@
 use crate::domain::card::Card;
-use crate::repo::repo_file::RepoFile;
+use crate::domain::clock::Clock;
+use crate::domain::date::Date;
+use crate::repo::traits::CardRepository;
+use crate::validators::card_validator::{card_rules_validator, card_validator};
+use crate::validators::context;
+use std::sync::Arc;
 
+/// CRUD and reports for client cards. A CNP can only be on one card.
 pub struct CardService<'a> {
-    cards: &'a mut RepoFile<Card>,
+    cards: &'a mut dyn CardRepository,
+    clock: Arc<dyn Clock>,
 }
 
 impl<'a> CardService<'a> {
-    pub fn new(repo: &'a mut RepoFile<Card>) -> Self {
-        CardService { cards: repo }
+    pub fn new(repo: &'a mut dyn CardRepository) -> Self {
+        CardService {
+            cards: repo,
+            clock: context::clock(),
+        }
+    }
+
+    /// The date the card rules are checked against on update. The
+    /// validation context's clock by default.
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
     }
 
     pub fn get_all(&self) -> &Vec<Card> {
@@ -31,7 +47,14 @@ impl<'a> CardService<'a> {
     }
 
     pub fn update(&mut self, index: usize, new_card: Card) -> Result<(), String> {
-        let mut found = false;
+        if index >= self.cards.get_all().len() {
+            return Err("There is no card with that ID.".into());
+        }
+
+        // the card was valid when it was built, but the rules depend on the
+        // current date and configuration, so they are checked again
+        card_validator(&new_card, &*self.clock)?;
+
         for (i, card) in self.cards.get_all().iter().enumerate() {
             if i != index {
                 if new_card.cnp() == card.cnp() {
@@ -39,19 +62,44 @@ impl<'a> CardService<'a> {
                 } else if new_card.id() == card.id() {
                     return Err("A different card with that ID already exists".into());
                 }
-
-                found = true;
             }
         }
 
-        if found == true {
-            self.cards.update_elem(index, new_card)
-        } else {
-            Err("There is no card with that ID.".into())
-        }
+        self.cards.update_elem(index, new_card)
+    }
+
+    /// The stored cards that break the rules spanning several fields, with
+    /// why. They were saved before the rules existed; updating them with the
+    /// right CNP, birthday or registration date fixes them.
+    pub fn rule_violations(&self) -> Vec<(u32, String)> {
+        self.cards
+            .get_all()
+            .iter()
+            .filter_map(|card| match card_rules_validator(card, &*self.clock) {
+                Result::Ok(_) => None,
+                Result::Err(err) => Some((card.id(), err)),
+            })
+            .collect()
     }
 
     pub fn remove(&mut self, index: usize) -> Result<(), String> {
         self.cards.remove_elem(index)
     }
+
+    pub fn sorted_by_points(&self) -> Result<Vec<Card>, String> {
+        self.cards.sorted_by_points()
+    }
+
+    pub fn add_points_for_birthdays(
+        &mut self,
+        start: Date,
+        end: Date,
+        points: u32,
+    ) -> Result<usize, String> {
+        if start > end {
+            return Err("The start of the interval must not be after its end.".into());
+        }
+
+        self.cards.add_points_for_birthdays(start, end, points)
+    }
 }
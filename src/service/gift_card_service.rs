@@ -0,0 +1,259 @@
+use crate::domain::clock::Clock;
+use crate::domain::gift_card::{GiftCard, GiftCardEntry, GiftCardMovement};
+use crate::domain::money::{Money, Rounding};
+use crate::repo::traits::Repository;
+use crate::validators::context;
+use std::cmp::Ordering;
+use std::sync::Arc;
+
+// A gift card's id, what it paid for a reservation, and what it got back.
+type Payment = (u32, Money, Option<Money>);
+
+/// Gift cards and their ledger. Every time money goes onto a card or off it,
+/// the balance changes and a ledger entry says why. Codes are unique.
+pub struct GiftCardService<'a> {
+    gift_cards: &'a mut dyn Repository<GiftCard>,
+    ledger: &'a mut dyn Repository<GiftCardEntry>,
+    clock: Arc<dyn Clock>,
+}
+
+impl<'a> GiftCardService<'a> {
+    pub fn new(
+        gift_cards: &'a mut dyn Repository<GiftCard>,
+        ledger: &'a mut dyn Repository<GiftCardEntry>,
+    ) -> Self {
+        GiftCardService {
+            gift_cards,
+            ledger,
+            clock: context::clock(),
+        }
+    }
+
+    /// Where expiry dates and ledger entries get the date from. The
+    /// validation context's clock by default.
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    pub fn get_all(&self) -> &Vec<GiftCard> {
+        self.gift_cards.get_all()
+    }
+
+    pub fn get(&self, index: usize) -> Result<&GiftCard, String> {
+        self.gift_cards.get_elem(index)
+    }
+
+    pub fn find(&self, code: &str) -> Option<&GiftCard> {
+        let code = code.trim().to_uppercase();
+        self.gift_cards.get_all().iter().find(|g| g.code() == code)
+    }
+
+    /// Adds a gift card that was just bought, and its value to the ledger.
+    pub fn issue(&mut self, new_gift_card: GiftCard) -> Result<(), String> {
+        for gift_card in self.gift_cards.get_all() {
+            if gift_card.code() == new_gift_card.code() {
+                return Err("A gift card with this code already exists.".into());
+            } else if gift_card.id() == new_gift_card.id() {
+                return Err("A gift card with this ID already exists.".into());
+            }
+        }
+        if new_gift_card.balance() != new_gift_card.value() {
+            return Err("A new gift card must have all of its value left.".into());
+        }
+
+        let entry = self.entry(
+            new_gift_card.id(),
+            GiftCardMovement::Issue,
+            new_gift_card.value(),
+            None,
+        )?;
+        self.gift_cards.add_elem(new_gift_card)?;
+        self.ledger.add_elem(entry)
+    }
+
+    /// What is left on the gift card. Anyone with the code can ask.
+    pub fn balance(&self, code: &str) -> Result<Money, String> {
+        Ok(self.gift_card(code)?.1.balance())
+    }
+
+    /// The gift card's ledger entries, oldest first.
+    pub fn ledger(&self, code: &str) -> Result<Vec<&GiftCardEntry>, String> {
+        let id = self.gift_card(code)?.1.id();
+        Ok(self
+            .ledger
+            .get_all()
+            .iter()
+            .filter(|e| e.gift_card_id() == id)
+            .collect())
+    }
+
+    /// What the gift card can pay of `due`: all of it if the card has
+    /// enough on it, otherwise all that is left on the card.
+    pub fn payable(&self, code: &str, due: Money) -> Result<Money, String> {
+        let gift_card = self.gift_card(code)?.1;
+
+        if gift_card.is_expired_on(self.clock.today()) {
+            return Err(format!("The gift card {} has expired.", gift_card.code()));
+        } else if gift_card.balance().is_zero() {
+            return Err(format!("The gift card {} is empty.", gift_card.code()));
+        }
+
+        match gift_card.balance().compare(&due)? {
+            Ordering::Less => Ok(gift_card.balance()),
+            _ => Ok(due),
+        }
+    }
+
+    /// Takes `amount` off the gift card for the reservation. Only
+    /// `ReservationService::pay_with_gift_card` calls it, once it has checked
+    /// the reservation and recorded the payment on it.
+    pub(crate) fn pay(
+        &mut self,
+        code: &str,
+        reservation_id: u32,
+        amount: Money,
+    ) -> Result<(), String> {
+        if self.payable(code, amount)? != amount {
+            return Err("There is not enough left on the gift card.".into());
+        }
+
+        let (index, gift_card) = self.gift_card(code)?;
+        let mut gift_card = gift_card.clone();
+        let entry = self.entry(
+            gift_card.id(),
+            GiftCardMovement::Payment,
+            amount,
+            Some(reservation_id),
+        )?;
+        gift_card.set_balance(gift_card.balance().checked_sub(amount)?)?;
+        self.gift_cards.update_elem(index, gift_card)?;
+        self.ledger.add_elem(entry)
+    }
+
+    /// For a reservation `ReservationService::cancel` is cancelling, gives
+    /// each gift card that paid for it `percent` percent of what it paid,
+    /// rounded down, and returns the total. A card that has expired gets
+    /// nothing back, so its share is refunded in money along with the rest.
+    /// A card an earlier attempt already refunded isn't refunded again, but
+    /// what it got counts in the total, so a cancellation that failed half
+    /// way can be tried again.
+    pub(crate) fn refund(&mut self, reservation_id: u32, percent: u32) -> Result<Money, String> {
+        let mut refunded = vec![];
+
+        for (gift_card_id, paid, earlier) in self.payments(reservation_id)? {
+            if let Some(amount) = earlier {
+                refunded.push(amount);
+                continue;
+            }
+
+            let index = match self
+                .gift_cards
+                .get_all()
+                .iter()
+                .position(|g| g.id() == gift_card_id)
+            {
+                Some(index) => index,
+                None => return Err("The ledger refers to a gift card that doesn't exist.".into()),
+            };
+            let mut gift_card = self.gift_cards.get_elem(index)?.clone();
+
+            // the entry is written even when nothing comes back, so that the
+            // payment counts as settled if the reservation ID is used again
+            let amount = if gift_card.is_expired_on(self.clock.today()) {
+                Money::zero(paid.currency())
+            } else {
+                paid.percent(percent, Rounding::Down)?
+            };
+
+            let entry = self.entry(
+                gift_card_id,
+                GiftCardMovement::Refund,
+                amount,
+                Some(reservation_id),
+            )?;
+            gift_card.set_balance(gift_card.balance().checked_add(amount)?)?;
+            self.gift_cards.update_elem(index, gift_card)?;
+            self.ledger.add_elem(entry)?;
+
+            refunded.push(amount);
+        }
+
+        let currency = refunded.first().map(|x| x.currency()).unwrap_or_default();
+        Money::sum(refunded, currency)
+    }
+
+    fn gift_card(&self, code: &str) -> Result<(usize, &GiftCard), String> {
+        let code = code.trim().to_uppercase();
+        match self
+            .gift_cards
+            .get_all()
+            .iter()
+            .enumerate()
+            .find(|(_, g)| g.code() == code)
+        {
+            Some(found) => Ok(found),
+            None => Err(format!("There is no gift card {}.", code)),
+        }
+    }
+
+    // The gift cards that paid for the reservation, in the order they were
+    // used, each with what it paid and what it got back if it was refunded
+    // since. Once every one of them has been refunded the payments are
+    // settled, and a payment for a later reservation with the same ID starts
+    // afresh.
+    fn payments(&self, reservation_id: u32) -> Result<Vec<Payment>, String> {
+        let mut payments: Vec<Payment> = vec![];
+
+        for entry in self.ledger.get_all() {
+            if entry.reservation_id() != Some(reservation_id) {
+                continue;
+            }
+
+            let gift_card_id = entry.gift_card_id();
+            match entry.movement() {
+                GiftCardMovement::Payment => {
+                    if payments.iter().all(|(_, _, refunded)| refunded.is_some()) {
+                        payments.clear();
+                    }
+                    match payments.iter_mut().find(|(id, _, _)| *id == gift_card_id) {
+                        Some((_, amount, _)) => *amount = amount.checked_add(entry.amount())?,
+                        None => payments.push((gift_card_id, entry.amount(), None)),
+                    }
+                }
+                GiftCardMovement::Refund => {
+                    if let Some((_, _, refunded)) =
+                        payments.iter_mut().find(|(id, _, _)| *id == gift_card_id)
+                    {
+                        *refunded = Some(entry.amount());
+                    }
+                }
+                GiftCardMovement::Issue => {}
+            }
+        }
+
+        Ok(payments)
+    }
+
+    // A ledger entry for today, with the next free id.
+    fn entry(
+        &self,
+        gift_card_id: u32,
+        movement: GiftCardMovement,
+        amount: Money,
+        reservation_id: Option<u32>,
+    ) -> Result<GiftCardEntry, String> {
+        let id = match self.ledger.get_all().iter().map(|e| e.id()).max() {
+            Some(id) => id + 1,
+            None => 1,
+        };
+
+        GiftCardEntry::new(
+            id,
+            gift_card_id,
+            self.clock.today(),
+            movement,
+            amount,
+            reservation_id,
+        )
+    }
+}
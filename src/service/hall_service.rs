@@ -0,0 +1,55 @@
+use crate::domain::hall::Hall;
+use crate::repo::traits::Repository;
+
+/// CRUD for halls. Names are unique.
+pub struct HallService<'a> {
+    halls: &'a mut dyn Repository<Hall>,
+}
+
+impl<'a> HallService<'a> {
+    pub fn new(repo: &'a mut dyn Repository<Hall>) -> Self {
+        HallService { halls: repo }
+    }
+
+    pub fn get_all(&self) -> &Vec<Hall> {
+        self.halls.get_all()
+    }
+
+    pub fn get(&self, index: usize) -> Result<&Hall, String> {
+        self.halls.get_elem(index)
+    }
+
+    pub fn add(&mut self, new_hall: Hall) -> Result<(), String> {
+        for hall in self.halls.get_all() {
+            if hall.name() == new_hall.name() {
+                return Err("A hall with this name already exists.".into());
+            } else if hall.id() == new_hall.id() {
+                return Err("A hall with this ID already exists.".into());
+            }
+        }
+
+        self.halls.add_elem(new_hall)
+    }
+
+    pub fn update(&mut self, index: usize, new_hall: Hall) -> Result<(), String> {
+        if index >= self.halls.get_all().len() {
+            return Err("There is no hall with that ID".into());
+        }
+
+        for (i, hall) in self.halls.get_all().iter().enumerate() {
+            if i != index {
+                if new_hall.name() == hall.name() {
+                    return Err("A different hall with that name already exists".into());
+                } else if new_hall.id() == hall.id() {
+                    return Err("A different hall with that ID already exists".into());
+                }
+            }
+        }
+
+        self.halls.update_elem(index, new_hall)
+    }
+
+    pub fn remove(&mut self, index: usize) -> Result<(), String> {
+        self.halls.remove_elem(index)
+    }
+}
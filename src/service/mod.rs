@@ -1,3 +1,10 @@
+//! Business rules that span several records or entities.
+
 pub mod card_service;
+pub mod gift_card_service;
+pub mod hall_service;
 pub mod movie_service;
+pub mod program_planner;
+pub mod promotion_service;
 pub mod reservation_service;
+pub mod screening_service;
@@ -1,13 +1,102 @@
-use crate::domain::movie::Movie;
-use crate::repo::repo_file::RepoFile;
+use crate::config::{MovieConfig, MovieKeyField, ScheduleConfig};
+use crate::domain::movie::{AgeRating, Movie, MovieFormat};
+use crate::domain::screening::Screening;
+use crate::repo::movie_json::{movies_from_json, movies_to_json};
+use crate::repo::traits::Repository;
+use crate::service::screening_service::screenings_overlap;
 
+/// What to look for in `MovieService::search`. Fields left as None match
+/// every movie.
+#[derive(Clone, Debug, Default)]
+pub struct MovieFilter {
+    /// part of the title, in any case
+    pub title: Option<String>,
+    pub genre: Option<String>,
+    /// movies rated this or lower, e.g. AP-12 also finds AG movies
+    pub max_age_rating: Option<AgeRating>,
+    pub language: Option<String>,
+    pub subtitles: Option<String>,
+    pub format: Option<MovieFormat>,
+    pub min_runtime: Option<u32>,
+    pub max_runtime: Option<u32>,
+    pub in_program: Option<bool>,
+}
+
+impl MovieFilter {
+    pub fn matches(&self, movie: &Movie) -> bool {
+        let title = match &self.title {
+            None => true,
+            Some(x) => movie.title().to_lowercase().contains(&x.to_lowercase()),
+        };
+        let genre = match &self.genre {
+            None => true,
+            Some(x) => movie.genres().iter().any(|g| g.eq_ignore_ascii_case(x)),
+        };
+
+        title
+            && genre
+            && self.max_age_rating.is_none_or(|x| movie.age_rating() <= x)
+            && self
+                .language
+                .as_ref()
+                .is_none_or(|x| movie.language() == Some(x.as_str()))
+            && self
+                .subtitles
+                .as_ref()
+                .is_none_or(|x| movie.subtitles() == Some(x.as_str()))
+            && self.format.is_none_or(|x| movie.format() == x)
+            && self.min_runtime.is_none_or(|x| movie.runtime() >= x)
+            && self.max_runtime.is_none_or(|x| movie.runtime() <= x)
+            && self.in_program.is_none_or(|x| movie.in_program() == x)
+    }
+}
+
+/// CRUD for movies. Two movies can't have the same unique key: the fields in
+/// `movies.unique_key`, title and release year by default, compared the way
+/// `movie_key` does, ignoring case and extra spaces.
 pub struct MovieService<'a> {
-    movies: &'a mut RepoFile<Movie>,
+    movies: &'a mut dyn Repository<Movie>,
+    screenings: &'a dyn Repository<Screening>,
+    config: MovieConfig,
+    schedule: ScheduleConfig,
 }
 
 impl<'a> MovieService<'a> {
-    pub fn new(repo: &'a mut RepoFile<Movie>) -> Self {
-        MovieService { movies: repo }
+    pub fn new(
+        repo: &'a mut dyn Repository<Movie>,
+        screenings: &'a dyn Repository<Screening>,
+    ) -> Self {
+        MovieService {
+            movies: repo,
+            screenings,
+            config: MovieConfig::default(),
+            schedule: ScheduleConfig::default(),
+        }
+    }
+
+    pub fn set_config(&mut self, config: MovieConfig) {
+        self.config = config;
+    }
+
+    /// The cleaning time between screenings, which a longer runtime must
+    /// still leave room for.
+    pub fn set_schedule(&mut self, schedule: ScheduleConfig) {
+        self.schedule = schedule;
+    }
+
+    // e.g. "title and release year"
+    fn key_description(&self) -> String {
+        let names: Vec<&str> = self.config.unique_key.iter().map(|f| f.name()).collect();
+        match names.split_last() {
+            Some((last, [])) => last.to_string(),
+            Some((last, rest)) => format!("{} and {}", rest.join(", "), last),
+            None => String::new(),
+        }
+    }
+
+    fn same_key(&self, a: &Movie, b: &Movie) -> bool {
+        let fields = &self.config.unique_key;
+        !fields.is_empty() && movie_key(a, fields) == movie_key(b, fields)
     }
 
     pub fn get_all(&self) -> &Vec<Movie> {
@@ -20,8 +109,11 @@ impl<'a> MovieService<'a> {
 
     pub fn add(&mut self, new_movie: Movie) -> Result<(), String> {
         for movie in self.movies.get_all() {
-            if movie.title() == new_movie.title() {
-                return Err("A movie with this title already exists".into());
+            if self.same_key(movie, &new_movie) {
+                return Err(format!(
+                    "A movie with this {} already exists.",
+                    self.key_description()
+                ));
             } else if movie.id() == new_movie.id() {
                 return Err("A movie with this ID already exists.".into());
             }
@@ -31,28 +123,207 @@ impl<'a> MovieService<'a> {
     }
 
     pub fn update(&mut self, index: usize, new_movie: Movie) -> Result<(), String> {
-        let mut found = false;
+        if index >= self.movies.get_all().len() {
+            return Err("There is no movie with that ID".into());
+        }
+
+        if new_movie.runtime() != self.movies.get_elem(index)?.runtime() {
+            self.check_screenings(&new_movie)?;
+        }
 
         for (i, movie) in self.movies.get_all().iter().enumerate() {
             if i != index {
-                if new_movie.title() == movie.title() {
-                    return Err("A different movie with that title already exists".into());
+                if self.same_key(movie, &new_movie) {
+                    return Err(format!(
+                        "A different movie with that {} already exists",
+                        self.key_description()
+                    ));
                 } else if new_movie.id() == movie.id() {
                     return Err("A different movie with that ID already exists".into());
                 }
-
-                found = true;
             }
         }
 
-        if found == true {
-            self.movies.update_elem(index, new_movie)
-        } else {
-            Err("There is no movie with that ID".into())
-        }
+        self.movies.update_elem(index, new_movie)
     }
 
     pub fn remove(&mut self, index: usize) -> Result<(), String> {
         self.movies.remove_elem(index)
     }
+
+    // With a new runtime the movie's screenings must still end, and the hall
+    // be cleaned, before the next screening in the same hall starts.
+    fn check_screenings(&self, movie: &Movie) -> Result<(), String> {
+        let runtime_of = |screening: &Screening| {
+            if screening.movie_id() == movie.id() {
+                Some(movie.runtime())
+            } else {
+                self.movies
+                    .get_all()
+                    .iter()
+                    .find(|m| m.id() == screening.movie_id())
+                    .map(|m| m.runtime())
+            }
+        };
+
+        let screenings = self.screenings.get_all();
+        for first in screenings.iter().filter(|s| s.movie_id() == movie.id()) {
+            for second in screenings.iter().filter(|s| s.id() != first.id()) {
+                // screenings of movies that no longer exist can't be checked
+                let second_runtime = match runtime_of(second) {
+                    Some(runtime) => runtime,
+                    None => continue,
+                };
+
+                if screenings_overlap(
+                    first,
+                    movie.runtime(),
+                    second,
+                    second_runtime,
+                    self.schedule.cleaning_minutes,
+                ) {
+                    return Err(format!(
+                        "With a runtime of {} minutes, screening {} would overlap screening {} on {} at {}.",
+                        movie.runtime(),
+                        first.id(),
+                        second.id(),
+                        second.date(),
+                        second.start()
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn search(&self, filter: &MovieFilter) -> Vec<Movie> {
+        self.movies
+            .get_all()
+            .iter()
+            .filter(|movie| filter.matches(movie))
+            .cloned()
+            .collect()
+    }
+
+    pub fn export_json(&self) -> Result<String, String> {
+        movies_to_json(self.movies.get_all())
+    }
+
+    /// Adds the movies of a JSON export, or none of them if one is refused.
+    /// Returns how many were added.
+    pub fn import_json(&mut self, json: &str) -> Result<usize, String> {
+        let mut added: Vec<u32> = vec![];
+
+        for movie in movies_from_json(json)? {
+            let id = movie.id();
+            let title = movie.title().to_string();
+            if let Err(err) = self.add(movie) {
+                for added_id in added.iter().rev() {
+                    if let Some(index) = self
+                        .movies
+                        .get_all()
+                        .iter()
+                        .position(|m| m.id() == *added_id)
+                    {
+                        self.movies.remove_elem(index)?;
+                    }
+                }
+
+                return Err(format!("{}: {}", title, err));
+            }
+            added.push(id);
+        }
+
+        Ok(added.len())
+    }
+}
+
+/// Lowercases and trims the text and turns every run of whitespace into a
+/// single space, so "  The  Matrix" and "the matrix" compare equal.
+pub fn normalize_key_text(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// The values of `fields` for `movie`, in a form that can be compared.
+pub fn movie_key(movie: &Movie, fields: &[MovieKeyField]) -> Vec<String> {
+    fields
+        .iter()
+        .map(|field| match field {
+            MovieKeyField::Title => normalize_key_text(movie.title()),
+            MovieKeyField::ReleaseYear => movie.release_year().to_string(),
+            MovieKeyField::Language => normalize_key_text(movie.language().unwrap_or("")),
+            MovieKeyField::Subtitles => normalize_key_text(movie.subtitles().unwrap_or("")),
+            MovieKeyField::Format => movie.format().to_string(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::clock::FixedClock;
+    use crate::domain::date::Date;
+    use crate::domain::money::{Currency, Money};
+    use crate::repo::repo_file::RepoFile;
+    use crate::test_util::TempDir;
+    use crate::validators::context;
+    use std::sync::Arc;
+
+    fn titles(movies: &RepoFile<Movie>) -> Vec<String> {
+        movies
+            .get_all()
+            .iter()
+            .map(|m| m.title().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn an_import_adds_every_movie_or_none() {
+        context::set_clock(Arc::new(FixedClock::new(Date::new(1, 6, 2026).unwrap())));
+        let dir = TempDir::new("movie-import");
+        let mut movies = RepoFile::<Movie>::new(&dir.path("movies.csv"));
+        let screenings = RepoFile::<Screening>::new(&dir.path("screenings.csv"));
+        let price = Money::new(2500, Currency::Ron);
+        movies
+            .add_elem(Movie::new(1, "Dune", 2021, price, true, 155).unwrap())
+            .unwrap();
+        let mut service = MovieService::new(&mut movies, &screenings);
+
+        let clashing = r#"[
+            {"id": 2, "title": "Arrival", "release_year": 2016, "price": 25, "in_program": true},
+            {"id": 3, "title": "Heat", "release_year": 1995, "price": "20.50 RON", "in_program": true},
+            {"id": 4, "title": "  dune ", "release_year": 2021, "price": 25, "in_program": true}
+        ]"#;
+        assert_eq!(
+            service.import_json(clashing).unwrap_err(),
+            "  dune : A movie with this title and release year already exists."
+        );
+        assert_eq!(titles(&movies), vec!["Dune"]);
+
+        let mut service = MovieService::new(&mut movies, &screenings);
+        let invalid = r#"[
+            {"id": 2, "title": "Arrival", "release_year": 2016, "price": 25, "in_program": true},
+            {"id": 3, "title": "Heat", "release_year": 1995, "price": 25, "in_program": true,
+             "age_rating": "R"}
+        ]"#;
+        assert!(service
+            .import_json(invalid)
+            .unwrap_err()
+            .starts_with("Movie number 2: "));
+
+        let fine = r#"[
+            {"id": 2, "title": "Arrival", "release_year": 2016, "price": 25, "in_program": true},
+            {"id": 3, "title": "Heat", "release_year": 1995, "price": "20.50 RON", "in_program": true}
+        ]"#;
+        assert_eq!(service.import_json(fine).unwrap(), 2);
+        assert_eq!(titles(&movies), vec!["Dune", "Arrival", "Heat"]);
+        assert_eq!(
+            movies.get_elem(2).unwrap().price(),
+            Money::new(2050, Currency::Ron)
+        );
+    }
 }
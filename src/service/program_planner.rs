@@ -0,0 +1,400 @@
+use crate::config::ScheduleConfig;
+use crate::domain::date::Date;
+use crate::domain::hall::Hall;
+use crate::domain::movie::Movie;
+use crate::domain::reservation::minutes_of_day;
+use crate::domain::screening::Screening;
+use crate::repo::traits::{Repository, ReservationRepository, Serializable};
+use std::collections::HashMap;
+
+const MINUTES_PER_DAY: u32 = 24 * 60;
+
+/// Proposes a week of screenings for the movies in program. Each hall is
+/// filled from opening to closing time, around the screenings already
+/// scheduled. Prime time goes mostly to the movies with the most
+/// reservations so far, the rest of the day is shared evenly.
+///
+/// Nothing is saved: the plan is meant to be reviewed, e.g. with
+/// `export_csv`, and then added with `ScreeningService::add_plan`.
+pub struct ProgramPlanner<'a> {
+    movies: &'a dyn Repository<Movie>,
+    halls: &'a dyn Repository<Hall>,
+    screenings: &'a dyn Repository<Screening>,
+    reservations: &'a dyn ReservationRepository,
+    schedule: ScheduleConfig,
+}
+
+impl<'a> ProgramPlanner<'a> {
+    pub fn new(
+        movies: &'a dyn Repository<Movie>,
+        halls: &'a dyn Repository<Hall>,
+        screenings: &'a dyn Repository<Screening>,
+        reservations: &'a dyn ReservationRepository,
+    ) -> Self {
+        ProgramPlanner {
+            movies,
+            halls,
+            screenings,
+            reservations,
+            schedule: ScheduleConfig::default(),
+        }
+    }
+
+    pub fn set_schedule(&mut self, schedule: ScheduleConfig) {
+        self.schedule = schedule;
+    }
+
+    /// The screenings for the seven days starting with `first_day`.
+    pub fn plan_week(&self, first_day: Date) -> Result<Vec<Screening>, String> {
+        // (movie, weight): one more than the movie's reservations so far
+        let mut movies: Vec<(&Movie, u32)> = vec![];
+        let counts = self.reservations.count_by_movie()?;
+        for movie in self.movies.get_all().iter().filter(|m| m.in_program()) {
+            let count = match counts.iter().find(|(id, _)| *id == movie.id()) {
+                Some((_, count)) => *count as u32,
+                None => 0,
+            };
+            movies.push((movie, count + 1));
+        }
+
+        if movies.is_empty() {
+            return Err("There are no movies in program.".into());
+        }
+        movies.sort_by_key(|(movie, weight)| (std::cmp::Reverse(*weight), movie.id()));
+
+        let mut halls: Vec<&Hall> = self.halls.get_all().iter().collect();
+        halls.sort_by_key(|hall| hall.id());
+
+        let prime_start = minutes_of_day(&self.schedule.prime_time_start);
+        let prime_end = minutes_of_day(&self.schedule.prime_time_end);
+
+        let mut next_id = match self.screenings.get_all().iter().map(|s| s.id()).max() {
+            Some(id) => id + 1,
+            None => 1,
+        };
+        let mut shown: HashMap<u32, u32> = HashMap::new();
+        let mut shown_in_prime_time: HashMap<u32, u32> = HashMap::new();
+        let mut plan: Vec<Screening> = vec![];
+
+        for day in 0..7 {
+            let date = match first_day.add_days(day) {
+                Some(date) => date,
+                None => return Err("The first day of the week does not exist.".into()),
+            };
+
+            for hall in &halls {
+                let (opens, closes) = hall.open_minutes();
+                let mut time = self.round_up(opens);
+
+                loop {
+                    let fitting: Vec<&(&Movie, u32)> = movies
+                        .iter()
+                        .filter(|(movie, _)| time + movie.runtime() <= closes)
+                        .collect();
+                    if fitting.is_empty() {
+                        break;
+                    }
+
+                    let time_of_day = time % MINUTES_PER_DAY;
+                    let prime_time = time_of_day >= prime_start && time_of_day < prime_end;
+
+                    // the movie furthest behind its share of the slots
+                    let (movie, _) = *fitting
+                        .iter()
+                        .min_by(|(a, a_weight), (b, b_weight)| {
+                            if prime_time {
+                                let a_shown = shown_in_prime_time.get(&a.id()).unwrap_or(&0);
+                                let b_shown = shown_in_prime_time.get(&b.id()).unwrap_or(&0);
+                                (a_shown * b_weight).cmp(&(b_shown * a_weight))
+                            } else {
+                                shown
+                                    .get(&a.id())
+                                    .unwrap_or(&0)
+                                    .cmp(shown.get(&b.id()).unwrap_or(&0))
+                            }
+                        })
+                        .unwrap();
+
+                    let screening = screening_at(next_id, movie, hall, date, time)?;
+
+                    if let Some(free_from) = self.busy_until(&screening, movie, &plan)? {
+                        // try again once the screening in the way is over
+                        let day_start = screening.start_minute() - time as i64;
+                        time = self.round_up(((free_from - day_start) as u32).max(time + 1));
+                        continue;
+                    }
+
+                    plan.push(screening);
+                    next_id += 1;
+                    *shown.entry(movie.id()).or_insert(0) += 1;
+                    if prime_time {
+                        *shown_in_prime_time.entry(movie.id()).or_insert(0) += 1;
+                    }
+
+                    time = self.round_up(time + movie.runtime() + self.schedule.cleaning_minutes);
+                }
+            }
+        }
+
+        Ok(plan)
+    }
+
+    // If the hall is not free for the screening, the minute it becomes free.
+    fn busy_until(
+        &self,
+        screening: &Screening,
+        movie: &Movie,
+        plan: &[Screening],
+    ) -> Result<Option<i64>, String> {
+        let cleaning = self.schedule.cleaning_minutes as i64;
+        let start = screening.start_minute();
+        let free_from = start + movie.runtime() as i64 + cleaning;
+
+        let mut busy_until = None;
+        for other in self.screenings.get_all().iter().chain(plan.iter()) {
+            if other.hall_id() != screening.hall_id() {
+                continue;
+            }
+
+            let runtime = match self
+                .movies
+                .get_all()
+                .iter()
+                .find(|m| m.id() == other.movie_id())
+            {
+                Some(other_movie) => other_movie.runtime() as i64,
+                None => {
+                    return Err(format!(
+                        "Screening {} is of a movie that no longer exists.",
+                        other.id()
+                    ))
+                }
+            };
+            let other_free_from = other.start_minute() + runtime + cleaning;
+
+            if start < other_free_from && other.start_minute() < free_from {
+                busy_until = Some(busy_until.unwrap_or(0).max(other_free_from));
+            }
+        }
+
+        Ok(busy_until)
+    }
+
+    fn round_up(&self, minutes: u32) -> u32 {
+        let step = self.schedule.start_step_minutes.max(1);
+        minutes.div_ceil(step) * step
+    }
+}
+
+// `time` is counted from the start of `date` and may run into the next day.
+fn screening_at(
+    id: u32,
+    movie: &Movie,
+    hall: &Hall,
+    date: Date,
+    time: u32,
+) -> Result<Screening, String> {
+    let date = match date.add_days((time / MINUTES_PER_DAY) as i64) {
+        Some(date) => date,
+        None => return Err("The screening's date does not exist.".into()),
+    };
+    let time = time % MINUTES_PER_DAY;
+
+    Screening::new(
+        id,
+        movie.id(),
+        hall.id(),
+        date,
+        format!("{:02}:{:02}", time / 60, time % 60),
+    )
+}
+
+/// The plan in the format of the screenings file, one screening per line.
+pub fn export_csv(plan: &[Screening]) -> String {
+    plan.iter().map(|s| s.to_csv() + "\n").collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::clock::FixedClock;
+    use crate::domain::money::{Currency, Money};
+    use crate::domain::reservation::Reservation;
+    use crate::repo::repo_file::RepoFile;
+    use crate::service::screening_service::{screenings_overlap, ScreeningService};
+    use crate::test_util::TempDir;
+    use crate::validators::context;
+    use std::sync::Arc;
+
+    struct Data {
+        movies: RepoFile<Movie>,
+        halls: RepoFile<Hall>,
+        screenings: RepoFile<Screening>,
+        reservations: RepoFile<Reservation>,
+        _dir: TempDir,
+    }
+
+    impl Data {
+        // Dune (155 minutes) and Arrival (116), and Cats, which is out of
+        // program, in a hall open from 10:00 to midnight, with the clock at
+        // 1.6.2026.
+        fn new(name: &str) -> Data {
+            context::set_clock(Arc::new(FixedClock::new(date(1, 6))));
+            let dir = TempDir::new(&format!("planner-{}", name));
+
+            let mut data = Data {
+                movies: RepoFile::new(&dir.path("movies.csv")),
+                halls: RepoFile::new(&dir.path("halls.csv")),
+                screenings: RepoFile::new(&dir.path("screenings.csv")),
+                reservations: RepoFile::new(&dir.path("reservations.csv")),
+                _dir: dir,
+            };
+
+            let price = Money::new(2500, Currency::Ron);
+            for (id, title, in_program, runtime) in [
+                (1, "Dune", true, 155),
+                (2, "Arrival", true, 116),
+                (3, "Cats", false, 110),
+            ] {
+                data.movies
+                    .add_elem(Movie::new(id, title, 2020, price, in_program, runtime).unwrap())
+                    .unwrap();
+            }
+            data.halls
+                .add_elem(Hall::new(1, "Mare", 10, vec!["SSSSSSSSSS".into()]).unwrap())
+                .unwrap();
+
+            data
+        }
+
+        fn planner(&self) -> ProgramPlanner<'_> {
+            ProgramPlanner::new(
+                &self.movies,
+                &self.halls,
+                &self.screenings,
+                &self.reservations,
+            )
+        }
+
+        fn runtime(&self, screening: &Screening) -> u32 {
+            let movie = self
+                .movies
+                .get_all()
+                .iter()
+                .find(|m| m.id() == screening.movie_id());
+            movie.unwrap().runtime()
+        }
+    }
+
+    fn date(day: u8, month: u8) -> Date {
+        Date::new(day, month, 2026).unwrap()
+    }
+
+    #[test]
+    fn a_week_fills_the_halls_around_what_is_scheduled() {
+        let mut data = Data::new("week");
+        let scheduled = Screening::new(7, 2, 1, date(9, 6), "14:00".into()).unwrap();
+        data.screenings.add_elem(scheduled).unwrap();
+
+        let plan = data.planner().plan_week(date(8, 6)).unwrap();
+
+        let everything: Vec<&Screening> = data.screenings.get_all().iter().chain(&plan).collect();
+        for day in 0..7 {
+            let date = date(8, 6).add_days(day).unwrap();
+            let on_day = everything.iter().filter(|s| s.date() == date).count();
+            assert!(on_day >= 4, "only {} screenings on day {}", on_day, day);
+        }
+
+        let cleaning = ScheduleConfig::default().cleaning_minutes;
+        for (i, screening) in plan.iter().enumerate() {
+            assert_eq!(screening.id(), 8 + i as u32);
+            assert_ne!(screening.movie_id(), 3);
+            assert!(screening.date() <= date(14, 6));
+            let start = minutes_of_day(screening.start());
+            assert!(start >= 10 * 60 && start.is_multiple_of(15));
+            assert!(start + data.runtime(screening) <= MINUTES_PER_DAY);
+
+            for other in &everything {
+                if other.id() != screening.id() {
+                    assert!(
+                        !screenings_overlap(
+                            screening,
+                            data.runtime(screening),
+                            other,
+                            data.runtime(other),
+                            cleaning
+                        ),
+                        "{} overlaps {}",
+                        screening.id(),
+                        other.id()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn prime_time_goes_mostly_to_the_most_reserved() {
+        let mut data = Data::new("prime-time");
+        let past = Screening::new(1, 2, 1, date(2, 6), "12:00".into()).unwrap();
+        for id in 1..=3 {
+            let reservation =
+                Reservation::for_screening(id, &past, None, vec![format!("A{}", id)]).unwrap();
+            data.reservations.add_elem(reservation).unwrap();
+        }
+        data.screenings.add_elem(past).unwrap();
+
+        let plan = data.planner().plan_week(date(8, 6)).unwrap();
+
+        let in_prime_time = |movie_id: u32| {
+            plan.iter()
+                .filter(|s| s.movie_id() == movie_id)
+                .filter(|s| (18 * 60..22 * 60).contains(&minutes_of_day(s.start())))
+                .count()
+        };
+        assert!(
+            in_prime_time(2) > in_prime_time(1),
+            "Arrival {}, Dune {}",
+            in_prime_time(2),
+            in_prime_time(1)
+        );
+    }
+
+    #[test]
+    fn a_plan_needs_movies_in_program() {
+        let mut data = Data::new("no-movies");
+        for index in 0..2 {
+            let mut movie = data.movies.get_elem(index).unwrap().clone();
+            movie.set_in_program(false).unwrap();
+            data.movies.update_elem(index, movie).unwrap();
+        }
+
+        assert_eq!(
+            data.planner().plan_week(date(8, 6)).err().unwrap(),
+            "There are no movies in program."
+        );
+    }
+
+    #[test]
+    fn a_plan_is_added_whole_or_not_at_all() {
+        let mut data = Data::new("add-plan");
+        let plan = data.planner().plan_week(date(8, 6)).unwrap();
+        let planned = plan.len();
+
+        // something got scheduled in the meantime, over the last screening
+        let last = plan.last().unwrap();
+        let meanwhile = Screening::new(1000, 2, 1, last.date(), last.start().to_string()).unwrap();
+        data.screenings.add_elem(meanwhile).unwrap();
+        let mut service = ScreeningService::new(&mut data.screenings, &data.movies, &data.halls);
+        let err = service.add_plan(plan.clone()).unwrap_err();
+        assert!(
+            err.starts_with(&format!("Screening {}: ", last.id())),
+            "{}",
+            err
+        );
+        assert_eq!(service.get_all().len(), 1);
+
+        service.remove(0).unwrap();
+        assert_eq!(service.add_plan(plan).unwrap(), planned);
+    }
+}
@@ -0,0 +1,60 @@
+use crate::domain::promotion::{normalize_code, Promotion};
+use crate::repo::traits::Repository;
+
+/// CRUD for promo codes. Codes are unique.
+pub struct PromotionService<'a> {
+    promotions: &'a mut dyn Repository<Promotion>,
+}
+
+impl<'a> PromotionService<'a> {
+    pub fn new(repo: &'a mut dyn Repository<Promotion>) -> Self {
+        PromotionService { promotions: repo }
+    }
+
+    pub fn get_all(&self) -> &Vec<Promotion> {
+        self.promotions.get_all()
+    }
+
+    pub fn get(&self, index: usize) -> Result<&Promotion, String> {
+        self.promotions.get_elem(index)
+    }
+
+    pub fn find(&self, code: &str) -> Option<&Promotion> {
+        let code = normalize_code(code);
+        self.promotions.get_all().iter().find(|p| p.code() == code)
+    }
+
+    pub fn add(&mut self, new_promotion: Promotion) -> Result<(), String> {
+        for promotion in self.promotions.get_all() {
+            if promotion.code() == new_promotion.code() {
+                return Err("A promotion with this code already exists.".into());
+            } else if promotion.id() == new_promotion.id() {
+                return Err("A promotion with this ID already exists.".into());
+            }
+        }
+
+        self.promotions.add_elem(new_promotion)
+    }
+
+    pub fn update(&mut self, index: usize, new_promotion: Promotion) -> Result<(), String> {
+        if index >= self.promotions.get_all().len() {
+            return Err("There is no promotion with that ID".into());
+        }
+
+        for (i, promotion) in self.promotions.get_all().iter().enumerate() {
+            if i != index {
+                if new_promotion.code() == promotion.code() {
+                    return Err("A different promotion with that code already exists".into());
+                } else if new_promotion.id() == promotion.id() {
+                    return Err("A different promotion with that ID already exists".into());
+                }
+            }
+        }
+
+        self.promotions.update_elem(index, new_promotion)
+    }
+
+    pub fn remove(&mut self, index: usize) -> Result<(), String> {
+        self.promotions.remove_elem(index)
+    }
+}
@@ -0,0 +1,1884 @@
+use crate::config::{HoldConfig, LoyaltyConfig, PricingConfig, TicketConfig};
+use crate::domain::card::Card;
+use crate::domain::clock::Clock;
+use crate::domain::date::Date;
+use crate::domain::gift_card::{GiftCard, GiftCardEntry};
+use crate::domain::hall::{Hall, Seat, SeatKind};
+use crate::domain::money::{Currency, Money, Rounding};
+use crate::domain::movie::{AgeRating, Movie};
+use crate::domain::pricing::{explain_price, PriceExplanation, Weekday};
+use crate::domain::promotion::{PromoBenefit, Promotion};
+use crate::domain::reservation::{hour_key, PriceSnapshot, Reservation, NO_SCREENING};
+use crate::domain::screening::Screening;
+use crate::domain::seat_hold::SeatHold;
+use crate::domain::ticket::TicketCategory;
+use crate::repo::traits::{CardRepository, Repository, ReservationRepository};
+use crate::service::gift_card_service::GiftCardService;
+use crate::validators::context;
+use std::cmp::{Ordering, Reverse};
+use std::sync::Arc;
+
+/// Reservations, the points they earn and the reports about them. A screening
+/// can't get more places booked or held than its hall's capacity, nor the
+/// same seat twice.
+pub struct ReservationService<'a> {
+    reservations: &'a mut dyn ReservationRepository,
+    holds: &'a mut dyn Repository<SeatHold>,
+    screenings: &'a dyn Repository<Screening>,
+    halls: &'a dyn Repository<Hall>,
+    movies: &'a dyn Repository<Movie>,
+    cards: &'a mut dyn CardRepository,
+    promotions: &'a dyn Repository<Promotion>,
+    loyalty: LoyaltyConfig,
+    holds_config: HoldConfig,
+    pricing: PricingConfig,
+    tickets: TicketConfig,
+    clock: Arc<dyn Clock>,
+    gift_cards: Option<GiftCardRepos<'a>>,
+}
+
+// The gift cards and their ledger, for paying with gift cards and giving
+// back what they paid.
+type GiftCardRepos<'a> = (
+    &'a mut dyn Repository<GiftCard>,
+    &'a mut dyn Repository<GiftCardEntry>,
+);
+
+/// What a cancelled reservation gives back.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Refund {
+    /// paid out to the client
+    pub money: Money,
+    /// put back onto the gift cards that paid for the reservation
+    pub gift_cards: Money,
+}
+
+impl<'a> ReservationService<'a> {
+    pub fn new(
+        reservations: &'a mut dyn ReservationRepository,
+        holds: &'a mut dyn Repository<SeatHold>,
+        screenings: &'a dyn Repository<Screening>,
+        halls: &'a dyn Repository<Hall>,
+        movies: &'a dyn Repository<Movie>,
+        cards: &'a mut dyn CardRepository,
+        promotions: &'a dyn Repository<Promotion>,
+    ) -> Self {
+        ReservationService {
+            reservations,
+            holds,
+            screenings,
+            halls,
+            movies,
+            cards,
+            promotions,
+            loyalty: LoyaltyConfig::default(),
+            holds_config: HoldConfig::default(),
+            pricing: PricingConfig::default(),
+            tickets: TicketConfig::default(),
+            clock: context::clock(),
+            gift_cards: None,
+        }
+    }
+
+    pub fn set_loyalty(&mut self, loyalty: LoyaltyConfig) {
+        self.loyalty = loyalty;
+    }
+
+    pub fn set_holds(&mut self, holds_config: HoldConfig) {
+        self.holds_config = holds_config;
+    }
+
+    pub fn set_pricing(&mut self, pricing: PricingConfig) {
+        self.pricing = pricing;
+    }
+
+    pub fn set_tickets(&mut self, tickets: TicketConfig) {
+        self.tickets = tickets;
+    }
+
+    /// Where holds and promo codes get the time from. The validation
+    /// context's clock by default.
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    /// Where reservations paid with gift cards are paid from and refunded
+    /// to. Without them, those reservations can't be cancelled.
+    pub fn set_gift_cards(
+        &mut self,
+        gift_cards: &'a mut dyn Repository<GiftCard>,
+        ledger: &'a mut dyn Repository<GiftCardEntry>,
+    ) {
+        self.gift_cards = Some((gift_cards, ledger));
+    }
+
+    pub fn get_all(&self) -> &Vec<Reservation> {
+        self.reservations.get_all()
+    }
+
+    pub fn get(&self, index: usize) -> Result<&Reservation, String> {
+        self.reservations.get_elem(index)
+    }
+
+    /// Returns the total points on the client's card, if the reservation has one.
+    pub fn add(&mut self, new_reservation: Reservation) -> Result<Option<u32>, String> {
+        self.release_expired()?;
+        self.book(new_reservation, None)
+    }
+
+    /// Holds seats for a screening for the configured timeout. The hold gets
+    /// the next free id and is returned, with its expiry time.
+    pub fn hold(&mut self, screening_id: u32, seats: Vec<String>) -> Result<SeatHold, String> {
+        self.release_expired()?;
+
+        let screening = self.screening(screening_id)?;
+        self.movie_in_program(screening.movie_id())?;
+        self.check_places(screening, &seats, seats.len() as u32, None)?;
+
+        let id = match self.holds.get_all().iter().map(|h| h.id()).max() {
+            Some(id) => id + 1,
+            None => 1,
+        };
+        let expires_at = self.clock.now() + self.holds_config.timeout_secs as i64;
+
+        let hold = SeatHold::new(id, screening_id, seats, expires_at)?;
+        self.holds.add_elem(hold.clone())?;
+
+        Ok(hold)
+    }
+
+    /// Turns a hold into the given reservation, made for the hold's screening
+    /// and seats with whatever card, ticket categories, promo codes, points
+    /// and supervision the client asked for. It is checked and priced like
+    /// one passed to `add`, whose return value it shares.
+    pub fn confirm(
+        &mut self,
+        hold_id: u32,
+        reservation: Reservation,
+    ) -> Result<Option<u32>, String> {
+        self.release_expired()?;
+
+        let hold = match self.holds.get_all().iter().find(|h| h.id() == hold_id) {
+            Some(hold) => hold,
+            None => return Err("There is no hold with that ID, or it has expired.".into()),
+        };
+
+        let mut held = hold.seats().clone();
+        let mut seats = reservation.seats().clone();
+        held.sort();
+        seats.sort();
+        if reservation.screening_id() != hold.screening_id() || seats != held {
+            return Err("The reservation is not for the held seats.".into());
+        }
+
+        self.book(reservation, Some(hold_id))
+    }
+
+    pub fn release(&mut self, hold_id: u32) -> Result<(), String> {
+        match self.holds.get_all().iter().position(|h| h.id() == hold_id) {
+            Some(index) => self.holds.remove_elem(index),
+            None => Err("There is no hold with that ID, or it has expired.".into()),
+        }
+    }
+
+    /// Removes the holds whose time is up and returns how many there were.
+    /// Expired holds stop counting as soon as they expire; this only cleans
+    /// them out of storage, and runs before every booking.
+    pub fn release_expired(&mut self) -> Result<usize, String> {
+        let now = self.clock.now();
+        let mut released = 0;
+
+        let mut index = self.holds.get_all().len();
+        while index > 0 {
+            index -= 1;
+            if self.holds.get_elem(index)?.is_expired(now) {
+                self.holds.remove_elem(index)?;
+                released += 1;
+            }
+        }
+
+        Ok(released)
+    }
+
+    // Adds the reservation, in place of the given hold if there is one.
+    fn book(
+        &mut self,
+        mut new_reservation: Reservation,
+        hold_id: Option<u32>,
+    ) -> Result<Option<u32>, String> {
+        if self
+            .reservations
+            .get_all()
+            .iter()
+            .any(|r| r.id() == new_reservation.id())
+        {
+            return Err("A reservation with this ID already exists.".into());
+        }
+
+        let screening = self.screening(new_reservation.screening_id())?;
+
+        if screening.movie_id() != new_reservation.movie_id()
+            || screening.date() != new_reservation.date()
+            || hour_key(screening.start()) != hour_key(new_reservation.hour())
+        {
+            return Err("The reservation does not match its screening.".into());
+        }
+
+        self.check_places(
+            screening,
+            new_reservation.seats(),
+            new_reservation.places(),
+            hold_id,
+        )?;
+
+        let movie = self.movie_in_program(new_reservation.movie_id())?;
+
+        let card_index = match new_reservation.card_id() {
+            None => None,
+            Some(card_id) => match self.cards.get_all().iter().position(|c| c.id() == card_id) {
+                Some(index) => Some(index),
+                None => return Err("There is no card with that ID.".into()),
+            },
+        };
+
+        let card = match card_index {
+            None => None,
+            Some(index) => Some(self.cards.get_elem(index)?),
+        };
+
+        // only reservations on a card say who is coming
+        if let Some(card) = card {
+            let age = card.birthday().years_until(screening.date());
+            check_age(movie.age_rating(), age, new_reservation.supervised())?;
+        }
+
+        // whatever price the reservation came with, it costs what the
+        // screening, its tickets and its promo codes make it now
+        let price = self.price_reservation(&new_reservation, screening, movie, card)?;
+        let points = price.points();
+        let redeemed_points = new_reservation.redeemed_points();
+        new_reservation.set_price(Some(price))?;
+
+        // the card goes first and is put back if the reservation can't be
+        // written, so there is never a reservation without its points
+        let card_update = match card_index {
+            None => None,
+            Some(index) => {
+                // pricing the reservation checked that the card has these points
+                let old_card = self.cards.get_elem(index)?.clone();
+                let mut card = old_card.clone();
+                card.set_points(card.points() - redeemed_points + points)?;
+                let total = card.points();
+                self.cards.update_elem(index, card)?;
+
+                Some((index, old_card, total))
+            }
+        };
+
+        if let Err(err) = self.reservations.add_elem(new_reservation) {
+            if let Some((index, old_card, _)) = card_update {
+                self.cards.update_elem(index, old_card)?;
+            }
+            return Err(err);
+        }
+
+        if let Some(hold_id) = hold_id {
+            self.release(hold_id)?;
+        }
+
+        Ok(card_update.map(|(_, _, total)| total))
+    }
+
+    /// The screening's ticket price, with the price rules that made it.
+    pub fn explain_price(&self, screening_id: u32) -> Result<PriceExplanation, String> {
+        let screening = self.screening(screening_id)?;
+        self.price_rules(self.movie(screening.movie_id())?, screening)
+    }
+
+    fn price_rules(
+        &self,
+        movie: &Movie,
+        screening: &Screening,
+    ) -> Result<PriceExplanation, String> {
+        explain_price(
+            movie,
+            screening,
+            &self.pricing.rules,
+            &self.pricing.holidays,
+            self.pricing.rounding,
+        )
+    }
+
+    /// What the reservation would cost if it was booked now: the screening's
+    /// price for every place, less what each ticket's category and then each
+    /// promo code takes off, and less what its points pay for.
+    pub fn quote(&self, reservation: &Reservation) -> Result<Money, String> {
+        let screening = self.screening(reservation.screening_id())?;
+        let card = match reservation.card_id() {
+            None => None,
+            Some(card_id) => match self.cards.get_all().iter().find(|c| c.id() == card_id) {
+                Some(card) => Some(card),
+                None => return Err("There is no card with that ID.".into()),
+            },
+        };
+
+        let movie = self.movie(screening.movie_id())?;
+        self.price_reservation(reservation, screening, movie, card)?
+            .paid(reservation.places())
+    }
+
+    // The price of the reservation as it would be booked now, with the points
+    // it earns and the part its points pay for if it is on a card.
+    fn price_reservation(
+        &self,
+        reservation: &Reservation,
+        screening: &Screening,
+        movie: &Movie,
+        card: Option<&Card>,
+    ) -> Result<PriceSnapshot, String> {
+        let age = card.map(|card| card.birthday().years_until(screening.date()));
+        let unit_price = self.price_rules(movie, screening)?.price;
+        let tickets = self.ticket_prices(unit_price, reservation, age)?;
+        let subtotal = Money::sum(
+            tickets.iter().map(|(_, price)| *price),
+            unit_price.currency(),
+        )?;
+        let promotions = self.promo_discounts(reservation, screening, &tickets, subtotal)?;
+
+        let discount = unit_price
+            .checked_mul(reservation.places() as i64)?
+            .checked_sub(subtotal)?
+            .checked_add(Money::sum(
+                promotions.iter().copied(),
+                unit_price.currency(),
+            )?)?;
+        let total = unit_price
+            .checked_mul(reservation.places() as i64)?
+            .checked_sub(discount)?;
+        let redeemed = self.redeemed_value(reservation, card, total)?;
+        let price = PriceSnapshot::new(unit_price, discount, 0)
+            .with_promotions(promotions)
+            .with_redeemed(redeemed);
+
+        // the card earns points on what is paid in money, not with points
+        let points = match reservation.card_id() {
+            None => 0,
+            Some(_) => self.points_for(price.paid(reservation.places())?)?,
+        };
+
+        Ok(price.with_points(points))
+    }
+
+    // What the points the reservation is paid with pay for out of its
+    // `total`. The card must have them, and there can't be more than it
+    // takes to pay for everything; the last one may pay for less than a
+    // point is worth.
+    fn redeemed_value(
+        &self,
+        reservation: &Reservation,
+        card: Option<&Card>,
+        total: Money,
+    ) -> Result<Money, String> {
+        let points = reservation.redeemed_points();
+        if points == 0 {
+            return Ok(Money::zero(total.currency()));
+        }
+
+        let available = match card {
+            Some(card) => card.points(),
+            None => return Err("Only a reservation on a card can be paid with points.".into()),
+        };
+        if points > available {
+            return Err(format!("The card has only {} points.", available));
+        } else if total.is_zero() {
+            return Err(
+                "The reservation costs nothing, there is nothing to pay with points.".into(),
+            );
+        }
+
+        let point_value = self.loyalty.point_value;
+        let value = point_value.checked_mul(points as i64)?;
+        let ordering = value.compare(&total)?;
+
+        let needed = (total.minor() + point_value.minor() - 1) / point_value.minor();
+        if points as i64 > needed {
+            return Err(format!(
+                "Only {} points are needed to pay for the reservation.",
+                needed
+            ));
+        }
+
+        match ordering {
+            Ordering::Greater => Ok(total),
+            _ => Ok(value),
+        }
+    }
+
+    // What each of the reservation's promo codes takes off, in turn, from
+    // the `subtotal` the `tickets` cost after their category discounts.
+    fn promo_discounts(
+        &self,
+        reservation: &Reservation,
+        screening: &Screening,
+        tickets: &[(TicketCategory, Money)],
+        subtotal: Money,
+    ) -> Result<Vec<Money>, String> {
+        let codes = reservation.promo_codes();
+        let mut remaining = subtotal;
+        let mut discounts = vec![];
+
+        for code in codes {
+            let promotion = match self.promotions.get_all().iter().find(|p| p.code() == code) {
+                Some(promotion) => promotion,
+                None => return Err(format!("There is no promo code {}.", code)),
+            };
+            self.check_promotion(promotion, reservation, screening, subtotal)?;
+
+            let eligible: Vec<Money> = tickets
+                .iter()
+                .filter(|(category, _)| promotion.applies_to(*category))
+                .map(|(_, price)| *price)
+                .collect();
+            if eligible.is_empty() {
+                return Err(format!(
+                    "None of the tickets can use the promo code {}.",
+                    code
+                ));
+            }
+
+            let discount = match promotion.benefit() {
+                PromoBenefit::Percent(percent) => {
+                    Money::sum(eligible, subtotal.currency())?.percent(percent, Rounding::Down)?
+                }
+                PromoBenefit::Amount(amount) => amount,
+                PromoBenefit::FreeTicket(every) => {
+                    if eligible.len() < every as usize {
+                        return Err(format!(
+                            "The promo code {} needs at least {} tickets.",
+                            code, every
+                        ));
+                    }
+
+                    let mut prices = eligible;
+                    prices.sort_by_key(|price| price.minor());
+                    let free = prices.len() / every as usize;
+                    Money::sum(prices.into_iter().take(free), subtotal.currency())?
+                }
+            };
+
+            // never more than what is left to pay
+            let discount = match discount.compare(&remaining)? {
+                Ordering::Greater => remaining,
+                _ => discount,
+            };
+            remaining = remaining.checked_sub(discount)?;
+            discounts.push(discount);
+        }
+
+        Ok(discounts)
+    }
+
+    // Whether the reservation may use the promotion: when it is booked, the
+    // day of its screening, the usage limits, the other codes and the spend.
+    fn check_promotion(
+        &self,
+        promotion: &Promotion,
+        reservation: &Reservation,
+        screening: &Screening,
+        subtotal: Money,
+    ) -> Result<(), String> {
+        let code = promotion.code();
+
+        if !promotion.is_valid_on(self.clock.today()) {
+            return Err(format!("The promo code {} is not valid today.", code));
+        }
+
+        let day = Weekday::of(screening.date());
+        if !promotion.days().is_empty() && !day.is_some_and(|d| promotion.days().contains(&d)) {
+            return Err(format!(
+                "The promo code {} is not valid for screenings on that day.",
+                code
+            ));
+        }
+
+        if reservation.promo_codes().len() > 1 && !promotion.stackable() {
+            return Err(format!(
+                "The promo code {} can't be combined with other codes.",
+                code
+            ));
+        }
+
+        let uses: Vec<&Reservation> = self
+            .reservations
+            .get_all()
+            .iter()
+            .filter(|r| r.id() != reservation.id())
+            .filter(|r| r.promo_codes().iter().any(|c| c == code))
+            .collect();
+        if promotion
+            .max_uses()
+            .is_some_and(|max| uses.len() >= max as usize)
+        {
+            return Err(format!("The promo code {} has been used up.", code));
+        }
+
+        if let Some(max) = promotion.max_uses_per_card() {
+            let card_id = match reservation.card_id() {
+                Some(card_id) => card_id,
+                None => return Err(format!("The promo code {} needs a card.", code)),
+            };
+            let card_uses = uses.iter().filter(|r| r.card_id() == Some(card_id)).count();
+            if card_uses >= max as usize {
+                return Err(format!(
+                    "The promo code {} has been used up on this card.",
+                    code
+                ));
+            }
+        }
+
+        if let Some(min_spend) = promotion.min_spend() {
+            if subtotal.compare(&min_spend)? == Ordering::Less {
+                return Err(format!(
+                    "The promo code {} needs a spend of at least {}.",
+                    code, min_spend
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    // What every ticket costs after its category's discount. `age` is the
+    // card holder's on the day of the screening. One of the tickets is the
+    // holder's, so at least one must be for their age; the child and senior
+    // tickets of the people who come with them, or without a card, are
+    // checked by the attendant at the door.
+    fn ticket_prices(
+        &self,
+        price: Money,
+        reservation: &Reservation,
+        age: Option<u32>,
+    ) -> Result<Vec<(TicketCategory, Money)>, String> {
+        let tickets = reservation.ticket_categories();
+        if tickets.len() != reservation.places() as usize {
+            return Err(format!(
+                "The reservation has {} places but {} tickets.",
+                reservation.places(),
+                tickets.len()
+            ));
+        }
+
+        if let Some(age) = age {
+            if !tickets.iter().any(|ticket| self.allows(*ticket, age)) {
+                return Err(format!(
+                    "None of the tickets is for the card holder, who will be {} on the day \
+                     of the screening. Child tickets are for {} and under, senior tickets \
+                     for {} and over.",
+                    age, self.tickets.child_max_age, self.tickets.senior_min_age
+                ));
+            }
+        }
+
+        let mut prices = vec![];
+        for ticket in tickets {
+            let discount = self.tickets.discount(ticket).on(price)?;
+            prices.push((ticket, price.checked_sub(discount)?));
+        }
+
+        Ok(prices)
+    }
+
+    // Whether someone of that age can have the ticket. Students aren't
+    // checked.
+    fn allows(&self, ticket: TicketCategory, age: u32) -> bool {
+        match ticket {
+            TicketCategory::Child => age <= self.tickets.child_max_age,
+            TicketCategory::Senior => age >= self.tickets.senior_min_age,
+            TicketCategory::Adult | TicketCategory::Student => true,
+        }
+    }
+
+    // Points are whole lei of the accrual share of what was paid.
+    fn points_for(&self, paid: Money) -> Result<u32, String> {
+        let share = paid.percent(self.loyalty.accrual_percent, Rounding::Down)?;
+        Ok(share.major() as u32)
+    }
+
+    /// What was paid for the reservation in money and with gift cards; the
+    /// part paid with points is not counted. Reservations booked before prices were recorded are
+    /// taken to have cost the movie's current price.
+    pub fn price_of(&self, reservation: &Reservation) -> Result<Money, String> {
+        match reservation.price() {
+            Some(price) => price.paid(reservation.places()),
+            None => self
+                .movie(reservation.movie_id())?
+                .price()
+                .checked_mul(reservation.places() as i64),
+        }
+    }
+
+    // The points the reservation credited, worked out again for old ones.
+    fn points_of(&self, reservation: &Reservation) -> Result<u32, String> {
+        match reservation.price() {
+            Some(price) => Ok(price.points()),
+            None => self.points_for(self.movie(reservation.movie_id())?.price()),
+        }
+    }
+
+    fn movie(&self, movie_id: u32) -> Result<&Movie, String> {
+        match self.movies.get_all().iter().find(|m| m.id() == movie_id) {
+            Some(movie) => Ok(movie),
+            None => Err("There is no movie with that ID.".into()),
+        }
+    }
+
+    /// Pays what is left to pay for the reservation with the gift card: all
+    /// of it if the card has enough on it, otherwise all that is left on the
+    /// card. The part the card paid is recorded in the reservation's price
+    /// and returned.
+    pub fn pay_with_gift_card(&mut self, reservation_id: u32, code: &str) -> Result<Money, String> {
+        let index = self.reservation_index(reservation_id)?;
+        let reservation = self.reservations.get_elem(index)?.clone();
+        let price = match reservation.price() {
+            Some(price) => price.clone(),
+            None => return Err("The reservation has no recorded price to pay.".into()),
+        };
+
+        let due = price.due(reservation.places())?;
+        if due.is_negative() || due.is_zero() {
+            return Err("The reservation is already paid.".into());
+        }
+
+        let mut gift_cards = gift_card_service(&mut self.gift_cards, &self.clock)?;
+        let amount = gift_cards.payable(code, due)?;
+
+        // the reservation goes first and is put back if the card can't be
+        // charged, so there is never a payment it doesn't know about
+        let mut paid = reservation.clone();
+        paid.set_price(Some(
+            price
+                .clone()
+                .with_gift_card(price.gift_card().checked_add(amount)?),
+        ))?;
+        self.reservations.update_elem(index, paid)?;
+
+        if let Err(err) = gift_cards.pay(code, reservation_id, amount) {
+            self.reservations.update_elem(index, reservation)?;
+            return Err(err);
+        }
+
+        Ok(amount)
+    }
+
+    /// Cancels the reservation and gives back the configured share of what
+    /// was paid for it, rounded down. The gift cards that paid for it get
+    /// their share back onto them and the rest is refunded in money. The
+    /// points it earned are taken off the card, and the points it was paid
+    /// with go back onto it. The reservation is removed last; if that fails
+    /// the card is put back, and cancelling again doesn't refund the gift
+    /// cards twice.
+    pub fn cancel(&mut self, reservation_id: u32) -> Result<Refund, String> {
+        let index = self.reservation_index(reservation_id)?;
+
+        let reservation = self.reservations.get_elem(index)?.clone();
+        let refund = self
+            .price_of(&reservation)?
+            .percent(self.pricing.refund_percent, Rounding::Down)?;
+        let paid_with_gift_cards = reservation
+            .price()
+            .is_some_and(|price| !price.gift_card().is_zero());
+        if paid_with_gift_cards && self.gift_cards.is_none() {
+            return Err(
+                "The reservation was paid with gift cards, which are not available.".into(),
+            );
+        }
+
+        // the gift cards and the card go first, and the reservation only
+        // goes once they got their share back, so a cancellation that fails
+        // on the way can be tried again; the gift card ledger makes sure no
+        // card is refunded twice
+        let gift_cards = if paid_with_gift_cards {
+            gift_card_service(&mut self.gift_cards, &self.clock)?
+                .refund(reservation_id, self.pricing.refund_percent)?
+        } else {
+            Money::zero(refund.currency())
+        };
+
+        let points = self.points_of(&reservation)?;
+        let card_update = match reservation.card_id() {
+            None => None,
+            Some(card_id) => match self.cards.get_all().iter().position(|c| c.id() == card_id) {
+                None => None,
+                Some(index) => {
+                    let old_card = self.cards.get_elem(index)?.clone();
+                    let mut card = old_card.clone();
+                    card.set_points(
+                        (card.points() + reservation.redeemed_points()).saturating_sub(points),
+                    )?;
+                    self.cards.update_elem(index, card)?;
+                    Some((index, old_card))
+                }
+            },
+        };
+
+        if let Err(err) = self.reservations.remove_elem(index) {
+            if let Some((index, old_card)) = card_update {
+                self.cards.update_elem(index, old_card)?;
+            }
+            return Err(err);
+        }
+
+        Ok(Refund {
+            money: refund.checked_sub(gift_cards)?,
+            gift_cards,
+        })
+    }
+
+    fn reservation_index(&self, reservation_id: u32) -> Result<usize, String> {
+        match self
+            .reservations
+            .get_all()
+            .iter()
+            .position(|r| r.id() == reservation_id)
+        {
+            Some(index) => Ok(index),
+            None => Err("There is no reservation with that ID.".into()),
+        }
+    }
+
+    // The seats must exist and be free, and the hall must have room for
+    // `places` more people. `hold_id` is the hold being confirmed, whose seats
+    // are the ones being asked for.
+    fn check_places(
+        &self,
+        screening: &Screening,
+        seats: &[String],
+        places: u32,
+        hold_id: Option<u32>,
+    ) -> Result<(), String> {
+        let remaining = self.remaining_seats_except(screening, hold_id)?;
+        if remaining == 0 {
+            return Err("The screening is sold out.".into());
+        } else if places > remaining {
+            return Err(format!(
+                "Only {} seats are left for this screening.",
+                remaining
+            ));
+        }
+
+        let hall = self.hall_of(screening)?;
+        let taken = self.taken_seats_except(screening.id(), hold_id);
+        for seat in seats {
+            if hall.seat(seat).is_none() {
+                return Err(format!("There is no seat {} in {}.", seat, hall.name()));
+            } else if taken.contains(seat) {
+                return Err(format!("The seat {} is already taken.", seat));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn movie_in_program(&self, movie_id: u32) -> Result<&Movie, String> {
+        match self.movies.get_all().iter().find(|m| m.id() == movie_id) {
+            None => Err("There is no movie with that ID.".into()),
+            Some(movie) if !movie.in_program() => Err("The movie is no longer in program.".into()),
+            Some(movie) => Ok(movie),
+        }
+    }
+
+    pub fn remaining_seats(&self, screening_id: u32) -> Result<u32, String> {
+        self.remaining_seats_for(self.screening(screening_id)?)
+    }
+
+    /// Every screening together with the seats still free for it.
+    pub fn remaining_seats_by_screening(&self) -> Result<Vec<(Screening, u32)>, String> {
+        let mut result = vec![];
+        for screening in self.screenings.get_all() {
+            result.push((screening.clone(), self.remaining_seats_for(screening)?));
+        }
+
+        Ok(result)
+    }
+
+    /// The seats already booked or held for a screening.
+    pub fn taken_seats(&self, screening_id: u32) -> Vec<String> {
+        self.taken_seats_except(screening_id, None)
+    }
+
+    fn taken_seats_except(&self, screening_id: u32, hold_id: Option<u32>) -> Vec<String> {
+        let booked = self
+            .reservations
+            .get_all()
+            .iter()
+            .filter(|r| r.screening_id() == screening_id)
+            .flat_map(|r| r.seats().iter().cloned());
+        let held = self
+            .live_holds(screening_id, hold_id)
+            .into_iter()
+            .flat_map(|h| h.seats().iter().cloned());
+
+        booked.chain(held).collect()
+    }
+
+    // Holds that have not expired, leaving out `hold_id`.
+    fn live_holds(&self, screening_id: u32, hold_id: Option<u32>) -> Vec<&SeatHold> {
+        let now = self.clock.now();
+
+        self.holds
+            .get_all()
+            .iter()
+            .filter(|h| {
+                h.screening_id() == screening_id && !h.is_expired(now) && Some(h.id()) != hold_id
+            })
+            .collect()
+    }
+
+    /// The best `count` free seats of one kind that sit next to each other in
+    /// a row: as close as possible to two thirds of the way back, then to the
+    /// middle of the row.
+    pub fn best_seats(
+        &self,
+        screening_id: u32,
+        count: usize,
+        kind: SeatKind,
+    ) -> Result<Vec<String>, String> {
+        let screening = self.screening(screening_id)?;
+        let hall = self.hall_of(screening)?;
+        let taken = self.taken_seats(screening_id);
+
+        if count == 0 {
+            return Err("Ask for at least one seat.".into());
+        } else if (self.remaining_seats_for(screening)? as usize) < count {
+            return Err("There are not that many seats left for this screening.".into());
+        }
+
+        let best_row = (hall.layout().len() - 1) as f64 * 2.0 / 3.0;
+        let mut best: Option<(f64, Vec<Seat>)> = None;
+
+        for (row, line) in hall.layout().iter().enumerate() {
+            let free: Vec<Seat> = hall
+                .seats()
+                .into_iter()
+                .filter(|seat| seat.row == row && seat.kind == kind && !taken.contains(&seat.label))
+                .collect();
+            let middle = (line.chars().count() - 1) as f64 / 2.0;
+
+            for block in free.windows(count) {
+                // a gap or a taken seat in between breaks the block
+                if block[count - 1].column - block[0].column != count - 1 {
+                    continue;
+                }
+
+                let center = (block[0].column + block[count - 1].column) as f64 / 2.0;
+                let score = (row as f64 - best_row).abs() * 2.0 + (center - middle).abs();
+                if best
+                    .as_ref()
+                    .is_none_or(|(best_score, _)| score < *best_score)
+                {
+                    best = Some((score, block.to_vec()));
+                }
+            }
+        }
+
+        match best {
+            Some((_, seats)) => Ok(seats.into_iter().map(|seat| seat.label).collect()),
+            None => Err(format!(
+                "There are no {} free seats next to each other.",
+                count
+            )),
+        }
+    }
+
+    fn screening(&self, screening_id: u32) -> Result<&Screening, String> {
+        match self
+            .screenings
+            .get_all()
+            .iter()
+            .find(|s| s.id() == screening_id)
+        {
+            Some(screening) => Ok(screening),
+            None => Err("There is no screening with that ID.".into()),
+        }
+    }
+
+    fn hall_of(&self, screening: &Screening) -> Result<&Hall, String> {
+        match self
+            .halls
+            .get_all()
+            .iter()
+            .find(|h| h.id() == screening.hall_id())
+        {
+            Some(hall) => Ok(hall),
+            None => Err("The screening's hall no longer exists.".into()),
+        }
+    }
+
+    fn remaining_seats_for(&self, screening: &Screening) -> Result<u32, String> {
+        self.remaining_seats_except(screening, None)
+    }
+
+    fn remaining_seats_except(
+        &self,
+        screening: &Screening,
+        hold_id: Option<u32>,
+    ) -> Result<u32, String> {
+        let hall = self.hall_of(screening)?;
+
+        let reserved: u32 = self
+            .reservations
+            .get_all()
+            .iter()
+            .filter(|r| r.screening_id() == screening.id())
+            .map(|r| r.places())
+            .sum();
+        let held: u32 = self
+            .live_holds(screening.id(), hold_id)
+            .iter()
+            .map(|h| h.seats().len() as u32)
+            .sum();
+
+        Ok(hall.capacity().saturating_sub(reserved + held))
+    }
+
+    pub fn in_hour_range(&self, start: &str, end: &str) -> Result<Vec<Reservation>, String> {
+        self.reservations.in_hour_range(start, end)
+    }
+
+    /// Every movie together with its number of reservations, most reserved first.
+    pub fn movies_by_reservation_count(&self) -> Result<Vec<(Movie, usize)>, String> {
+        let counts = self.reservations.count_by_movie()?;
+
+        let mut result: Vec<(Movie, usize)> = vec![];
+        for (movie_id, count) in counts {
+            if let Some(movie) = self.movies.get_all().iter().find(|m| m.id() == movie_id) {
+                result.push((movie.clone(), count));
+            }
+        }
+
+        for movie in self.movies.get_all() {
+            if !result.iter().any(|(m, _)| m.id() == movie.id()) {
+                result.push((movie.clone(), 0));
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// What every movie's reservations brought in, highest first. Movies
+    /// without reservations are there with nothing.
+    pub fn revenue_by_movie(&self) -> Result<Vec<(Movie, Money)>, String> {
+        let mut result: Vec<(Movie, Money)> = vec![];
+        for movie in self.movies.get_all() {
+            let amounts = self
+                .reservations
+                .get_all()
+                .iter()
+                .filter(|r| r.movie_id() == movie.id())
+                .map(|r| self.price_of(r))
+                .collect::<Result<Vec<Money>, String>>()?;
+            // what was paid, even if the movie is priced in another
+            // currency by now
+            let currency = match amounts.first() {
+                Some(amount) => amount.currency(),
+                None => movie.price().currency(),
+            };
+            result.push((movie.clone(), Money::sum(amounts, currency)?));
+        }
+
+        result.sort_by_key(|(_, amount)| Reverse(amount.minor()));
+        Ok(result)
+    }
+
+    /// Everything the reservations brought in, 0 lei if nothing. Fails if they
+    /// were not all paid in the same currency.
+    pub fn total_revenue(&self) -> Result<Money, String> {
+        let revenues = self.revenue_by_movie()?;
+        Money::sum(
+            revenues
+                .into_iter()
+                .map(|(_, amount)| amount)
+                .filter(|amount| !amount.is_zero()),
+            Currency::default(),
+        )
+    }
+
+    /// Every promo code with the number of reservations that used it and what
+    /// it took off them altogether, most used first.
+    pub fn promotion_redemptions(&self) -> Result<Vec<(Promotion, usize, Money)>, String> {
+        let mut result: Vec<(Promotion, usize, Money)> = vec![];
+        for promotion in self.promotions.get_all() {
+            let mut count = 0;
+            let mut amounts: Vec<Money> = vec![];
+            for reservation in self.reservations.get_all() {
+                let position = reservation
+                    .promo_codes()
+                    .iter()
+                    .position(|code| code == promotion.code());
+                if let Some(position) = position {
+                    count += 1;
+                    if let Some(amount) = reservation
+                        .price()
+                        .and_then(|price| price.promotions().get(position))
+                    {
+                        amounts.push(*amount);
+                    }
+                }
+            }
+
+            let currency = match amounts.first() {
+                Some(amount) => amount.currency(),
+                None => Currency::default(),
+            };
+            result.push((promotion.clone(), count, Money::sum(amounts, currency)?));
+        }
+
+        result.sort_by_key(|(_, count, _)| Reverse(*count));
+        Ok(result)
+    }
+
+    /// Cancels the reservations between two dates, inclusive, each the way
+    /// `cancel` does, and returns their ids with what each gives back.
+    pub fn remove_in_date_range(
+        &mut self,
+        start: Date,
+        end: Date,
+    ) -> Result<Vec<(u32, Refund)>, String> {
+        if start > end {
+            return Err("The start of the interval must not be after its end.".into());
+        }
+
+        let ids: Vec<u32> = self
+            .reservations
+            .get_all()
+            .iter()
+            .filter(|r| r.date() >= start && r.date() <= end)
+            .map(|r| r.id())
+            .collect();
+
+        let mut refunds = vec![];
+        for id in ids {
+            refunds.push((id, self.cancel(id)?));
+        }
+
+        Ok(refunds)
+    }
+}
+
+/// Links the reservations read from files written before there were
+/// screenings to the screening of their movie at their date and hour. Like
+/// the SQLite migration that brought in screenings, the showings that have
+/// none get one in hall 1, and hall 1 gets a 10 x 10 layout if it doesn't
+/// exist. Returns how many reservations were linked.
+pub fn link_to_screenings(
+    reservations: &mut dyn Repository<Reservation>,
+    screenings: &mut dyn Repository<Screening>,
+    halls: &mut dyn Repository<Hall>,
+) -> Result<usize, String> {
+    let mut linked = 0;
+    for index in 0..reservations.get_all().len() {
+        let mut reservation = reservations.get_elem(index)?.clone();
+        if reservation.screening_id() != NO_SCREENING {
+            continue;
+        }
+
+        let found = screenings.get_all().iter().position(|s| {
+            s.movie_id() == reservation.movie_id()
+                && s.date() == reservation.date()
+                && hour_key(s.start()) == hour_key(reservation.hour())
+        });
+        let screening_index = match found {
+            Some(screening_index) => screening_index,
+            None => {
+                if !halls.get_all().iter().any(|h| h.id() == LEGACY_HALL) {
+                    let layout = vec!["SSSSSSSSSS".to_string(); 10];
+                    halls.add_elem(Hall::new(LEGACY_HALL, "Hall 1", 100, layout)?)?;
+                }
+                let id = match screenings.get_all().iter().map(|s| s.id()).max() {
+                    Some(id) => id + 1,
+                    None => 1,
+                };
+                screenings.add_elem(Screening::new(
+                    id,
+                    reservation.movie_id(),
+                    LEGACY_HALL,
+                    reservation.date(),
+                    reservation.hour().to_string(),
+                )?)?;
+                screenings.get_all().len() - 1
+            }
+        };
+
+        reservation.set_screening(screenings.get_elem(screening_index)?)?;
+        reservations.update_elem(index, reservation)?;
+        linked += 1;
+    }
+
+    Ok(linked)
+}
+
+// The hall of the screenings made for reservations from before screenings.
+const LEGACY_HALL: u32 = 1;
+
+// The card holder's age on the day of the screening against the movie's
+// rating. A supervising adult can only make up for the age below IM-18.
+fn check_age(rating: AgeRating, age: u32, supervised: bool) -> Result<(), String> {
+    if age >= rating.min_age() {
+        Ok(())
+    } else if !rating.allows_supervised() {
+        Err(format!(
+            "The card holder will be {} and {} movies are for {} and over only.",
+            age,
+            rating,
+            rating.min_age()
+        ))
+    } else if !supervised {
+        Err(format!(
+            "The card holder will be {} and {} movies are for {} and over. \
+             Make the reservation supervised if they come with a parent or guardian.",
+            age,
+            rating,
+            rating.min_age()
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+// A gift card service over the repositories given to `set_gift_cards`, on the
+// reservation service's clock.
+fn gift_card_service<'b>(
+    gift_cards: &'b mut Option<GiftCardRepos<'_>>,
+    clock: &Arc<dyn Clock>,
+) -> Result<GiftCardService<'b>, String> {
+    match gift_cards {
+        Some((gift_cards, ledger)) => {
+            let mut service = GiftCardService::new(&mut **gift_cards, &mut **ledger);
+            service.set_clock(clock.clone());
+            Ok(service)
+        }
+        None => Err("The gift cards are not available.".into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::clock::FixedClock;
+    use crate::domain::gift_card::GiftCardMovement;
+    use crate::domain::money::Currency;
+    use crate::repo::repo_file::RepoFile;
+    use crate::repo::traits::Serializable;
+    use crate::test_util::TempDir;
+
+    // The CSV files of one test, in a directory of its own that goes away
+    // after them.
+    struct Data {
+        reservations: RepoFile<Reservation>,
+        holds: RepoFile<SeatHold>,
+        screenings: RepoFile<Screening>,
+        halls: RepoFile<Hall>,
+        movies: RepoFile<Movie>,
+        cards: RepoFile<Card>,
+        promotions: RepoFile<Promotion>,
+        gift_cards: RepoFile<GiftCard>,
+        ledger: RepoFile<GiftCardEntry>,
+        _dir: TempDir,
+    }
+
+    impl Data {
+        // A screening of a 25 RON movie on 10.6.2026 in a hall of ten seats,
+        // and a card, with the clock at 1.6.2026.
+        fn new(name: &str) -> Data {
+            context::set_clock(Arc::new(FixedClock::new(Date::new(1, 6, 2026).unwrap())));
+            let dir = TempDir::new(&format!("reservations-{}", name));
+            let path = |file: &str| dir.path(file);
+
+            let mut data = Data {
+                reservations: RepoFile::new(&path("reservations.csv")),
+                holds: RepoFile::new(&path("holds.csv")),
+                screenings: RepoFile::new(&path("screenings.csv")),
+                halls: RepoFile::new(&path("halls.csv")),
+                movies: RepoFile::new(&path("movies.csv")),
+                cards: RepoFile::new(&path("cards.csv")),
+                promotions: RepoFile::new(&path("promotions.csv")),
+                gift_cards: RepoFile::new(&path("gift_cards.csv")),
+                ledger: RepoFile::new(&path("gift_card_ledger.csv")),
+                _dir: dir,
+            };
+
+            let date = Date::new(10, 6, 2026).unwrap();
+            let price = Money::new(2500, Currency::Ron);
+            data.movies
+                .add_elem(Movie::new(1, "Dune", 2021, price, true, 155).unwrap())
+                .unwrap();
+            data.halls
+                .add_elem(Hall::new(1, "Mare", 10, vec!["SSSSSSSSSS".into()]).unwrap())
+                .unwrap();
+            data.screenings
+                .add_elem(Screening::new(1, 1, 1, date, "18:00".into()).unwrap())
+                .unwrap();
+            let birthday = Date::new(2, 5, 2012).unwrap();
+            let registered = Date::new(1, 6, 2026).unwrap();
+            data.cards
+                .add_elem(
+                    Card::new(1, "Ana", "Pop", "6120502123456", birthday, registered, 0).unwrap(),
+                )
+                .unwrap();
+
+            data
+        }
+
+        fn service(&mut self) -> ReservationService<'_> {
+            let mut service = ReservationService::new(
+                &mut self.reservations,
+                &mut self.holds,
+                &self.screenings,
+                &self.halls,
+                &self.movies,
+                &mut self.cards,
+                &self.promotions,
+            );
+            service.set_gift_cards(&mut self.gift_cards, &mut self.ledger);
+            service
+        }
+
+        // A gift card worth `value` lei that expires at the end of June.
+        fn gift_card(&mut self, id: u32, code: &str, value: i64) {
+            let gift_card = GiftCard::new(
+                id,
+                code,
+                Money::new(value * 100, Currency::Ron),
+                Date::new(30, 6, 2026).unwrap(),
+            )
+            .unwrap();
+            GiftCardService::new(&mut self.gift_cards, &mut self.ledger)
+                .issue(gift_card)
+                .unwrap();
+        }
+
+        // A code for 10% off, good all year.
+        fn promote(&mut self, id: u32, code: &str) -> Promotion {
+            Promotion::new(
+                id,
+                code,
+                PromoBenefit::Percent(10),
+                Date::new(1, 1, 2026).unwrap(),
+                Date::new(31, 12, 2026).unwrap(),
+            )
+            .unwrap()
+        }
+
+        fn screening(&self) -> Screening {
+            self.screenings.get_elem(0).unwrap().clone()
+        }
+
+        // Gives the screening's hall another capacity and layout.
+        fn rebuild_hall(&mut self, capacity: u32, layout: &[&str]) {
+            let layout = layout.iter().map(|row| row.to_string()).collect();
+            self.halls
+                .update_elem(0, Hall::new(1, "Mare", capacity, layout).unwrap())
+                .unwrap();
+        }
+    }
+
+    fn seats(seats: &[&str]) -> Vec<String> {
+        seats.iter().map(|seat| seat.to_string()).collect()
+    }
+
+    fn reservation(
+        data: &Data,
+        id: u32,
+        card_id: Option<u32>,
+        seat: &str,
+        codes: &[&str],
+    ) -> Reservation {
+        let mut reservation =
+            Reservation::for_screening(id, &data.screening(), card_id, vec![seat.into()]).unwrap();
+        reservation
+            .set_promo_codes(codes.iter().map(|code| code.to_string()).collect())
+            .unwrap();
+        reservation
+    }
+
+    #[test]
+    fn a_reservation_on_a_card_credits_its_points() {
+        let mut data = Data::new("points");
+        let screening = data.screening();
+
+        let total = data
+            .service()
+            .add(Reservation::for_screening(1, &screening, Some(1), vec![]).unwrap())
+            .unwrap();
+        assert_eq!(total, Some(2));
+        assert_eq!(data.cards.get_elem(0).unwrap().points(), 2);
+
+        // a reservation that can't be written leaves the card as it was
+        assert!(data
+            .service()
+            .add(Reservation::for_screening(1, &screening, Some(1), vec![]).unwrap())
+            .is_err());
+        assert_eq!(data.cards.get_elem(0).unwrap().points(), 2);
+    }
+
+    #[test]
+    fn reservations_from_before_screenings_get_one_in_hall_one() {
+        let mut data = Data::new("legacy");
+        data.halls.remove_elem(0).unwrap();
+        for line in [
+            "\"7\",\"1\",\"None\",\"10.6.2026\",\"18:00\"",
+            "\"8\",\"1\",\"None\",\"10.6.2026\",\"20:00\"",
+            "\"9\",\"1\",\"None\",\"10.6.2026\",\"20.00\"",
+        ] {
+            data.reservations
+                .add_elem(Reservation::from_csv_to_obj(line).unwrap())
+                .unwrap();
+        }
+
+        let linked = link_to_screenings(
+            &mut data.reservations,
+            &mut data.screenings,
+            &mut data.halls,
+        )
+        .unwrap();
+
+        assert_eq!(linked, 3);
+        let screenings: Vec<u32> = data
+            .reservations
+            .get_all()
+            .iter()
+            .map(|r| r.screening_id())
+            .collect();
+        assert_eq!(screenings, vec![1, 2, 2]);
+        assert_eq!(data.screenings.get_elem(1).unwrap().hall_id(), 1);
+        assert_eq!(data.halls.get_elem(0).unwrap().capacity(), 100);
+        let again = link_to_screenings(
+            &mut data.reservations,
+            &mut data.screenings,
+            &mut data.halls,
+        )
+        .unwrap();
+        assert_eq!(again, 0);
+    }
+
+    #[test]
+    fn the_capacity_limits_the_places_left() {
+        let mut data = Data::new("capacity");
+        data.rebuild_hall(3, &["SSSSSSSSSS"]);
+        let pair =
+            Reservation::for_screening(1, &data.screening(), None, seats(&["A1", "A2"])).unwrap();
+        let another_pair =
+            Reservation::for_screening(2, &data.screening(), None, seats(&["A3", "A4"])).unwrap();
+        let taken = Reservation::for_screening(3, &data.screening(), None, seats(&["A2"])).unwrap();
+        let single =
+            Reservation::for_screening(4, &data.screening(), None, seats(&["A9"])).unwrap();
+
+        let mut service = data.service();
+        assert_eq!(service.remaining_seats(1).unwrap(), 3);
+        service.add(pair).unwrap();
+        assert_eq!(service.remaining_seats(1).unwrap(), 1);
+        assert_eq!(
+            service.add(another_pair).unwrap_err(),
+            "Only 1 seats are left for this screening."
+        );
+        assert_eq!(
+            service.add(taken).unwrap_err(),
+            "The seat A2 is already taken."
+        );
+        service.add(single).unwrap();
+        assert_eq!(service.remaining_seats(1).unwrap(), 0);
+        assert_eq!(
+            service.hold(1, seats(&["A5"])).err().unwrap(),
+            "The screening is sold out."
+        );
+        assert_eq!(
+            service.remaining_seats(2).unwrap_err(),
+            "There is no screening with that ID."
+        );
+    }
+
+    #[test]
+    fn the_best_seats_sit_together_two_thirds_back_in_the_middle() {
+        let mut data = Data::new("best-seats");
+        data.rebuild_hall(19, &["SSSSS", "SSSSS", "SSSSS", "WW.SS"]);
+        let middle =
+            Reservation::for_screening(1, &data.screening(), None, seats(&["C3"])).unwrap();
+
+        let mut service = data.service();
+        assert_eq!(
+            service.best_seats(1, 3, SeatKind::Standard).unwrap(),
+            seats(&["C2", "C3", "C4"])
+        );
+        service.add(middle).unwrap();
+        assert_eq!(
+            service.best_seats(1, 2, SeatKind::Standard).unwrap(),
+            seats(&["C1", "C2"])
+        );
+        assert_eq!(
+            service.best_seats(1, 4, SeatKind::Standard).unwrap(),
+            seats(&["B1", "B2", "B3", "B4"])
+        );
+        assert_eq!(
+            service.best_seats(1, 2, SeatKind::Wheelchair).unwrap(),
+            seats(&["D1", "D2"])
+        );
+        assert_eq!(
+            service.best_seats(1, 3, SeatKind::Wheelchair).unwrap_err(),
+            "There are no 3 free seats next to each other."
+        );
+        assert_eq!(
+            service.best_seats(1, 0, SeatKind::Standard).unwrap_err(),
+            "Ask for at least one seat."
+        );
+    }
+
+    #[test]
+    fn a_hold_keeps_its_seats_until_it_expires_or_is_released() {
+        let mut data = Data::new("holds");
+        let clock = Arc::new(FixedClock::new(Date::new(1, 6, 2026).unwrap()));
+        let mut service = data.service();
+        service.set_clock(clock.clone());
+
+        let hold = service.hold(1, seats(&["A1", "A2"])).unwrap();
+        assert_eq!(hold.expires_at(), clock.now() + 600);
+        assert_eq!(service.remaining_seats(1).unwrap(), 8);
+        assert_eq!(
+            service.hold(1, seats(&["A2", "A3"])).err().unwrap(),
+            "The seat A2 is already taken."
+        );
+
+        service.release(hold.id()).unwrap();
+        assert_eq!(service.remaining_seats(1).unwrap(), 10);
+        assert_eq!(
+            service.release(hold.id()).unwrap_err(),
+            "There is no hold with that ID, or it has expired."
+        );
+
+        // an expired hold stops counting at once and is cleaned out later
+        let hold = service.hold(1, seats(&["A2", "A3"])).unwrap();
+        clock.advance(600);
+        assert_eq!(service.remaining_seats(1).unwrap(), 10);
+        let booked =
+            Reservation::for_screening(1, &data.screening(), None, seats(&["A2", "A3"])).unwrap();
+        let mut service = data.service();
+        service.set_clock(clock.clone());
+        assert_eq!(
+            service.confirm(hold.id(), booked).unwrap_err(),
+            "There is no hold with that ID, or it has expired."
+        );
+        assert!(data.holds.get_all().is_empty());
+    }
+
+    #[test]
+    fn the_card_holder_must_be_old_enough_or_supervised() {
+        let mut data = Data::new("age");
+        let mut movie = data.movies.get_elem(0).unwrap().clone();
+        movie.set_age_rating(AgeRating::N15).unwrap();
+        data.movies.update_elem(0, movie.clone()).unwrap();
+
+        // the card holder is 14
+        let alone = reservation(&data, 1, Some(1), "A1", &[]);
+        let mut supervised = reservation(&data, 1, Some(1), "A1", &[]);
+        supervised.set_supervised(true).unwrap();
+        let no_card = reservation(&data, 2, None, "A2", &[]);
+        let mut service = data.service();
+        assert_eq!(
+            service.add(alone).unwrap_err(),
+            "The card holder will be 14 and N-15 movies are for 15 and over. \
+             Make the reservation supervised if they come with a parent or guardian."
+        );
+        service.add(supervised.clone()).unwrap();
+        service.add(no_card).unwrap();
+        service.cancel(1).unwrap();
+
+        movie.set_age_rating(AgeRating::Im18).unwrap();
+        data.movies.update_elem(0, movie).unwrap();
+        assert_eq!(
+            data.service().add(supervised).unwrap_err(),
+            "The card holder will be 14 and IM-18 movies are for 18 and over only."
+        );
+    }
+
+    #[test]
+    fn a_promo_code_runs_out_after_its_uses() {
+        let mut data = Data::new("uses");
+        let mut promotion = data.promote(1, "SPRING");
+        promotion.set_max_uses(Some(1)).unwrap();
+        data.promotions.add_elem(promotion).unwrap();
+
+        let first = reservation(&data, 1, None, "A1", &["spring"]);
+        let second = reservation(&data, 2, None, "A2", &["spring"]);
+        let mut service = data.service();
+        service.add(first).unwrap();
+        let err = service.add(second).unwrap_err();
+        assert_eq!(err, "The promo code SPRING has been used up.");
+
+        // cancelling gives the use back
+        service.cancel(1).unwrap();
+        let again = reservation(&data, 3, None, "A3", &["SPRING"]);
+        assert!(data.service().add(again).is_ok());
+    }
+
+    #[test]
+    fn a_promo_code_runs_out_per_card() {
+        let mut data = Data::new("uses-per-card");
+        let mut promotion = data.promote(1, "LOYAL");
+        promotion.set_max_uses_per_card(Some(1)).unwrap();
+        data.promotions.add_elem(promotion).unwrap();
+
+        let first = reservation(&data, 1, Some(1), "A1", &["LOYAL"]);
+        let second = reservation(&data, 2, Some(1), "A2", &["LOYAL"]);
+        let anonymous = reservation(&data, 3, None, "A3", &["LOYAL"]);
+        let mut service = data.service();
+        service.add(first).unwrap();
+        assert_eq!(
+            service.add(second).unwrap_err(),
+            "The promo code LOYAL has been used up on this card."
+        );
+        assert_eq!(
+            service.add(anonymous).unwrap_err(),
+            "The promo code LOYAL needs a card."
+        );
+    }
+
+    #[test]
+    fn only_stackable_codes_combine() {
+        let mut data = Data::new("stacking");
+        let mut first = data.promote(1, "FIRST");
+        let mut second = data.promote(2, "SECOND");
+        first.set_stackable(true).unwrap();
+        data.promotions.add_elem(first.clone()).unwrap();
+        data.promotions.add_elem(second.clone()).unwrap();
+
+        let mixed = reservation(&data, 1, None, "A1", &["FIRST", "SECOND"]);
+        assert_eq!(
+            data.service().add(mixed).unwrap_err(),
+            "The promo code SECOND can't be combined with other codes."
+        );
+
+        second.set_stackable(true).unwrap();
+        data.promotions.update_elem(1, second).unwrap();
+        let stacked = reservation(&data, 1, None, "A1", &["FIRST", "SECOND"]);
+        data.service().add(stacked).unwrap();
+
+        // each takes its share of the tickets, not of what the other left
+        let price = data
+            .reservations
+            .get_elem(0)
+            .unwrap()
+            .price()
+            .unwrap()
+            .clone();
+        assert_eq!(
+            price.promotions(),
+            &vec![
+                Money::new(250, Currency::Ron),
+                Money::new(250, Currency::Ron)
+            ]
+        );
+        assert_eq!(price.total(1).unwrap(), Money::new(2000, Currency::Ron));
+    }
+
+    #[test]
+    fn one_ticket_must_be_the_card_holders_and_the_rest_are_checked_at_the_door() {
+        let mut data = Data::new("concessions");
+        let seats = |seats: &[&str]| seats.iter().map(|seat| seat.to_string()).collect();
+        let with_tickets = |id, card_id, seats, tickets| {
+            let mut reservation =
+                Reservation::for_screening(id, &data.screening(), card_id, seats).unwrap();
+            reservation.set_tickets(tickets).unwrap();
+            reservation
+        };
+
+        // the card holder is 14
+        let seniors = with_tickets(
+            1,
+            Some(1),
+            seats(&["A1", "A2"]),
+            vec![TicketCategory::Senior, TicketCategory::Senior],
+        );
+        let family = with_tickets(
+            2,
+            Some(1),
+            seats(&["A3", "A4", "A5"]),
+            vec![
+                TicketCategory::Senior,
+                TicketCategory::Child,
+                TicketCategory::Child,
+            ],
+        );
+        let no_card = with_tickets(
+            3,
+            None,
+            seats(&["A6", "A7"]),
+            vec![TicketCategory::Child, TicketCategory::Senior],
+        );
+
+        let mut service = data.service();
+        assert_eq!(
+            service.add(seniors).unwrap_err(),
+            "None of the tickets is for the card holder, who will be 14 on the day of the \
+             screening. Child tickets are for 15 and under, senior tickets for 65 and over."
+        );
+        service.add(family).unwrap();
+        service.add(no_card).unwrap();
+
+        // 17.50 for the senior and 12.50 for each child
+        let price = data
+            .reservations
+            .get_elem(0)
+            .unwrap()
+            .price()
+            .unwrap()
+            .clone();
+        assert_eq!(price.total(3).unwrap(), Money::new(4250, Currency::Ron));
+    }
+
+    #[test]
+    fn a_hold_is_confirmed_with_tickets_codes_and_points() {
+        let mut data = Data::new("confirm");
+        let promotion = data.promote(1, "SPRING");
+        data.promotions.add_elem(promotion).unwrap();
+        let mut card = data.cards.get_elem(0).unwrap().clone();
+        card.set_points(100).unwrap();
+        data.cards.update_elem(0, card).unwrap();
+
+        let hold = data
+            .service()
+            .hold(1, vec!["A1".into(), "A2".into()])
+            .unwrap();
+
+        let elsewhere = reservation(&data, 1, Some(1), "A3", &[]);
+        assert_eq!(
+            data.service().confirm(hold.id(), elsewhere).unwrap_err(),
+            "The reservation is not for the held seats."
+        );
+
+        let mut reservation = Reservation::for_screening(
+            1,
+            &data.screening(),
+            Some(1),
+            vec!["A2".into(), "A1".into()],
+        )
+        .unwrap();
+        reservation
+            .set_tickets(vec![TicketCategory::Adult, TicketCategory::Adult])
+            .unwrap();
+        reservation.set_promo_codes(vec!["SPRING".into()]).unwrap();
+        reservation.set_redeemed_points(10).unwrap();
+        data.service().confirm(hold.id(), reservation).unwrap();
+
+        let booked = data.reservations.get_elem(0).unwrap();
+        assert_eq!(booked.promo_codes(), &vec!["SPRING".to_string()]);
+        assert_eq!(booked.redeemed_points(), 10);
+        assert_eq!(
+            booked.price().unwrap().promotions(),
+            &vec![Money::new(500, Currency::Ron)]
+        );
+        assert!(data.holds.get_all().is_empty());
+    }
+
+    fn lei(lei: i64) -> Money {
+        Money::new(lei * 100, Currency::Ron)
+    }
+
+    #[test]
+    fn gift_cards_pay_what_is_left_and_the_reservation_records_it() {
+        let mut data = Data::new("gift-card-payment");
+        data.gift_card(1, "GIFT-SMALL", 10);
+        data.gift_card(2, "GIFT-LARGE", 100);
+        let booked =
+            Reservation::for_screening(1, &data.screening(), None, vec!["A1".into(), "A2".into()])
+                .unwrap();
+        data.service().add(booked).unwrap();
+
+        let mut service = data.service();
+        assert_eq!(
+            service.pay_with_gift_card(2, "GIFT-SMALL").unwrap_err(),
+            "There is no reservation with that ID."
+        );
+        assert_eq!(
+            service.pay_with_gift_card(1, "gift-small").unwrap(),
+            lei(10)
+        );
+        assert_eq!(
+            service.pay_with_gift_card(1, "GIFT-LARGE").unwrap(),
+            lei(40)
+        );
+        assert_eq!(
+            service.pay_with_gift_card(1, "GIFT-LARGE").unwrap_err(),
+            "The reservation is already paid."
+        );
+
+        let price = data
+            .reservations
+            .get_elem(0)
+            .unwrap()
+            .price()
+            .unwrap()
+            .clone();
+        assert_eq!(price.gift_card(), lei(50));
+        assert!(price.due(2).unwrap().is_zero());
+        assert_eq!(data.gift_cards.get_elem(0).unwrap().balance(), lei(0));
+        assert_eq!(data.gift_cards.get_elem(1).unwrap().balance(), lei(60));
+
+        let payments: Vec<(u32, Money)> = data
+            .ledger
+            .get_all()
+            .iter()
+            .filter(|e| e.movement() == GiftCardMovement::Payment)
+            .map(|e| (e.gift_card_id(), e.amount()))
+            .collect();
+        assert_eq!(payments, vec![(1, lei(10)), (2, lei(40))]);
+    }
+
+    #[test]
+    fn cancelling_gives_the_gift_cards_their_share_and_the_rest_in_money() {
+        let mut data = Data::new("gift-card-refund");
+        data.gift_card(1, "GIFT-CARD", 10);
+        let booked =
+            Reservation::for_screening(1, &data.screening(), None, vec!["A1".into(), "A2".into()])
+                .unwrap();
+        data.service().add(booked).unwrap();
+
+        let mut service = data.service();
+        service.set_pricing(PricingConfig {
+            refund_percent: 50,
+            ..PricingConfig::default()
+        });
+        service.pay_with_gift_card(1, "GIFT-CARD").unwrap();
+        let refund = service.cancel(1).unwrap();
+
+        // half of the 50 lei paid: 5 back onto the card, 20 in money
+        assert_eq!(refund.gift_cards, lei(5));
+        assert_eq!(refund.money, lei(20));
+        assert_eq!(data.gift_cards.get_elem(0).unwrap().balance(), lei(5));
+        let last = data.ledger.get_all().last().unwrap();
+        assert_eq!(last.movement(), GiftCardMovement::Refund);
+        assert_eq!(last.reservation_id(), Some(1));
+    }
+
+    #[test]
+    fn a_cancellation_cut_short_refunds_only_the_cards_left() {
+        let mut data = Data::new("gift-card-retry");
+        data.gift_card(1, "GIFT-SMALL", 10);
+        data.gift_card(2, "GIFT-LARGE", 100);
+        let booked =
+            Reservation::for_screening(1, &data.screening(), None, vec!["A1".into(), "A2".into()])
+                .unwrap();
+        data.service().add(booked).unwrap();
+        data.service().pay_with_gift_card(1, "GIFT-SMALL").unwrap();
+        data.service().pay_with_gift_card(1, "GIFT-LARGE").unwrap();
+
+        // an earlier attempt got as far as the first card
+        let date = Date::new(1, 6, 2026).unwrap();
+        data.ledger
+            .add_elem(
+                GiftCardEntry::new(5, 1, date, GiftCardMovement::Refund, lei(10), Some(1)).unwrap(),
+            )
+            .unwrap();
+
+        let mut service = data.service();
+        service.set_pricing(PricingConfig {
+            refund_percent: 100,
+            ..PricingConfig::default()
+        });
+        let refund = service.cancel(1).unwrap();
+
+        assert_eq!(refund.gift_cards, lei(50));
+        assert_eq!(refund.money, lei(0));
+        assert_eq!(data.gift_cards.get_elem(0).unwrap().balance(), lei(0));
+        assert_eq!(data.gift_cards.get_elem(1).unwrap().balance(), lei(100));
+        assert!(data.reservations.get_all().is_empty());
+    }
+
+    #[test]
+    fn an_expired_gift_card_is_refunded_in_money() {
+        let mut data = Data::new("gift-card-expired");
+        data.gift_card(1, "GIFT-CARD", 10);
+        let booked =
+            Reservation::for_screening(1, &data.screening(), None, vec!["A1".into()]).unwrap();
+        data.service().add(booked).unwrap();
+        data.service().pay_with_gift_card(1, "GIFT-CARD").unwrap();
+
+        let clock = Arc::new(FixedClock::new(Date::new(1, 7, 2026).unwrap()));
+        let mut service = data.service();
+        service.set_clock(clock);
+        let refund = service.cancel(1).unwrap();
+
+        assert_eq!(refund.gift_cards, lei(0));
+        assert_eq!(refund.money, lei(25));
+        assert_eq!(data.gift_cards.get_elem(0).unwrap().balance(), lei(0));
+    }
+
+    #[test]
+    fn points_are_earned_on_what_is_paid_in_money() {
+        let mut data = Data::new("accrual");
+        let mut card = data.cards.get_elem(0).unwrap().clone();
+        card.set_points(20).unwrap();
+        data.cards.update_elem(0, card).unwrap();
+
+        let mut booked = Reservation::for_screening(
+            1,
+            &data.screening(),
+            Some(1),
+            vec!["A1".into(), "A2".into()],
+        )
+        .unwrap();
+        booked.set_redeemed_points(10).unwrap();
+
+        // 50 lei, 10 of them paid with points: 10% of the other 40
+        assert_eq!(data.service().add(booked).unwrap(), Some(14));
+        assert_eq!(
+            data.reservations
+                .get_elem(0)
+                .unwrap()
+                .price()
+                .unwrap()
+                .points(),
+            4
+        );
+    }
+
+    #[test]
+    fn removing_by_date_cancels_every_reservation() {
+        let mut data = Data::new("remove-by-date");
+        data.gift_card(1, "GIFT-CARD", 10);
+        let first =
+            Reservation::for_screening(1, &data.screening(), Some(1), vec!["A1".into()]).unwrap();
+        let second =
+            Reservation::for_screening(2, &data.screening(), None, vec!["A2".into()]).unwrap();
+        let mut service = data.service();
+        service.add(first).unwrap();
+        service.add(second).unwrap();
+        service.pay_with_gift_card(2, "GIFT-CARD").unwrap();
+
+        let day = Date::new(10, 6, 2026).unwrap();
+        let refunds = service.remove_in_date_range(day, day).unwrap();
+
+        assert_eq!(refunds.len(), 2);
+        assert_eq!(
+            refunds[0],
+            (
+                1,
+                Refund {
+                    money: lei(25),
+                    gift_cards: lei(0)
+                }
+            )
+        );
+        assert_eq!(
+            refunds[1],
+            (
+                2,
+                Refund {
+                    money: lei(15),
+                    gift_cards: lei(10)
+                }
+            )
+        );
+        assert!(data.reservations.get_all().is_empty());
+        assert_eq!(data.cards.get_elem(0).unwrap().points(), 0);
+        assert_eq!(data.gift_cards.get_elem(0).unwrap().balance(), lei(10));
+    }
+}
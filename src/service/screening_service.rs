@@ -0,0 +1,380 @@
+use crate::config::ScheduleConfig;
+use crate::domain::hall::Hall;
+use crate::domain::movie::Movie;
+use crate::domain::reservation::minutes_of_day;
+use crate::domain::screening::Screening;
+use crate::repo::traits::Repository;
+
+/// CRUD for screenings. Only movies that are in program can be scheduled,
+/// only in halls that exist, and a hall can't hold two screenings at once:
+/// the next one starts at the earliest after the previous movie ended and
+/// the hall was cleaned.
+pub struct ScreeningService<'a> {
+    screenings: &'a mut dyn Repository<Screening>,
+    movies: &'a dyn Repository<Movie>,
+    halls: &'a dyn Repository<Hall>,
+    schedule: ScheduleConfig,
+}
+
+impl<'a> ScreeningService<'a> {
+    pub fn new(
+        screenings: &'a mut dyn Repository<Screening>,
+        movies: &'a dyn Repository<Movie>,
+        halls: &'a dyn Repository<Hall>,
+    ) -> Self {
+        ScreeningService {
+            screenings,
+            movies,
+            halls,
+            schedule: ScheduleConfig::default(),
+        }
+    }
+
+    pub fn set_schedule(&mut self, schedule: ScheduleConfig) {
+        self.schedule = schedule;
+    }
+
+    pub fn get_all(&self) -> &Vec<Screening> {
+        self.screenings.get_all()
+    }
+
+    pub fn get(&self, index: usize) -> Result<&Screening, String> {
+        self.screenings.get_elem(index)
+    }
+
+    pub fn add(&mut self, new_screening: Screening) -> Result<(), String> {
+        if self
+            .screenings
+            .get_all()
+            .iter()
+            .any(|s| s.id() == new_screening.id())
+        {
+            return Err("A screening with this ID already exists.".into());
+        }
+
+        self.check_movie(&new_screening)?;
+        self.check_hall(&new_screening)?;
+        self.check_overlap(&new_screening, None)?;
+        self.screenings.add_elem(new_screening)
+    }
+
+    pub fn update(&mut self, index: usize, new_screening: Screening) -> Result<(), String> {
+        if index >= self.screenings.get_all().len() {
+            return Err("There is no screening with that ID".into());
+        }
+
+        for (i, screening) in self.screenings.get_all().iter().enumerate() {
+            if i != index && screening.id() == new_screening.id() {
+                return Err("A different screening with that ID already exists".into());
+            }
+        }
+
+        self.check_movie(&new_screening)?;
+        self.check_hall(&new_screening)?;
+        self.check_overlap(&new_screening, Some(index))?;
+        self.screenings.update_elem(index, new_screening)
+    }
+
+    pub fn remove(&mut self, index: usize) -> Result<(), String> {
+        self.screenings.remove_elem(index)
+    }
+
+    /// Adds a whole plan, e.g. from `ProgramPlanner`, or none of it if one
+    /// of its screenings is refused. Returns how many were added.
+    pub fn add_plan(&mut self, plan: Vec<Screening>) -> Result<usize, String> {
+        let mut added: Vec<u32> = vec![];
+
+        for screening in plan {
+            let id = screening.id();
+            if let Err(err) = self.add(screening) {
+                for added_id in added.iter().rev() {
+                    if let Some(index) = self
+                        .screenings
+                        .get_all()
+                        .iter()
+                        .position(|s| s.id() == *added_id)
+                    {
+                        self.screenings.remove_elem(index)?;
+                    }
+                }
+
+                return Err(format!("Screening {}: {}", id, err));
+            }
+            added.push(id);
+        }
+
+        Ok(added.len())
+    }
+
+    /// Every pair of screenings that overlap in the same hall, cleaning time
+    /// included, e.g. in a schedule written before these checks existed.
+    pub fn conflicts(&self) -> Result<Vec<(Screening, Screening)>, String> {
+        let screenings = self.screenings.get_all();
+
+        let mut conflicts = vec![];
+        for (i, first) in screenings.iter().enumerate() {
+            for second in &screenings[i + 1..] {
+                if self.overlap(first, second)? {
+                    conflicts.push((first.clone(), second.clone()));
+                }
+            }
+        }
+
+        Ok(conflicts)
+    }
+
+    // `index` is the screening being replaced, if any.
+    fn check_overlap(&self, screening: &Screening, index: Option<usize>) -> Result<(), String> {
+        for (i, other) in self.screenings.get_all().iter().enumerate() {
+            if Some(i) != index && self.overlap(screening, other)? {
+                return Err(format!(
+                    "The hall is taken by screening {} on {} at {}.",
+                    other.id(),
+                    other.date(),
+                    other.start()
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn overlap(&self, first: &Screening, second: &Screening) -> Result<bool, String> {
+        Ok(screenings_overlap(
+            first,
+            self.runtime_of(first)?,
+            second,
+            self.runtime_of(second)?,
+            self.schedule.cleaning_minutes,
+        ))
+    }
+
+    fn runtime_of(&self, screening: &Screening) -> Result<u32, String> {
+        match self
+            .movies
+            .get_all()
+            .iter()
+            .find(|m| m.id() == screening.movie_id())
+        {
+            Some(movie) => Ok(movie.runtime()),
+            None => Err(format!(
+                "Screening {} is of a movie that no longer exists.",
+                screening.id()
+            )),
+        }
+    }
+
+    fn check_movie(&self, screening: &Screening) -> Result<(), String> {
+        match self
+            .movies
+            .get_all()
+            .iter()
+            .find(|m| m.id() == screening.movie_id())
+        {
+            None => Err("There is no movie with that ID.".into()),
+            Some(movie) if !movie.in_program() => Err("The movie is no longer in program.".into()),
+            Some(_) => Ok(()),
+        }
+    }
+
+    // The hall must exist and be open for the whole movie. A screening after
+    // midnight may belong to the previous day's opening hours.
+    fn check_hall(&self, screening: &Screening) -> Result<(), String> {
+        let hall = match self
+            .halls
+            .get_all()
+            .iter()
+            .find(|h| h.id() == screening.hall_id())
+        {
+            Some(hall) => hall,
+            None => return Err("There is no hall with that ID.".into()),
+        };
+
+        let runtime = match self
+            .movies
+            .get_all()
+            .iter()
+            .find(|m| m.id() == screening.movie_id())
+        {
+            Some(movie) => movie.runtime(),
+            None => return Err("There is no movie with that ID.".into()),
+        };
+
+        let (opens, closes) = hall.open_minutes();
+        let start = minutes_of_day(screening.start());
+        if [start, start + 24 * 60]
+            .iter()
+            .any(|&start| start >= opens && start + runtime <= closes)
+        {
+            Ok(())
+        } else {
+            Err(format!(
+                "The screening must fit in the opening hours of {}, {} to {}.",
+                hall.name(),
+                hall.opens(),
+                hall.closes()
+            ))
+        }
+    }
+}
+
+/// Whether two screenings keep the same hall busy at the same time, given how
+/// long their movies run and how long the hall takes to clean after each.
+pub fn screenings_overlap(
+    first: &Screening,
+    first_runtime: u32,
+    second: &Screening,
+    second_runtime: u32,
+    cleaning_minutes: u32,
+) -> bool {
+    // the minute the hall is ready for the next screening
+    let free_from = |screening: &Screening, runtime: u32| {
+        screening.start_minute() + (runtime + cleaning_minutes) as i64
+    };
+
+    first.hall_id() == second.hall_id()
+        && first.start_minute() < free_from(second, second_runtime)
+        && second.start_minute() < free_from(first, first_runtime)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::clock::FixedClock;
+    use crate::domain::date::Date;
+    use crate::domain::money::{Currency, Money};
+    use crate::repo::repo_file::RepoFile;
+    use crate::test_util::TempDir;
+    use crate::validators::context;
+    use std::sync::Arc;
+
+    struct Data {
+        screenings: RepoFile<Screening>,
+        movies: RepoFile<Movie>,
+        halls: RepoFile<Hall>,
+        _dir: TempDir,
+    }
+
+    impl Data {
+        // A movie of 155 minutes and two halls, open from 10:00 to midnight,
+        // with the clock at 1.6.2026.
+        fn new(name: &str) -> Data {
+            context::set_clock(Arc::new(FixedClock::new(Date::new(1, 6, 2026).unwrap())));
+            let dir = TempDir::new(&format!("screenings-{}", name));
+
+            let mut data = Data {
+                screenings: RepoFile::new(&dir.path("screenings.csv")),
+                movies: RepoFile::new(&dir.path("movies.csv")),
+                halls: RepoFile::new(&dir.path("halls.csv")),
+                _dir: dir,
+            };
+
+            let price = Money::new(2500, Currency::Ron);
+            data.movies
+                .add_elem(Movie::new(1, "Dune", 2021, price, true, 155).unwrap())
+                .unwrap();
+            for (id, name) in [(1, "Mare"), (2, "Mica")] {
+                data.halls
+                    .add_elem(Hall::new(id, name, 10, vec!["SSSSSSSSSS".into()]).unwrap())
+                    .unwrap();
+            }
+
+            data
+        }
+
+        fn service(&mut self) -> ScreeningService<'_> {
+            ScreeningService::new(&mut self.screenings, &self.movies, &self.halls)
+        }
+    }
+
+    // A screening of the movie on 10.6.2026.
+    fn screening(id: u32, hall_id: u32, start: &str) -> Screening {
+        Screening::new(
+            id,
+            1,
+            hall_id,
+            Date::new(10, 6, 2026).unwrap(),
+            start.into(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn a_hall_holds_one_screening_at_a_time_cleaning_included() {
+        let mut data = Data::new("overlap");
+        let mut service = data.service();
+
+        // 18:00 + 155 minutes + 15 of cleaning: free from 20:50
+        service.add(screening(1, 1, "18:00")).unwrap();
+        assert_eq!(
+            service.add(screening(2, 1, "20:45")).unwrap_err(),
+            "The hall is taken by screening 1 on 10.6.2026 at 18:00."
+        );
+        assert_eq!(
+            service.add(screening(2, 1, "16:00")).unwrap_err(),
+            "The hall is taken by screening 1 on 10.6.2026 at 18:00."
+        );
+        service.add(screening(2, 1, "20:50")).unwrap();
+        service.add(screening(3, 2, "19:00")).unwrap();
+
+        // a screening doesn't get in its own way when it moves
+        service.update(0, screening(1, 1, "17:30")).unwrap();
+        assert_eq!(
+            service.update(2, screening(3, 1, "20:00")).unwrap_err(),
+            "The hall is taken by screening 1 on 10.6.2026 at 17:30."
+        );
+    }
+
+    #[test]
+    fn a_screening_fits_in_the_opening_hours() {
+        let mut data = Data::new("opening-hours");
+        let mut late = data.halls.get_elem(1).unwrap().clone();
+        late.set_opening_hours("16:00", "04:00").unwrap();
+        data.halls.update_elem(1, late).unwrap();
+        let mut service = data.service();
+
+        assert_eq!(
+            service.add(screening(1, 1, "09:30")).unwrap_err(),
+            "The screening must fit in the opening hours of Mare, 10:00 to 00:00."
+        );
+        assert_eq!(
+            service.add(screening(1, 1, "21:30")).unwrap_err(),
+            "The screening must fit in the opening hours of Mare, 10:00 to 00:00."
+        );
+        service.add(screening(1, 1, "21:25")).unwrap();
+
+        // after midnight, in the previous day's opening hours
+        service.add(screening(2, 2, "23:00")).unwrap();
+        service.add(screening(3, 2, "00:30")).unwrap();
+        assert_eq!(
+            service.add(screening(4, 2, "02:00")).unwrap_err(),
+            "The screening must fit in the opening hours of Mica, 16:00 to 04:00."
+        );
+        assert_eq!(
+            service.add(screening(4, 3, "18:00")).unwrap_err(),
+            "There is no hall with that ID."
+        );
+    }
+
+    #[test]
+    fn the_conflict_report_finds_screenings_added_before_the_checks() {
+        let mut data = Data::new("conflicts");
+        for added in [
+            screening(1, 1, "18:00"),
+            screening(2, 1, "20:00"),
+            screening(3, 2, "20:00"),
+            screening(4, 1, "22:00"),
+        ] {
+            data.screenings.add_elem(added).unwrap();
+        }
+
+        let conflicts: Vec<(u32, u32)> = data
+            .service()
+            .conflicts()
+            .unwrap()
+            .iter()
+            .map(|(first, second)| (first.id(), second.id()))
+            .collect();
+        assert_eq!(conflicts, vec![(1, 2), (2, 4)]);
+    }
+}
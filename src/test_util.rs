@@ -0,0 +1,28 @@
+//! Helpers shared by the tests.
+
+use std::fs;
+use std::path::PathBuf;
+
+/// A directory of its own for one test, removed with everything in it when
+/// it is dropped.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> TempDir {
+        let dir = std::env::temp_dir().join(format!("cinema-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        TempDir(dir)
+    }
+
+    /// The path of a file in the directory.
+    pub fn path(&self, filename: &str) -> String {
+        self.0.join(filename).to_string_lossy().into_owned()
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
@@ -0,0 +1,4 @@
+//! Text output for the console.
+
+pub mod schedule;
+pub mod seat_map;
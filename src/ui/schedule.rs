@@ -0,0 +1,41 @@
+use crate::domain::hall::Hall;
+use crate::domain::movie::Movie;
+use crate::domain::screening::Screening;
+
+/// Lists screenings by day and hall, one per line:
+///
+/// ```text
+/// 20.10.2026
+///   Sala 1      18:00  Dune (155 min)
+/// ```
+pub fn render_schedule(screenings: &[Screening], movies: &[Movie], halls: &[Hall]) -> String {
+    let mut sorted: Vec<&Screening> = screenings.iter().collect();
+    sorted.sort_by_key(|s| (s.date(), s.hall_id(), s.start_minute()));
+
+    let mut text = String::new();
+    let mut day = None;
+    for screening in sorted {
+        if day != Some(screening.date()) {
+            day = Some(screening.date());
+            text.push_str(&format!("{}\n", screening.date()));
+        }
+
+        let hall = match halls.iter().find(|h| h.id() == screening.hall_id()) {
+            Some(hall) => hall.name().to_string(),
+            None => format!("hall {}", screening.hall_id()),
+        };
+        let movie = match movies.iter().find(|m| m.id() == screening.movie_id()) {
+            Some(movie) => format!("{} ({} min)", movie.title(), movie.runtime()),
+            None => format!("movie {}", screening.movie_id()),
+        };
+
+        text.push_str(&format!(
+            "  {:<10}  {}  {}\n",
+            hall,
+            screening.start(),
+            movie
+        ));
+    }
+
+    text
+}
@@ -0,0 +1,60 @@
+use crate::domain::hall::{row_letter, seat_label, Hall, SeatKind};
+
+const FREE: char = 'o';
+const FREE_WHEELCHAIR: char = 'w';
+const FREE_VIP: char = 'v';
+const TAKEN: char = 'x';
+
+/// Draws a hall as seen from the back, screen at the top, one line per row:
+///
+/// ```text
+///         SCREEN
+///  A  o o o   o o o
+///  B  o x x   o o v
+/// ```
+pub fn render_seat_map(hall: &Hall, taken: &[String]) -> String {
+    let width = hall
+        .layout()
+        .iter()
+        .map(|row| row.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    let mut map = format!("{:^1$}", "SCREEN", 4 + width * 2)
+        .trim_end()
+        .to_string();
+    map.push('\n');
+    for (row, line) in hall.layout().iter().enumerate() {
+        map.push_str(&format!(" {:<2} ", row_letter(row)));
+
+        let mut number = 0;
+        for c in line.chars() {
+            let cell = match SeatKind::from_char(c) {
+                None => ' ',
+                Some(kind) => {
+                    number += 1;
+                    if taken.contains(&seat_label(row, number)) {
+                        TAKEN
+                    } else {
+                        match kind {
+                            SeatKind::Standard => FREE,
+                            SeatKind::Wheelchair => FREE_WHEELCHAIR,
+                            SeatKind::Vip => FREE_VIP,
+                        }
+                    }
+                }
+            };
+            map.push(cell);
+            map.push(' ');
+        }
+
+        map.truncate(map.trim_end().len());
+        map.push('\n');
+    }
+
+    map.push_str(&format!(
+        "{} free, {} wheelchair, {} VIP, {} taken\n",
+        FREE, FREE_WHEELCHAIR, FREE_VIP, TAKEN
+    ));
+    map
+}
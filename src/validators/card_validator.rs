@@ -1,12 +1,18 @@
 use crate::domain::card::Card;
+use crate::domain::clock::Clock;
+use crate::domain::date::Date;
 use crate::validators::common_validators::id_validator;
-use crate::validators::date_validators::date_validator;
+use crate::validators::context::limits;
+use crate::validators::date_validators::{date_validator, not_in_future_validator};
 
 fn name_validator(name: &str, name_type: &str) -> Result<(), String> {
-    if name.len() < 2 || name.len() > 16 {
+    let limits = limits();
+    let length = name.chars().count();
+
+    if length < limits.min_name_length || length > limits.max_name_length {
         Result::Err(format!(
-            "The {} needs to be between 1 and 17 characters.",
-            name_type,
+            "The {} needs to be between {} and {} characters.",
+            name_type, limits.min_name_length, limits.max_name_length
         ))
     } else if name.contains(' ') {
         Result::Err(format!("The {} can't contain spaces.", name_type))
@@ -33,14 +39,100 @@ pub fn cnp_validator(cnp: &str) -> Result<(), String> {
     }
 }
 
-pub fn card_validator(card: &Card) -> Result<(), String> {
+pub fn birthday_validator(birthday: &Date, clock: &dyn Clock) -> Result<(), String> {
+    date_validator(birthday, clock)?;
+    not_in_future_validator(birthday, "birthday", clock)
+}
+
+// The birth date written in a CNP: S YY MM DD, where S also gives the century
+// for people born in Romania. For residents and foreigners (7, 8, 9) the
+// century is unknown, so only the year within the century is returned.
+fn cnp_birth_date(cnp: &str) -> Option<(Option<u32>, u32, u8, u8)> {
+    if cnp.len() != 13 || !cnp.is_ascii() {
+        return None;
+    }
+
+    let digit = |i: usize| cnp[i..i + 1].parse::<u32>().ok();
+    let number = |i: usize| cnp[i..i + 2].parse::<u32>().ok();
+
+    let century = match digit(0)? {
+        1 | 2 => Some(1900),
+        3 | 4 => Some(1800),
+        5 | 6 => Some(2000),
+        7..=9 => None,
+        _ => return None,
+    };
+
+    Some((century, number(1)?, number(3)? as u8, number(5)? as u8))
+}
+
+pub fn cnp_birthday_validator(cnp: &str, birthday: &Date) -> Result<(), String> {
+    let matches = match cnp_birth_date(cnp) {
+        None => false,
+        Some((century, year, month, day)) => {
+            let year_matches = match century {
+                Some(century) => century + year == birthday.year(),
+                None => year == birthday.year() % 100,
+            };
+
+            year_matches && month == birthday.month() && day == birthday.day()
+        }
+    };
+
+    if matches {
+        Result::Ok(())
+    } else {
+        Result::Err("The CNP does not match the birthday.".into())
+    }
+}
+
+pub fn registration_date_validator(card: &Card, clock: &dyn Clock) -> Result<(), String> {
+    let min_age = limits().min_card_holder_age;
+
+    if card.registration_date() < card.birthday() {
+        Result::Err("The registration date can't be before the birthday.".into())
+    } else if card.birthday().years_until(card.registration_date()) < min_age {
+        Result::Err(format!(
+            "The card holder must be at least {} years old when registering.",
+            min_age
+        ))
+    } else {
+        not_in_future_validator(&card.registration_date(), "registration date", clock)
+    }
+}
+
+/// Rules that look at several fields at once. They run after the fields
+/// themselves are known to be valid, and on every change to a card.
+pub fn card_rules_validator(card: &Card, clock: &dyn Clock) -> Result<(), String> {
+    let errors = vec![
+        cnp_birthday_validator(card.cnp(), &card.birthday()),
+        registration_date_validator(card, clock),
+    ];
+
+    let mut msg = String::new();
+    for error in errors {
+        if let Result::Err(err) = error {
+            msg.push_str(&err);
+            msg.push(' ');
+        }
+    }
+
+    if msg.is_empty() {
+        Result::Ok(())
+    } else {
+        Result::Err(msg)
+    }
+}
+
+/// Each field on its own, without the rules spanning several of them.
+pub fn card_fields_validator(card: &Card, clock: &dyn Clock) -> Result<(), String> {
     let errors = vec![
         id_validator(card.id()),
         first_name_validator(card.first_name()),
         last_name_validator(card.last_name()),
         cnp_validator(card.cnp()),
-        date_validator(&card.birthday()),
-        date_validator(&card.registration_date()),
+        birthday_validator(&card.birthday(), clock),
+        date_validator(&card.registration_date(), clock),
     ];
 
     let mut msg = String::new();
@@ -57,3 +149,65 @@ pub fn card_validator(card: &Card) -> Result<(), String> {
         Result::Err(msg)
     }
 }
+
+pub fn card_validator(card: &Card, clock: &dyn Clock) -> Result<(), String> {
+    card_fields_validator(card, clock)?;
+    card_rules_validator(card, clock)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::clock::FixedClock;
+    use crate::validators::context;
+    use std::sync::Arc;
+
+    fn date(day: u8, month: u8, year: u32) -> Date {
+        Date::new(day, month, year).unwrap()
+    }
+
+    #[test]
+    fn cards_are_built_against_the_context_clock() {
+        context::set_clock(Arc::new(FixedClock::new(date(1, 6, 2026))));
+        let birthday = date(1, 5, 2012);
+
+        assert!(Card::new(
+            1,
+            "Ana",
+            "Pop",
+            "6120501123456",
+            birthday,
+            date(1, 6, 2026),
+            0
+        )
+        .is_ok());
+        assert!(Card::new(
+            1,
+            "Ana",
+            "Pop",
+            "6120501123456",
+            birthday,
+            date(2, 6, 2026),
+            0
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn the_registration_date_cant_be_after_the_clocks_day() {
+        context::set_clock(Arc::new(FixedClock::new(date(1, 6, 2026))));
+        let card = Card::new(
+            1,
+            "Ana",
+            "Pop",
+            "6120501123456",
+            date(1, 5, 2012),
+            date(1, 6, 2026),
+            0,
+        )
+        .unwrap();
+
+        assert!(registration_date_validator(&card, &FixedClock::new(date(1, 6, 2026))).is_ok());
+        assert!(registration_date_validator(&card, &FixedClock::new(date(31, 5, 2026))).is_err());
+    }
+}
@@ -1,4 +1,4 @@
-pub fn id_validator(id: u32) -> Result<(), String> {
+pub fn id_validator(_id: u32) -> Result<(), String> {
     // no validation, exists cause it may be needed in the future
     Result::Ok(())
 }
@@ -0,0 +1,78 @@
+use crate::domain::clock::{Clock, SystemClock};
+use serde::Deserialize;
+use std::cell::RefCell;
+use std::sync::Arc;
+
+/// Validation ranges that used to be hard-coded. Year limits are relative to
+/// the clock's current year, so they don't expire.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ValidationLimits {
+    /// earliest year accepted in any date
+    pub min_year: u32,
+    /// how many years after the current one a date can be in, so that
+    /// reservations can be made ahead of time
+    pub max_years_ahead: u32,
+    pub min_release_year: u32,
+    /// films announced for next year can be registered with the default of 1
+    pub release_years_ahead: u32,
+    /// age the card holder must have reached on the registration date
+    pub min_card_holder_age: u32,
+    pub min_name_length: usize,
+    pub max_name_length: usize,
+    pub min_title_length: usize,
+    pub max_title_length: usize,
+    /// highest ticket price, in whole units of the price's currency
+    pub max_price: u32,
+    /// longest running time of a movie, in minutes
+    pub max_runtime: u32,
+}
+
+impl Default for ValidationLimits {
+    fn default() -> Self {
+        ValidationLimits {
+            min_year: 1900,
+            max_years_ahead: 1,
+            min_release_year: 1926,
+            release_years_ahead: 1,
+            min_card_holder_age: 14,
+            min_name_length: 2,
+            max_name_length: 16,
+            min_title_length: 2,
+            max_title_length: 20,
+            max_price: 9_999,
+            max_runtime: 600,
+        }
+    }
+}
+
+struct ValidationContext {
+    clock: Arc<dyn Clock>,
+    limits: ValidationLimits,
+}
+
+// The validators are plain functions called from constructors and setters.
+// Those that depend on the date take a clock, and constructors pass this one.
+// Each thread has its own, so that tests can pin their own date side by side.
+thread_local! {
+    static CONTEXT: RefCell<ValidationContext> = RefCell::new(ValidationContext {
+        clock: Arc::new(SystemClock),
+        limits: ValidationLimits::default(),
+    });
+}
+
+pub fn set_clock(clock: Arc<dyn Clock>) {
+    CONTEXT.with(|context| context.borrow_mut().clock = clock);
+}
+
+pub fn set_limits(limits: ValidationLimits) {
+    CONTEXT.with(|context| context.borrow_mut().limits = limits);
+}
+
+pub fn clock() -> Arc<dyn Clock> {
+    CONTEXT.with(|context| context.borrow().clock.clone())
+}
+
+pub fn limits() -> ValidationLimits {
+    CONTEXT.with(|context| context.borrow().limits.clone())
+}
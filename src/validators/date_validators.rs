@@ -1,34 +1,56 @@
+use crate::domain::clock::Clock;
 use crate::domain::date::Date;
+use crate::validators::context::limits;
 
 pub fn day_validator(day: u8) -> Result<(), String> {
-    if day < 1 && day > 30 {
-        Result::Err("The date's day should be between 0 and 31.".into())
+    if !(1..=31).contains(&day) {
+        Result::Err("The date's day should be between 1 and 31.".into())
     } else {
         Result::Ok(())
     }
 }
 
 pub fn month_validator(month: u8) -> Result<(), String> {
-    if month < 1 && month > 12 {
-        Result::Err("The date's month should be between 0 and 13.".into())
+    if !(1..=12).contains(&month) {
+        Result::Err("The date's month should be between 1 and 12.".into())
     } else {
         Result::Ok(())
     }
 }
 
-pub fn year_validator(year: u32) -> Result<(), String> {
-    if year < 1925 && year > 2025 {
-        Result::Err("The date's month should be between 1924 and 2026.".into())
+pub fn year_validator(year: u32, clock: &dyn Clock) -> Result<(), String> {
+    let limits = limits();
+    let max_year = clock.today().year() + limits.max_years_ahead;
+
+    if year < limits.min_year || year > max_year {
+        Result::Err(format!(
+            "The date's year should be between {} and {}.",
+            limits.min_year, max_year
+        ))
     } else {
         Result::Ok(())
     }
 }
 
-pub fn date_validator(date: &Date) -> Result<(), String> {
+pub fn not_in_future_validator(date: &Date, name: &str, clock: &dyn Clock) -> Result<(), String> {
+    let today = clock.today();
+
+    if *date > today {
+        Result::Err(format!("The {} can't be after today ({}).", name, today))
+    } else {
+        Result::Ok(())
+    }
+}
+
+pub fn date_validator(date: &Date, clock: &dyn Clock) -> Result<(), String> {
     let errors = vec![
         day_validator(date.day()),
         month_validator(date.month()),
-        year_validator(date.year()),
+        year_validator(date.year(), clock),
+        match date.to_naive() {
+            Some(_) => Result::Ok(()),
+            None => Result::Err(format!("The date {} does not exist.", date)),
+        },
     ];
 
     let mut msg = String::new();
@@ -46,3 +68,32 @@ pub fn date_validator(date: &Date) -> Result<(), String> {
         Result::Err(msg)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::clock::FixedClock;
+
+    fn clock(day: u8, month: u8, year: u32) -> FixedClock {
+        FixedClock::new(Date::new(day, month, year).unwrap())
+    }
+
+    #[test]
+    fn the_last_year_accepted_follows_the_clock() {
+        let clock_2026 = clock(19, 10, 2026);
+        assert!(year_validator(2027, &clock_2026).is_ok());
+        assert!(year_validator(2028, &clock_2026).is_err());
+
+        assert!(year_validator(2028, &clock(1, 1, 2027)).is_ok());
+    }
+
+    #[test]
+    fn a_date_is_in_the_future_after_the_clocks_day() {
+        let clock = clock(19, 10, 2026);
+        let today = Date::new(19, 10, 2026).unwrap();
+        let tomorrow = Date::new(20, 10, 2026).unwrap();
+
+        assert!(not_in_future_validator(&today, "birthday", &clock).is_ok());
+        assert!(not_in_future_validator(&tomorrow, "birthday", &clock).is_err());
+    }
+}
@@ -0,0 +1,102 @@
+use super::common_validators::id_validator;
+use crate::domain::gift_card::{GiftCard, GiftCardEntry, GiftCardMovement};
+
+/// 6 to 24 capital letters, digits or dashes.
+pub fn gift_card_code_validator(code: &str) -> Result<(), String> {
+    let length = code.chars().count();
+
+    if !(6..=24).contains(&length) {
+        Result::Err("A gift card code must have 6 to 24 characters.".into())
+    } else if !code
+        .chars()
+        .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '-')
+    {
+        Result::Err(format!(
+            "Invalid gift card code: {}. Use letters, digits and dashes.",
+            code
+        ))
+    } else {
+        Result::Ok(())
+    }
+}
+
+pub fn gift_card_validator(gift_card: &GiftCard) -> Result<(), String> {
+    let value = gift_card.value();
+    let balance = gift_card.balance();
+
+    let errors = vec![
+        id_validator(gift_card.id()),
+        gift_card_code_validator(gift_card.code()),
+        if value.is_negative() || value.is_zero() {
+            Result::Err("A gift card must be worth more than nothing.".into())
+        } else {
+            Result::Ok(())
+        },
+        if balance.currency() != value.currency() {
+            Result::Err(format!(
+                "The balance of a gift card must be in {}.",
+                value.currency()
+            ))
+        } else if balance.is_negative() {
+            Result::Err("The balance of a gift card can't be negative.".into())
+        } else {
+            Result::Ok(())
+        },
+    ];
+
+    let mut msg = String::new();
+    for error in errors {
+        if let Result::Err(err) = error {
+            msg.push_str(&err);
+            msg.push(' ');
+        }
+    }
+
+    if msg.is_empty() {
+        Result::Ok(())
+    } else {
+        Result::Err(msg)
+    }
+}
+
+pub fn gift_card_entry_validator(entry: &GiftCardEntry) -> Result<(), String> {
+    let amount = entry.amount();
+
+    let errors = vec![
+        id_validator(entry.id()),
+        id_validator(entry.gift_card_id()),
+        // a refund onto a card that has expired gives back nothing
+        match entry.movement() {
+            _ if amount.is_negative() => {
+                Result::Err("A gift card ledger amount can't be negative.".into())
+            }
+            GiftCardMovement::Issue | GiftCardMovement::Payment if amount.is_zero() => {
+                Result::Err("A gift card ledger amount must be more than nothing.".into())
+            }
+            _ => Result::Ok(()),
+        },
+        match (entry.movement(), entry.reservation_id()) {
+            (GiftCardMovement::Issue, Some(_)) => {
+                Result::Err("Issuing a gift card has no reservation.".into())
+            }
+            (GiftCardMovement::Payment | GiftCardMovement::Refund, None) => {
+                Result::Err("A gift card payment or refund needs its reservation.".into())
+            }
+            _ => Result::Ok(()),
+        },
+    ];
+
+    let mut msg = String::new();
+    for error in errors {
+        if let Result::Err(err) = error {
+            msg.push_str(&err);
+            msg.push(' ');
+        }
+    }
+
+    if msg.is_empty() {
+        Result::Ok(())
+    } else {
+        Result::Err(msg)
+    }
+}
@@ -0,0 +1,77 @@
+use super::common_validators::id_validator;
+use super::reservation_validator::time_validator;
+use crate::domain::hall::{Hall, SeatKind, GAP, SEAT, VIP, WHEELCHAIR};
+
+// one letter per row
+const MAX_ROWS: usize = 26;
+
+pub fn hall_name_validator(name: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        Result::Err("The hall needs a name.".into())
+    } else if name.contains('"') {
+        Result::Err("The hall's name can't contain quotes.".into())
+    } else {
+        Result::Ok(())
+    }
+}
+
+pub fn layout_validator(layout: &[String]) -> Result<(), String> {
+    if layout.is_empty() || layout.iter().any(|row| row.is_empty()) {
+        Result::Err("The hall's layout can't have empty rows.".into())
+    } else if layout.len() > MAX_ROWS {
+        Result::Err(format!("The hall can have at most {} rows.", MAX_ROWS))
+    } else if layout.iter().any(|row| {
+        row.chars()
+            .any(|c| c != GAP && SeatKind::from_char(c).is_none())
+    }) {
+        Result::Err(format!(
+            "The hall's layout can only contain seats ('{}'), wheelchair places ('{}'), \
+             VIP seats ('{}') and gaps ('{}').",
+            SEAT, WHEELCHAIR, VIP, GAP
+        ))
+    } else {
+        Result::Ok(())
+    }
+}
+
+pub fn capacity_validator(hall: &Hall) -> Result<(), String> {
+    if hall.capacity() == 0 {
+        Result::Err("The hall's capacity must be at least 1.".into())
+    } else if hall.capacity() > hall.seat_count() {
+        Result::Err(format!(
+            "The hall's capacity can't be more than its {} seats.",
+            hall.seat_count()
+        ))
+    } else {
+        Result::Ok(())
+    }
+}
+
+pub fn opening_hours_validator(opens: &str, closes: &str) -> Result<(), String> {
+    time_validator(opens)?;
+    time_validator(closes)
+}
+
+pub fn hall_validator(hall: &Hall) -> Result<(), String> {
+    let errors = vec![
+        id_validator(hall.id()),
+        hall_name_validator(hall.name()),
+        layout_validator(hall.layout()),
+        capacity_validator(hall),
+        opening_hours_validator(hall.opens(), hall.closes()),
+    ];
+
+    let mut msg = String::new();
+    for error in errors {
+        if let Result::Err(err) = error {
+            msg.push_str(&err);
+            msg.push(' ');
+        }
+    }
+
+    if msg.is_empty() {
+        Result::Ok(())
+    } else {
+        Result::Err(msg)
+    }
+}
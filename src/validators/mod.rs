@@ -1,5 +1,13 @@
+//! The checks behind the domain types, and the clock and limits they use.
+
 pub mod card_validator;
 pub mod common_validators;
+pub mod context;
 pub mod date_validators;
+pub mod gift_card_validator;
+pub mod hall_validator;
 pub mod movie_validator;
+pub mod promotion_validator;
 pub mod reservation_validator;
+pub mod screening_validator;
+pub mod seat_hold_validator;
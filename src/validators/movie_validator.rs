@@ -1,36 +1,99 @@
+use crate::domain::clock::Clock;
+use crate::domain::money::{Money, MINOR_PER_MAJOR};
 use crate::domain::movie::Movie;
 use crate::validators::common_validators::id_validator;
+use crate::validators::context::limits;
 
 pub fn title_validator(title: &str) -> Result<(), String> {
-    if title.len() > 1 && title.len() < 21 {
+    let limits = limits();
+    let length = title.chars().count();
+
+    if length >= limits.min_title_length && length <= limits.max_title_length {
         Result::Ok(())
     } else {
-        Result::Err("The title needs to be between 1 and 21 characters.".into())
+        Result::Err(format!(
+            "The title needs to be between {} and {} characters.",
+            limits.min_title_length, limits.max_title_length
+        ))
     }
 }
 
-pub fn release_year_validator(release_year: u32) -> Result<(), String> {
-    if release_year > 1925 && release_year < 2025 {
+pub fn release_year_validator(release_year: u32, clock: &dyn Clock) -> Result<(), String> {
+    let limits = limits();
+    let max_release_year = clock.today().year() + limits.release_years_ahead;
+
+    if release_year >= limits.min_release_year && release_year <= max_release_year {
         Result::Ok(())
     } else {
-        Result::Err("The release year needs to be between 1925 and 2025".into())
+        Result::Err(format!(
+            "The release year needs to be between {} and {}.",
+            limits.min_release_year, max_release_year
+        ))
     }
 }
 
-pub fn price_validator(price: u32) -> Result<(), String> {
-    if price < 10_000 {
+pub fn price_validator(price: Money) -> Result<(), String> {
+    let max_price = limits().max_price;
+
+    if price.is_negative() {
+        Result::Err("The price can't be negative.".into())
+    } else if price.minor() <= max_price as i64 * MINOR_PER_MAJOR {
+        Result::Ok(())
+    } else {
+        Result::Err(format!("The price can be at most {}.", max_price))
+    }
+}
+
+pub fn runtime_validator(runtime: u32) -> Result<(), String> {
+    let max_runtime = limits().max_runtime;
+
+    if runtime >= 1 && runtime <= max_runtime {
         Result::Ok(())
     } else {
-        Result::Err("The price must be lower than 10.000".into())
+        Result::Err(format!(
+            "The runtime needs to be between 1 and {} minutes.",
+            max_runtime
+        ))
+    }
+}
+
+pub fn genres_validator(genres: &[String]) -> Result<(), String> {
+    for (i, genre) in genres.iter().enumerate() {
+        if genre.trim().is_empty() || genre.chars().count() > 20 {
+            return Result::Err("Each genre needs to be between 1 and 20 characters.".into());
+        } else if genre.contains(';') || genre.contains('"') {
+            return Result::Err("A genre can't contain ';' or quotes.".into());
+        } else if genres[..i].iter().any(|g| g.eq_ignore_ascii_case(genre)) {
+            return Result::Err(format!("The genre {} is listed twice.", genre));
+        }
+    }
+
+    Result::Ok(())
+}
+
+/// Languages are ISO 639-1 codes, two lowercase letters such as "ro".
+pub fn language_validator(language: Option<&str>, name: &str) -> Result<(), String> {
+    match language {
+        Some(code) if code.len() != 2 || !code.chars().all(|c| c.is_ascii_lowercase()) => {
+            Result::Err(format!(
+                "The {} must be a two letter code such as \"ro\" or \"en\".",
+                name
+            ))
+        }
+        _ => Result::Ok(()),
     }
 }
 
-pub fn movie_validator(movie: &Movie) -> Result<(), String> {
+pub fn movie_validator(movie: &Movie, clock: &dyn Clock) -> Result<(), String> {
     let errors = vec![
         id_validator(movie.id()),
         title_validator(movie.title()),
-        release_year_validator(movie.release_year()),
+        release_year_validator(movie.release_year(), clock),
         price_validator(movie.price()),
+        runtime_validator(movie.runtime()),
+        genres_validator(movie.genres()),
+        language_validator(movie.language(), "language"),
+        language_validator(movie.subtitles(), "subtitle language"),
     ];
 
     let mut msg = String::new();
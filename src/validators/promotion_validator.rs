@@ -0,0 +1,75 @@
+use super::common_validators::id_validator;
+use crate::domain::promotion::{PromoBenefit, Promotion};
+
+/// 3 to 20 capital letters, digits or dashes.
+pub fn promo_code_validator(code: &str) -> Result<(), String> {
+    let length = code.chars().count();
+
+    if !(3..=20).contains(&length) {
+        Result::Err("A promo code must have 3 to 20 characters.".into())
+    } else if !code
+        .chars()
+        .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '-')
+    {
+        Result::Err(format!(
+            "Invalid promo code: {}. Use letters, digits and dashes.",
+            code
+        ))
+    } else {
+        Result::Ok(())
+    }
+}
+
+/// Valid codes, each at most once.
+pub fn promo_codes_validator(codes: &[String]) -> Result<(), String> {
+    for (i, code) in codes.iter().enumerate() {
+        promo_code_validator(code)?;
+        if codes[..i].contains(code) {
+            return Result::Err(format!("The promo code {} is given twice.", code));
+        }
+    }
+
+    Result::Ok(())
+}
+
+pub fn promo_benefit_validator(benefit: PromoBenefit) -> Result<(), String> {
+    match benefit {
+        PromoBenefit::Percent(percent) if percent == 0 || percent > 100 => {
+            Result::Err("A promo code takes off 1 to 100 percent.".into())
+        }
+        PromoBenefit::Amount(amount) if amount.is_negative() || amount.is_zero() => {
+            Result::Err("A promo code takes off more than nothing.".into())
+        }
+        PromoBenefit::FreeTicket(every) if every < 2 => {
+            Result::Err("At most every second ticket can be free.".into())
+        }
+        _ => Result::Ok(()),
+    }
+}
+
+pub fn promotion_validator(promotion: &Promotion) -> Result<(), String> {
+    let errors = vec![
+        id_validator(promotion.id()),
+        promo_code_validator(promotion.code()),
+        promo_benefit_validator(promotion.benefit()),
+        if promotion.valid_from() <= promotion.valid_until() {
+            Result::Ok(())
+        } else {
+            Result::Err("The promo code must start being valid before it stops.".into())
+        },
+    ];
+
+    let mut msg = String::new();
+    for error in errors {
+        if let Result::Err(err) = error {
+            msg.push_str(&err);
+            msg.push(' ');
+        }
+    }
+
+    if msg.is_empty() {
+        Result::Ok(())
+    } else {
+        Result::Err(msg)
+    }
+}
@@ -1,21 +1,22 @@
 use super::common_validators::id_validator;
 use super::date_validators::date_validator;
-use crate::domain::reservation::Reservation;
+use crate::domain::clock::Clock;
+use crate::domain::reservation::{PriceSnapshot, Reservation};
 
 pub fn time_validator(time: &str) -> Result<(), String> {
-    if time.len() != 5 {
+    if time.len() != 5 || !time.is_ascii() {
         Result::Err("The time must have 5 characters. (hh:mm)".into())
     } else if match time[0..2].parse::<i32>() {
-        Ok(x) => (0..=23).contains(&x),
+        Ok(x) => !(0..=23).contains(&x),
         Err(err) => return Result::Err(err.to_string()),
     } {
         Result::Err("The hour must be from 0 to 23.".into())
     } else if match time[3..5].parse::<i32>() {
-        Ok(x) => (0..=59).contains(&x),
+        Ok(x) => !(0..=59).contains(&x),
         Err(err) => return Result::Err(err.to_string()),
     } {
         Result::Err("The minutes must be from 0 to 59.".into())
-    } else if ['.', ':', '-'].contains(&time.chars().nth(2).unwrap_or('x')) {
+    } else if !['.', ':', '-'].contains(&time.chars().nth(2).unwrap_or('x')) {
         Result::Err("The time separators must be of the following kind: '.', ':', '-'".into())
     } else {
         Result::Ok(())
@@ -29,12 +30,71 @@ pub fn card_id_validator(id: Option<u32>) -> Result<(), String> {
     }
 }
 
-pub fn reservation_validator(reservation: &Reservation) -> Result<(), String> {
+/// Seats are a row letter followed by the seat's number, each at most once.
+pub fn seats_validator(seats: &[String]) -> Result<(), String> {
+    for (i, seat) in seats.iter().enumerate() {
+        let mut chars = seat.chars();
+        let row_ok = matches!(chars.next(), Some('A'..='Z'));
+        let number = chars.as_str();
+
+        if !row_ok || number.is_empty() || !number.chars().all(|c| c.is_ascii_digit()) {
+            return Result::Err(format!("Invalid seat: {}", seat));
+        } else if seats[..i].contains(seat) {
+            return Result::Err(format!("The seat {} is listed twice.", seat));
+        }
+    }
+
+    Result::Ok(())
+}
+
+/// Neither the price nor the discount can be negative, and the discount can't
+/// be more than `places` places cost. The parts paid with points and gift
+/// cards can't be negative either, nor more than what is left to pay. A price
+/// with promo discounts has one for each of the reservation's `codes` codes.
+pub fn price_snapshot_validator(
+    price: &PriceSnapshot,
+    places: u32,
+    codes: usize,
+    redeemed_points: u32,
+) -> Result<(), String> {
+    let total = price.total(places)?;
+    let promotions = price.promotions().len();
+    let redeemed = price.redeemed();
+    let gift_card = price.gift_card();
+
+    if price.unit_price().is_negative() {
+        Result::Err("The price can't be negative.".into())
+    } else if price.discount().is_negative() {
+        Result::Err("The discount can't be negative.".into())
+    } else if total.is_negative() {
+        Result::Err("The discount can't be more than the price.".into())
+    } else if redeemed.is_negative() {
+        Result::Err("The part paid with points can't be negative.".into())
+    } else if total.checked_sub(redeemed)?.is_negative() {
+        Result::Err("The points can't pay for more than the price.".into())
+    } else if gift_card.is_negative() {
+        Result::Err("The part paid with gift cards can't be negative.".into())
+    } else if price.due(places)?.is_negative() {
+        Result::Err("The gift cards can't pay for more than the price.".into())
+    } else if redeemed_points == 0 && !redeemed.is_zero() {
+        Result::Err("Part of the price was paid with points, but no points were used.".into())
+    } else if promotions != 0 && promotions != codes {
+        Result::Err(format!(
+            "The price has {} promo discounts for {} promo codes.",
+            promotions, codes
+        ))
+    } else {
+        Result::Ok(())
+    }
+}
+
+pub fn reservation_validator(reservation: &Reservation, clock: &dyn Clock) -> Result<(), String> {
     let errors = vec![
         id_validator(reservation.id()),
+        id_validator(reservation.screening_id()),
         id_validator(reservation.movie_id()),
         card_id_validator(reservation.card_id()),
-        date_validator(&reservation.date()),
+        date_validator(&reservation.date(), clock),
         time_validator(reservation.hour()),
     ];
 
@@ -0,0 +1,29 @@
+use super::common_validators::id_validator;
+use super::date_validators::date_validator;
+use super::reservation_validator::time_validator;
+use crate::domain::clock::Clock;
+use crate::domain::screening::Screening;
+
+pub fn screening_validator(screening: &Screening, clock: &dyn Clock) -> Result<(), String> {
+    let errors = vec![
+        id_validator(screening.id()),
+        id_validator(screening.movie_id()),
+        id_validator(screening.hall_id()),
+        date_validator(&screening.date(), clock),
+        time_validator(screening.start()),
+    ];
+
+    let mut msg = String::new();
+    for error in errors {
+        if let Result::Err(err) = error {
+            msg.push_str(&err);
+            msg.push(' ');
+        }
+    }
+
+    if msg.is_empty() {
+        Result::Ok(())
+    } else {
+        Result::Err(msg)
+    }
+}
@@ -0,0 +1,29 @@
+use super::common_validators::id_validator;
+use super::reservation_validator::seats_validator;
+use crate::domain::seat_hold::SeatHold;
+
+pub fn seat_hold_validator(hold: &SeatHold) -> Result<(), String> {
+    if hold.seats().is_empty() {
+        return Result::Err("A hold needs at least one seat.".into());
+    }
+
+    let errors = vec![
+        id_validator(hold.id()),
+        id_validator(hold.screening_id()),
+        seats_validator(hold.seats()),
+    ];
+
+    let mut msg = String::new();
+    for error in errors {
+        if let Result::Err(err) = error {
+            msg.push_str(&err);
+            msg.push(' ');
+        }
+    }
+
+    if msg.is_empty() {
+        Result::Ok(())
+    } else {
+        Result::Err(msg)
+    }
+}